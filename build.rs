@@ -0,0 +1,191 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+
+/**
+ * Generates the registry of attributes (see `src/attributes.rs`) from their declarations in
+ * `src/lib.rs`: every `impl_attribute!` in a module, and every attribute in a module implemented by
+ * hand (a struct with an `impl Attribute for`), together with the comment preceding it.
+ *
+ * The registry is written to `$OUT_DIR/attributes.rs` as the body of a function returning a vector
+ * of `AttributeInfo`s. Each entry tries to create a dynamic getter for its attribute with
+ * `dynamic!`, which yields `None` for attributes whose values cannot be represented as `Value`s.
+ */
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/lib.rs");
+
+    let source = fs::read_to_string("src/lib.rs").expect("cannot read src/lib.rs");
+    let attributes = parse(&source);
+
+    let mut output = String::new();
+    output.push_str("// Generated by build.rs from the declarations of attributes in src/lib.rs.\n");
+    output.push_str("vec![\n");
+    for attribute in attributes {
+        output.push_str(&attribute.to_rust());
+    }
+    output.push_str("]\n");
+
+    let destination = Path::new(&env::var("OUT_DIR").unwrap()).join("attributes.rs");
+    fs::write(destination, output).expect("cannot write the attribute registry");
+}
+
+struct Declaration {
+    module: String,
+    name: String,
+    parameters: Vec<String>,
+    return_type: String,
+    optional: bool,
+    collection: bool,
+    doc: Option<String>,
+}
+
+impl Declaration {
+    fn to_rust(&self) -> String {
+        format!("    AttributeInfo {{ entity: {:?}, name: {:?}, parameters: vec![{}], return_type: {:?}, \
+                 optional: {}, collection: {}, doc: {}, dynamic: dynamic!(crate::{}::{}, {:?}) }},\n",
+                self.module, self.name,
+                self.parameters.iter().map(|parameter| format!("{:?}", parameter)).collect::<Vec<_>>().join(", "),
+                self.return_type, self.optional, self.collection,
+                self.doc.as_ref().map_or("None".to_owned(), |doc| format!("Some({:?})", doc)),
+                self.module, self.name, self.name)
+    }
+}
+
+fn parse(source: &str) -> Vec<Declaration> {
+    let module_start = Regex::new(r"^pub mod (\w+) \{").unwrap();
+    let declaration = Regex::new(
+        r"^\s*impl_attribute!\[\s*([!?]\+?(?:\.\.)?)\s+(objects::\w+)\s*,\s*(\w+)\s*(?:\(([^)]*)\))?\s*(?:,(.*))?\];"
+    ).unwrap();
+    let manual = Regex::new(r"^\s*pub struct (\w+)\s*(?:\(([^;]*)\))?\s*;").unwrap();
+    let implementation = Regex::new(r"^\s*impl(?:<'a>)? (Getter|OptionGetter|OptionCountable)(?:<'a>)? for (\w+)").unwrap();
+    let into_item = Regex::new(r"^\s*type IntoItem = (.*);").unwrap();
+
+    let mut attributes: Vec<Declaration> = Vec::new();
+    let mut module: Option<&str> = None;
+    let mut comment: Option<Vec<&str>> = None;
+    let mut doc: Option<String> = None;
+    // The trait implemented by hand whose `IntoItem` is expected on the next line.
+    let mut implemented: Option<(String, usize)> = None;
+
+    for line in source.lines() {
+        if let Some(lines) = comment.as_mut() {
+            lines.push(line);
+            if line.contains("*/") {
+                doc = clean_comment(&comment.take().unwrap());
+            }
+            continue
+        }
+        if let Some(captures) = module_start.captures(line) {
+            module = Some(captures.get(1).unwrap().as_str());
+            continue
+        }
+        if line.starts_with('}') {
+            module = None;
+            continue
+        }
+        let module = match module { Some(module) => module, None => continue };
+
+        if let Some((kind, index)) = implemented.take() {
+            if let Some(captures) = into_item.captures(line) {
+                let item = captures.get(1).unwrap().as_str().trim();
+                match kind.as_str() {
+                    "Getter" => attributes[index].optional = item.starts_with("Option<"),
+                    _ => attributes[index].return_type = item.to_owned(),
+                }
+                continue
+            }
+        }
+
+        let trimmed = line.trim();
+        if trimmed.starts_with("/*") {
+            if trimmed.contains("*/") {
+                doc = clean_comment(&[line]);
+            } else {
+                comment = Some(vec![line]);
+            }
+            continue
+        }
+        if trimmed.is_empty() || trimmed.starts_with("//") {
+            continue
+        }
+
+        if let Some(captures) = declaration.captures(line) {
+            let sigil = captures.get(1).unwrap().as_str();
+            // The declared type is followed by the getter and, for collections, the counter.
+            let rest = captures.get(5).map_or(Vec::new(), |rest| split_top_level(rest.as_str()));
+            let return_type = if rest.is_empty() { captures.get(2).unwrap().as_str() } else { rest[0] };
+            attributes.push(Declaration {
+                module: module.to_owned(),
+                name: captures.get(3).unwrap().as_str().to_owned(),
+                parameters: captures.get(4).map_or(Vec::new(), |parameters| split_top_level(parameters.as_str()))
+                    .into_iter().map(str::to_owned).collect(),
+                return_type: return_type.to_owned(),
+                optional: sigil.starts_with('?'),
+                collection: sigil.ends_with(".."),
+                doc: doc.take(),
+            });
+            continue
+        }
+        if let Some(captures) = manual.captures(line) {
+            attributes.push(Declaration {
+                module: module.to_owned(),
+                name: captures.get(1).unwrap().as_str().to_owned(),
+                parameters: captures.get(2).map_or(Vec::new(), |parameters| split_top_level(parameters.as_str()))
+                    .into_iter().map(|parameter| parameter.trim_start_matches("pub ").to_owned()).collect(),
+                return_type: String::new(),
+                optional: false,
+                collection: false,
+                doc: doc.take(),
+            });
+            continue
+        }
+        if let Some(captures) = implementation.captures(line) {
+            let name = captures.get(2).unwrap().as_str();
+            let index = attributes.iter().rposition(|attribute| attribute.module == module && attribute.name == name);
+            if let Some(index) = index {
+                match captures.get(1).unwrap().as_str() {
+                    "OptionCountable" => attributes[index].collection = true,
+                    kind => implemented = Some((kind.to_owned(), index)),
+                }
+            }
+        }
+        doc = None;
+    }
+
+    // Structs that turned out not to be attributes have no return type.
+    attributes.retain(|attribute| !attribute.return_type.is_empty());
+    attributes
+}
+
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, character) in text.char_indices() {
+        match character {
+            '(' | '<' => depth += 1,
+            ')' | '>' => depth -= 1,
+            ',' if depth == 0 => { parts.push(text[start..index].trim()); start = index + 1 }
+            _ => (),
+        }
+    }
+    parts.push(text[start..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+fn clean_comment(lines: &[&str]) -> Option<String> {
+    let lines: Vec<&str> = lines.iter().map(|line| {
+        let line = line.trim();
+        let line = line.strip_prefix("/*").unwrap_or(line);
+        let line = line.find("*/").map_or(line, |end| &line[..end]);
+        let line = line.trim();
+        line.strip_prefix('*').unwrap_or(line).trim()
+    }).collect();
+    let first = lines.iter().position(|line| !line.is_empty())?;
+    let last = lines.iter().rposition(|line| !line.is_empty())?;
+    Some(lines[first..=last].join("\n"))
+}
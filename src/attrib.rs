@@ -1,7 +1,11 @@
 use std::hash::Hash;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
 
-use crate::objects::ItemWithData;
+use itertools::Either;
+
+use crate::objects::{ItemWithData, Identifiable};
 
 pub trait Attribute {
     type Object;
@@ -9,6 +13,11 @@ pub trait Attribute {
 pub trait Getter<'a>: Attribute {
     type IntoItem;
     fn get(&self, object: &ItemWithData<'a, Self::Object>) -> Self::IntoItem;
+    // Called with all the objects before the attribute is used on any of them, for attributes whose
+    // values depend on the other objects in the iterator, like quantile buckets. Attributes wrapping
+    // other attributes forward both methods to them.
+    fn prepare(&self, _objects: &[ItemWithData<'a, Self::Object>]) {}
+    fn needs_preparation(&self) -> bool { false }
     fn _get_with_data(&self, object: &ItemWithData<'a, Self::Object>) -> ItemWithData<'a, Self::IntoItem> {
         ItemWithData::new(object.data, self.get(object))
     }
@@ -16,6 +25,9 @@ pub trait Getter<'a>: Attribute {
 pub trait OptionGetter<'a>: Attribute {
     type IntoItem;
     fn get_opt(&self, object: &ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem>;
+    // See `Getter::prepare`.
+    fn prepare(&self, _objects: &[ItemWithData<'a, Self::Object>]) {}
+    fn needs_preparation(&self) -> bool { false }
     fn _get_opt_with_data(&self, object: &ItemWithData<'a, Self::Object>) -> Option<ItemWithData<'a, Self::IntoItem>> {
         self.get_opt(object).map(|result| {
             ItemWithData::new(object.data, result)
@@ -40,6 +52,9 @@ pub trait Countable<'a>: Attribute { // TODO Option? // FIXME needed?
 
 pub trait OptionCountable<'a>: Attribute { // TODO Option? // FIXME needed?
     fn count(&self, object: &ItemWithData<'a, Self::Object>) -> Option<usize>;
+    // See `Getter::prepare`.
+    fn prepare(&self, _objects: &[ItemWithData<'a, Self::Object>]) {}
+    fn needs_preparation(&self) -> bool { false }
 }
 
 pub trait Group<'a, T, I: Hash + Eq>: Attribute<Object=T> + Getter<'a, IntoItem=I> { // XXX
@@ -53,18 +68,62 @@ pub trait Select<'a, T, I>: Attribute<Object=T> + Getter<'a, IntoItem=I> { // XX
 impl<'a, T, I, A> Select<'a, T, I> for A where A: Attribute<Object=T> + Getter<'a, IntoItem=I> {}
 
 pub mod sort {
+    use std::cmp::Ordering;
+
     #[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Debug)]
     pub enum Direction { Ascending, Descending }
+
+    // Where objects without a value for a sort key go, regardless of the direction.
+    #[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Debug)]
+    pub enum Nulls { First, Last }
+
+    pub fn compare<I: Ord>(a: &Option<I>, b: &Option<I>, direction: Direction, nulls: Nulls) -> Ordering {
+        match (a, b, nulls) {
+            (None, None, _) => Ordering::Equal,
+            (None, Some(_), Nulls::First) | (Some(_), None, Nulls::Last) => Ordering::Less,
+            (None, Some(_), Nulls::Last) | (Some(_), None, Nulls::First) => Ordering::Greater,
+            (Some(a), Some(b), _) => match direction {
+                Direction::Ascending => a.cmp(b),
+                Direction::Descending => b.cmp(a),
+            },
+        }
+    }
+
+    // A stable sort that computes the key of each object once.
+    pub fn sort_by_cached_keys<O, K, F, C>(vector: &mut Vec<O>, key: F, compare: C)
+        where F: Fn(&O) -> K, C: Fn(&K, &K) -> Ordering {
+        let mut keyed: Vec<(K, O)> = vector.drain(..).map(|object| (key(&object), object)).collect();
+        keyed.sort_by(|(a, _), (b, _)| compare(a, b));
+        vector.extend(keyed.into_iter().map(|(_, object)| object));
+    }
 }
 
 pub trait Sort<'a, T,I: Ord>: Attribute<Object=T> + Getter<'a, IntoItem=I> {
     fn sort(&self, direction: sort::Direction, vector: &mut Vec<ItemWithData<'a, T>>) {
-        vector.sort_by_key(|object| self.get(object));
-        if direction == sort::Direction::Descending {
-            vector.reverse()
+        self.prepare(vector);
+        // Not sorting ascending and reversing, so that equal objects keep their order.
+        match direction {
+            sort::Direction::Ascending => vector.sort_by_cached_key(|object| self.get(object)),
+            sort::Direction::Descending => vector.sort_by_cached_key(|object| Reverse(self.get(object))),
         }
     }
 }
+
+// Several sort keys, each with its own direction, compared one after another, eg. `Select!(...)`.
+// There is one direction per key, eg. `[Direction; 2]` for two keys.
+pub trait SortKeys<'a, T> {
+    type Keys;
+    type Directions: Copy;
+    fn sort_keys(&self, object: &ItemWithData<'a, T>) -> Self::Keys;
+    fn compare_keys(a: &Self::Keys, b: &Self::Keys, directions: &Self::Directions, nulls: sort::Nulls) -> Ordering;
+    // See `Getter::prepare`.
+    fn prepare(&self, _objects: &[ItemWithData<'a, T>]) {}
+    fn sort_by_keys(&self, directions: Self::Directions, nulls: sort::Nulls, vector: &mut Vec<ItemWithData<'a, T>>) {
+        self.prepare(vector);
+        sort::sort_by_cached_keys(vector, |object| self.sort_keys(object),
+                                  |a, b| Self::compare_keys(a, b, &directions, nulls));
+    }
+}
 impl<'a, A, I, T> Sort<'a, T, I> for A where A: Getter<'a, IntoItem=I> + Attribute<Object=T>, I: Ord {}
 
 pub trait Sampler<'a, T> {
@@ -76,9 +135,55 @@ pub trait Sampler<'a, T> {
     }
 }
 
+// Samples groups rather than objects, eg. by `AttributeGroupIterator::sample_key`.
+pub trait KeySampler {
+    fn sample_keys<K, V>(&self, groups: Vec<(K, V)>) -> Vec<(K, V)> where K: Ord;
+}
+
+// Reduces a group of objects into a single value, eg. by `AttributeGroupIterator::aggregate`.
+pub trait Aggregator<'a, T> {
+    type Output;
+    fn aggregate(&self, objects: &Vec<ItemWithData<'a, T>>) -> Self::Output;
+}
+
+// Objects that an attribute leads to, eg. by `AttributeIterator::expand`: either one or many.
+pub trait Expansion<'a, U> {
+    fn into_objects(self) -> Vec<ItemWithData<'a, U>>;
+}
+impl<'a, U> Expansion<'a, U> for ItemWithData<'a, U> {
+    fn into_objects(self) -> Vec<ItemWithData<'a, U>> { vec![self] }
+}
+impl<'a, U> Expansion<'a, U> for Vec<ItemWithData<'a, U>> {
+    fn into_objects(self) -> Vec<ItemWithData<'a, U>> { self }
+}
+
+// Keys by which `AttributeIterator::join` pairs objects of type T with objects of type U.
+pub trait JoinKey<'a, T, U> {
+    type Key: Hash + Eq + Clone;
+    fn left_keys(&self, object: &ItemWithData<'a, T>) -> Vec<Self::Key>;
+    fn right_keys(&self, object: &ItemWithData<'a, U>) -> Vec<Self::Key>;
+}
+
 pub trait Filter<'a> {
     type Item;
     fn accept(&self, item_with_data: &ItemWithData<'a, Self::Item>) -> bool;
+    // See `Getter::prepare`.
+    fn prepare(&self, _objects: &[ItemWithData<'a, Self::Item>]) {}
+    fn needs_preparation(&self) -> bool { false }
+}
+
+// The objects of an iterator, collected first if an attribute needs to see all of them before it
+// is used on any (see `Getter::prepare`), and passed along lazily otherwise.
+pub type PreparedIter<'a, I, T> = Either<I, std::vec::IntoIter<ItemWithData<'a, T>>>;
+pub(crate) fn prepared<'a, I, T, F>(iterator: I, needs_preparation: bool, prepare: F) -> PreparedIter<'a, I, T>
+    where I: Iterator<Item=ItemWithData<'a, T>>, F: FnOnce(&[ItemWithData<'a, T>]) {
+    if needs_preparation {
+        let vector: Vec<ItemWithData<'a, T>> = iterator.collect();
+        prepare(&vector);
+        Either::Right(vector.into_iter())
+    } else {
+        Either::Left(iterator)
+    }
 }
 
 pub struct AttributeFilterIter<I, A> {
@@ -107,6 +212,7 @@ impl<'a,I,A,K,T> Iterator for AttributeGroupFilterIter<I, A>
         let attribute = &self.attribute;
         let next_group = self.iterator.next();
         next_group.map(|(key, vector)| {
+            attribute.prepare(&vector);
             let filtered_vector: Vec<ItemWithData<T>> =
                 vector.into_iter().filter(|item_with_data| {
                     attribute.accept(item_with_data)
@@ -116,9 +222,12 @@ impl<'a,I,A,K,T> Iterator for AttributeGroupFilterIter<I, A>
     }
 }
 
-pub struct AttributeMapIter<I, A, Ta, Tb> {
+pub struct AttributeMapIter<I, A, Ta, Tb> where I: Iterator {
     pub(crate) iterator: I,
     pub(crate) attribute: A,
+    // The objects of the iterator, collected on the first call to `next` if the attribute needs
+    // to see all of them first (see `Getter::prepare`).
+    pub(crate) prepared: Option<std::vec::IntoIter<I::Item>>,
     pub(crate) function: PhantomData<(Ta, Tb)>
 }
 impl<'a, I, A, Ta, Tb> Iterator for AttributeMapIter<I, A, Ta, Tb>
@@ -126,7 +235,16 @@ impl<'a, I, A, Ta, Tb> Iterator for AttributeMapIter<I, A, Ta, Tb>
     type Item = Tb; //ItemWithData<'a, Tb>;
     fn next(&mut self) -> Option<Self::Item> {
         let attribute = &self.attribute;
-        self.iterator.next().map(|item_with_data| {
+        if self.prepared.is_none() && attribute.needs_preparation() {
+            let vector: Vec<ItemWithData<'a, Ta>> = self.iterator.by_ref().collect();
+            attribute.prepare(&vector);
+            self.prepared = Some(vector.into_iter());
+        }
+        let next = match self.prepared.as_mut() {
+            Some(prepared) => prepared.next(),
+            None => self.iterator.next(),
+        };
+        next.map(|item_with_data| {
             attribute.select(&item_with_data)
         })
     }
@@ -144,6 +262,7 @@ impl<'a, I, A, K, Ta, Tb> Iterator for AttributeGroupMapIter<I, A, Ta, Tb>
         let attribute = &self.attribute;
         let next_group = self.iterator.next();
         next_group.map(|(key, vector)| {
+            attribute.prepare(&vector);
             let mapped_vector: Vec<Tb> =
                 vector.into_iter().map(|item_with_data| {
                     //ItemWithData::new(item_with_data.data,
@@ -152,4 +271,204 @@ impl<'a, I, A, K, Ta, Tb> Iterator for AttributeGroupMapIter<I, A, Ta, Tb>
             (key, mapped_vector)
         })
     }
-}
\ No newline at end of file
+}
+
+pub struct AttributeGroupKeyFilterIter<I, F> {
+    pub(crate) iterator: I,
+    pub(crate) predicate: F
+}
+impl<'a, I, F, K, T> Iterator for AttributeGroupKeyFilterIter<I, F>
+    where I: Iterator<Item=(K, Vec<ItemWithData<'a, T>>)>, F: Fn(&K) -> bool {
+    type Item = (K, Vec<ItemWithData<'a, T>>);
+    fn next(&mut self) -> Option<Self::Item> {
+        let predicate = &self.predicate;
+        self.iterator.find(|(key, _)| predicate(key))
+    }
+}
+
+pub struct AttributeGroupAggregateIter<I, A> {
+    pub(crate) iterator: I,
+    pub(crate) aggregator: A
+}
+impl<'a, I, A, K, T> Iterator for AttributeGroupAggregateIter<I, A>
+    where I: Iterator<Item=(K, Vec<ItemWithData<'a, T>>)>, A: Aggregator<'a, T> {
+    type Item = (K, A::Output);
+    fn next(&mut self) -> Option<Self::Item> {
+        let aggregator = &self.aggregator;
+        self.iterator.next().map(|(key, vector)| {
+            (key, aggregator.aggregate(&vector))
+        })
+    }
+}
+
+pub struct AttributeExpandIter<'a, I, A, U> {
+    pub(crate) iterator: I,
+    pub(crate) attribute: A,
+    pub(crate) expanded: std::vec::IntoIter<ItemWithData<'a, U>>
+}
+impl<'a, I, A, T, U, E> Iterator for AttributeExpandIter<'a, I, A, U>
+    where I: Iterator<Item=ItemWithData<'a, T>>, A: Attribute<Object=T> + OptionGetter<'a, IntoItem=E>, E: Expansion<'a, U> {
+    type Item = ItemWithData<'a, U>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(object) = self.expanded.next() {
+                return Some(object)
+            }
+            let item_with_data = self.iterator.next()?;
+            self.expanded = self.attribute.get_opt(&item_with_data)
+                .map_or(Vec::new(), |expansion| expansion.into_objects())
+                .into_iter();
+        }
+    }
+}
+
+// What `AttributeIterator::distinct` and `distinct_by` tell objects apart by: their ids (`ById`), or
+// the value of an attribute.
+pub trait DistinctKey<O> {
+    type Key: Hash + Eq;
+    fn key(&self, object: &O) -> Self::Key;
+}
+pub struct ById;
+impl<'a, T> DistinctKey<ItemWithData<'a, T>> for ById where T: Identifiable {
+    type Key = T::Identity;
+    fn key(&self, object: &ItemWithData<'a, T>) -> Self::Key { object.item.id() }
+}
+impl<'a, A, T, K> DistinctKey<ItemWithData<'a, T>> for A where A: Attribute<Object=T> + Getter<'a, IntoItem=K>, K: Hash + Eq {
+    type Key = K;
+    fn key(&self, object: &ItemWithData<'a, T>) -> Self::Key { self.get(object) }
+}
+
+pub struct AttributeDistinctIter<I, D, K> {
+    pub(crate) iterator: I,
+    pub(crate) key: D,
+    pub(crate) seen: HashSet<K>
+}
+impl<I, D, K> Iterator for AttributeDistinctIter<I, D, K>
+    where I: Iterator, D: DistinctKey<I::Item, Key=K>, K: Hash + Eq {
+    type Item = I::Item;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, seen) = (&self.key, &mut self.seen);
+        self.iterator.find(|object| seen.insert(key.key(object)))
+    }
+}
+
+// The objects on the right side of `AttributeIterator::join`, each stored once, and the positions
+// of the objects with each key. Objects without keys are dropped.
+pub struct JoinIndex<K, U> {
+    objects: Vec<U>,
+    positions: HashMap<K, Vec<usize>>
+}
+impl<K, U> JoinIndex<K, U> where K: Hash + Eq {
+    pub fn new<I, F>(objects: I, keys: F) -> Self where I: Iterator<Item=U>, F: Fn(&U) -> Vec<K> {
+        let mut index = JoinIndex { objects: Vec::new(), positions: HashMap::new() };
+        for object in objects {
+            let keys = keys(&object);
+            if keys.is_empty() {
+                continue
+            }
+            let position = index.objects.len();
+            for key in keys {
+                let positions = index.positions.entry(key).or_insert_with(Vec::new);
+                if positions.last() != Some(&position) {
+                    positions.push(position)
+                }
+            }
+            index.objects.push(object);
+        }
+        index
+    }
+    // Positions of the objects sharing at least one of the keys, in the order they were indexed.
+    pub fn matches(&self, keys: &[K]) -> Vec<usize> {
+        let mut positions: Vec<usize> =
+            keys.iter().flat_map(|key| self.positions.get(key)).flatten().copied().collect();
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+    pub fn get(&self, position: usize) -> &U { &self.objects[position] }
+}
+
+pub struct AttributeJoinIter<'a, I, J, T, U> where J: JoinKey<'a, T, U> {
+    pub(crate) iterator: I,
+    pub(crate) key: J,
+    pub(crate) index: JoinIndex<J::Key, ItemWithData<'a, U>>,
+    pub(crate) current: Option<(ItemWithData<'a, T>, std::vec::IntoIter<usize>)>
+}
+impl<'a, I, J, T, U> Iterator for AttributeJoinIter<'a, I, J, T, U>
+    where I: Iterator<Item=ItemWithData<'a, T>>, J: JoinKey<'a, T, U>, T: Clone, U: Clone {
+    type Item = (ItemWithData<'a, T>, ItemWithData<'a, U>);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((object, matches)) = self.current.as_mut() {
+                if let Some(position) = matches.next() {
+                    return Some((object.clone(), self.index.get(position).clone()))
+                }
+            }
+            let object = self.iterator.next()?;
+            let matches = self.index.matches(&self.key.left_keys(&object));
+            self.current = Some((object, matches.into_iter()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use crate::attrib::*;
+
+    struct Parity;
+    impl DistinctKey<u32> for Parity {
+        type Key = bool;
+        fn key(&self, object: &u32) -> Self::Key { object % 2 == 0 }
+    }
+
+    #[test] fn test_distinct() {
+        let distinct = |objects: Vec<u32>| -> Vec<u32> {
+            AttributeDistinctIter { iterator: objects.into_iter(), key: Parity, seen: HashSet::new() }.collect()
+        };
+        assert_eq!(distinct(vec![3, 5, 4, 7, 6, 1]), vec![3, 4]);
+        assert_eq!(distinct(vec![2, 4]), vec![2]);
+        assert_eq!(distinct(vec![]), Vec::<u32>::new());
+    }
+
+    #[test] fn test_compare() {
+        use std::cmp::Ordering::*;
+        use crate::attrib::sort::{compare, Direction::*, Nulls};
+        assert_eq!(compare(&Some(1), &Some(2), Ascending, Nulls::Last), Less);
+        assert_eq!(compare(&Some(1), &Some(2), Descending, Nulls::Last), Greater);
+        assert_eq!(compare(&Some(2), &Some(2), Descending, Nulls::First), Equal);
+        assert_eq!(compare::<u32>(&None, &None, Ascending, Nulls::First), Equal);
+        // Nulls go first or last whatever the direction.
+        for direction in vec![Ascending, Descending] {
+            assert_eq!(compare(&None, &Some(1), direction, Nulls::First), Less);
+            assert_eq!(compare(&Some(1), &None, direction, Nulls::First), Greater);
+            assert_eq!(compare(&None, &Some(1), direction, Nulls::Last), Greater);
+            assert_eq!(compare(&Some(1), &None, direction, Nulls::Last), Less);
+        }
+    }
+
+    #[test] fn test_sort_by_cached_keys() {
+        // Objects with equal keys keep their order, in either direction.
+        let mut objects = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (3, 'e')];
+        sort::sort_by_cached_keys(&mut objects, |(key, _)| *key, |a, b| a.cmp(b));
+        assert_eq!(objects, vec![(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c'), (3, 'e')]);
+        sort::sort_by_cached_keys(&mut objects, |(key, _)| *key, |a, b| b.cmp(a));
+        assert_eq!(objects, vec![(3, 'e'), (2, 'a'), (2, 'c'), (1, 'b'), (1, 'd')]);
+        sort::sort_by_cached_keys(&mut objects, |_| (), |a, b| a.cmp(b));
+        assert_eq!(objects, vec![(3, 'e'), (2, 'a'), (2, 'c'), (1, 'b'), (1, 'd')]);
+    }
+
+    #[test] fn test_join_index() {
+        // Objects with their keys: "a" has none, "b" repeats one.
+        let objects = vec![("a", vec![]), ("b", vec![1, 1, 2]), ("c", vec![2]), ("d", vec![3])];
+        let index = JoinIndex::new(objects.into_iter(), |(_, keys)| keys.clone());
+        let matches = |keys: &[u32]| -> Vec<&str> {
+            index.matches(keys).into_iter().map(|position| index.get(position).0).collect()
+        };
+        assert_eq!(matches(&[1]), vec!["b"]);
+        assert_eq!(matches(&[2]), vec!["b", "c"]);
+        assert_eq!(matches(&[3, 2, 1]), vec!["b", "c", "d"]);
+        assert_eq!(matches(&[4]), Vec::<&str>::new());
+        assert_eq!(matches(&[]), Vec::<&str>::new());
+    }
+}
@@ -0,0 +1,466 @@
+use std::any::Any;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+
+use crate::attrib::{Attribute, OptionGetter, OptionCountable};
+use crate::objects::{self, Identifiable, ItemWithData};
+use crate::time::Duration;
+use crate::Store;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Entity { Project, Commit, User, Path, Snapshot }
+
+impl Entity {
+    pub fn from_source(source: &str) -> Option<Entity> {
+        match source {
+            "projects" => Some(Entity::Project),
+            "commits"  => Some(Entity::Commit),
+            "users"    => Some(Entity::User),
+            "paths"    => Some(Entity::Path),
+            _          => None,
+        }
+    }
+    /** Name of the module containing the attributes of this entity, eg. `project`. */
+    pub fn module(&self) -> &'static str {
+        match self {
+            Entity::Project  => "project",
+            Entity::Commit   => "commit",
+            Entity::User     => "user",
+            Entity::Path     => "path",
+            Entity::Snapshot => "snapshot",
+        }
+    }
+    /** Name of the collection of all objects of this entity, eg. `projects`. */
+    pub fn source(&self) -> &'static str {
+        match self {
+            Entity::Project  => "projects",
+            Entity::Commit   => "commits",
+            Entity::User     => "users",
+            Entity::Path     => "paths",
+            Entity::Snapshot => "snapshots",
+        }
+    }
+}
+
+impl Display for Entity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Entity::Project  => write!(f, "ProjectId"),
+            Entity::Commit   => write!(f, "CommitId"),
+            Entity::User     => write!(f, "UserId"),
+            Entity::Path     => write!(f, "PathId"),
+            Entity::Snapshot => write!(f, "SnapshotId"),
+        }
+    }
+}
+
+/** The type of the values of an attribute. */
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Type {
+    Bool,
+    Int,
+    Float,
+    String,
+    Id(Entity),
+    List(Box<Type>),
+    Tuple(Vec<Type>),
+}
+
+impl Type {
+    pub fn is_collection(&self) -> bool {
+        match self { Type::List(_) => true, _ => false }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Bool          => write!(f, "bool"),
+            Type::Int           => write!(f, "int"),
+            Type::Float         => write!(f, "float"),
+            Type::String        => write!(f, "string"),
+            Type::Id(entity)    => write!(f, "{}", entity),
+            Type::List(element) => write!(f, "[{}]", element),
+            Type::Tuple(types)  => write!(f, "({})", types.iter().join(", ")),
+        }
+    }
+}
+
+/**
+ * A dynamically typed value of an attribute. Counts, sizes, and timestamps are `Int`s, durations
+ * are `Int`s in seconds, languages, stores, categories, and other enumerations are `String`s, and
+ * objects are represented by their ids.
+ */
+#[derive(Clone, Debug)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Id(Entity, u64),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+}
+
+impl Value {
+    fn rank(&self) -> u8 {
+        match self {
+            Value::Null      => 0,
+            Value::Bool(_)   => 1,
+            Value::Int(_)    => 2,
+            Value::Float(_)  => 2,
+            Value::String(_) => 3,
+            Value::Id(_, _)  => 4,
+            Value::List(_)   => 5,
+            Value::Tuple(_)  => 6,
+        }
+    }
+}
+
+fn compare_floats(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+}
+
+// Integers and floats are compared by their numeric values. Values of different types are ordered
+// by type, with Null being the smallest, so that missing values are sorted like `None`s.
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Bool(a),   Value::Bool(b))   => a.cmp(b),
+            (Value::Int(a),    Value::Int(b))    => a.cmp(b),
+            (Value::Int(a),    Value::Float(b))  => compare_floats(*a as f64, *b),
+            (Value::Float(a),  Value::Int(b))    => compare_floats(*a, *b as f64),
+            (Value::Float(a),  Value::Float(b))  => compare_floats(*a, *b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Id(ea, a), Value::Id(eb, b)) => ea.cmp(eb).then(a.cmp(b)),
+            (Value::List(a),   Value::List(b))   => a.cmp(b),
+            (Value::Tuple(a),  Value::Tuple(b))  => a.cmp(b),
+            (a, b) => a.rank().cmp(&b.rank()),
+        }
+    }
+}
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+}
+impl Eq for Value {}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null          => Ok(()),
+            Value::Bool(value)   => write!(f, "{}", value),
+            Value::Int(value)    => write!(f, "{}", value),
+            Value::Float(value)  => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Id(_, id)     => write!(f, "{}", id),
+            Value::List(values)  => write!(f, "[{}]", values.iter().join(", ")),
+            Value::Tuple(values) => write!(f, "({})", values.iter().join(", ")),
+        }
+    }
+}
+
+/** Conversion of the values returned by attributes into `Value`s. */
+pub trait IntoValue {
+    fn value_type() -> Type;
+    fn into_value(self) -> Value;
+}
+
+macro_rules! impl_into_value {
+    (Int: $($source:ty),+) => {
+        $(impl IntoValue for $source {
+            fn value_type() -> Type { Type::Int }
+            fn into_value(self) -> Value { Value::Int(self as i64) }
+        })+
+    };
+    (String: $($source:ty),+) => {
+        $(impl IntoValue for $source {
+            fn value_type() -> Type { Type::String }
+            fn into_value(self) -> Value { Value::String(self.to_string()) }
+        })+
+    };
+    (Id: $($source:ty => $entity:ident),+) => {
+        $(impl IntoValue for $source {
+            fn value_type() -> Type { Type::Id(Entity::$entity) }
+            fn into_value(self) -> Value { Value::Id(Entity::$entity, self.id().0) }
+        })+
+    };
+}
+
+impl_into_value!(Int: usize, u64, u32, u8, i64, i32);
+impl_into_value!(String: String, objects::Language, objects::LanguageKind, objects::CommitCategory,
+                 objects::Tool, objects::Dependency, objects::DetectedLicense, Store);
+impl_into_value!(Id: objects::Project => Project, objects::Commit => Commit, objects::User => User,
+                 objects::Path => Path, objects::Snapshot => Snapshot);
+
+impl IntoValue for bool {
+    fn value_type() -> Type { Type::Bool }
+    fn into_value(self) -> Value { Value::Bool(self) }
+}
+impl IntoValue for f64 {
+    fn value_type() -> Type { Type::Float }
+    fn into_value(self) -> Value { Value::Float(self) }
+}
+impl IntoValue for Duration {
+    fn value_type() -> Type { Type::Int }
+    fn into_value(self) -> Value { Value::Int(self.as_seconds() as i64) }
+}
+
+macro_rules! impl_id_into_value {
+    ($($id:ident => $entity:ident),+) => {
+        $(impl IntoValue for objects::$id {
+            fn value_type() -> Type { Type::Id(Entity::$entity) }
+            fn into_value(self) -> Value { Value::Id(Entity::$entity, self.0) }
+        })+
+    }
+}
+
+impl_id_into_value!(ProjectId => Project, CommitId => Commit, UserId => User, PathId => Path,
+                    SnapshotId => Snapshot);
+
+impl<'a, T> IntoValue for ItemWithData<'a, T> where T: IntoValue {
+    fn value_type() -> Type { T::value_type() }
+    fn into_value(self) -> Value { self.item.into_value() }
+}
+impl<T> IntoValue for Option<T> where T: IntoValue {
+    fn value_type() -> Type { T::value_type() }
+    fn into_value(self) -> Value { self.map_or(Value::Null, IntoValue::into_value) }
+}
+impl<T> IntoValue for Vec<T> where T: IntoValue {
+    fn value_type() -> Type { Type::List(Box::new(T::value_type())) }
+    fn into_value(self) -> Value { Value::List(self.into_iter().map(IntoValue::into_value).collect()) }
+}
+impl<A, B> IntoValue for (A, B) where A: IntoValue, B: IntoValue {
+    fn value_type() -> Type { Type::Tuple(vec![A::value_type(), B::value_type()]) }
+    fn into_value(self) -> Value { Value::Tuple(vec![self.0.into_value(), self.1.into_value()]) }
+}
+
+pub type DynamicGetter<T> = for<'a> fn(&ItemWithData<'a, T>) -> Option<Value>;
+pub type DynamicCounter<T> = for<'a> fn(&ItemWithData<'a, T>) -> Option<usize>;
+
+/** An attribute that can be looked up by name and whose values are retrieved dynamically. */
+pub struct AttributeEntry<T> {
+    pub name: &'static str,
+    pub value_type: Type,
+    pub getter: DynamicGetter<T>,
+    pub counter: Option<DynamicCounter<T>>,
+}
+
+impl<T> Clone for AttributeEntry<T> {
+    fn clone(&self) -> Self {
+        AttributeEntry {
+            name: self.name,
+            value_type: self.value_type.clone(),
+            getter: self.getter,
+            counter: self.counter,
+        }
+    }
+}
+
+/** Objects that can be queried. */
+pub trait Queryable: Sized + 'static {
+    const ENTITY: Entity;
+    /** The attributes of the object that can be used in queries: the attributes in the module of
+        the entity that take no parameters and whose values can be represented as `Value`s. */
+    fn attributes() -> Vec<AttributeEntry<Self>> {
+        of(Self::ENTITY.module()).into_iter().filter_map(|info| info.entry::<Self>()).cloned().collect()
+    }
+}
+
+impl Queryable for objects::Project { const ENTITY: Entity = Entity::Project; }
+impl Queryable for objects::Commit  { const ENTITY: Entity = Entity::Commit;  }
+impl Queryable for objects::User    { const ENTITY: Entity = Entity::User;    }
+impl Queryable for objects::Path    { const ENTITY: Entity = Entity::Path;    }
+
+/**
+ * Description of an attribute declared in `lib.rs`, either with `impl_attribute!` or by hand: the
+ * module it is in (`project`, `commit`, ..., `snapshot`), its name, the types of its parameters,
+ * the type it returns (the type of the elements for collections), whether it is optional or a
+ * collection, and its doc comment.
+ *
+ * Attributes that take no parameters and return values that can be represented as `Value`s also
+ * have a dynamic getter (see `get`). Those of projects, commits, users, and paths are the
+ * attributes that can be used in queries (see `query::Query`).
+ */
+pub struct AttributeInfo {
+    pub entity: &'static str,
+    pub name: &'static str,
+    pub parameters: Vec<&'static str>,
+    pub return_type: &'static str,
+    pub optional: bool,
+    pub collection: bool,
+    pub doc: Option<&'static str>,
+    dynamic: Option<(Type, Box<dyn Any + Send + Sync>)>,
+}
+
+impl AttributeInfo {
+    /** Full name of the attribute, eg. `project::Stars`. */
+    pub fn path(&self) -> String {
+        format!("{}::{}", self.entity, self.name)
+    }
+    /** Type of the dynamic values of the attribute, if it has a dynamic getter. */
+    pub fn value_type(&self) -> Option<&Type> {
+        self.dynamic.as_ref().map(|(value_type, _)| value_type)
+    }
+    pub fn has_getter(&self) -> bool {
+        self.dynamic.is_some()
+    }
+    fn entry<T: 'static>(&self) -> Option<&AttributeEntry<T>> {
+        self.dynamic.as_ref().and_then(|(_, entry)| entry.downcast_ref::<AttributeEntry<T>>())
+    }
+    /** Retrieves the value of the attribute for an object. Fails if the attribute has no dynamic
+        getter or if it is not an attribute of this kind of object. */
+    pub fn get<'a, T: 'static>(&self, object: &ItemWithData<'a, T>) -> Result<Option<Value>> {
+        if !self.has_getter() {
+            return Err(anyhow!("`{}` has no dynamic getter", self.path()))
+        }
+        let entry = self.entry::<T>()
+            .ok_or_else(|| anyhow!("`{}` is not an attribute of {}", self.path(), std::any::type_name::<T>()))?;
+        Ok((entry.getter)(object))
+    }
+}
+
+/*
+ * Dynamic getters are created by autoref-based specialization: `dynamic!` calls `entry` on a
+ * `&&&Dynamic<A>`, and method resolution picks the first of `DynamicCollection` (implemented for
+ * `&&Dynamic<A>`), `DynamicValue` (for `&Dynamic<A>`), and `NotDynamic` (for `Dynamic<A>`) whose
+ * bounds `A` satisfies. This lets the generated registry try every attribute without knowing which
+ * ones have values that can be represented as `Value`s. Only attributes without parameters
+ * implement `Default`, so attributes with parameters never have dynamic getters.
+ */
+pub struct Dynamic<A>(PhantomData<A>);
+
+pub trait DynamicCollection {
+    type Object;
+    fn entry(&self, name: &'static str) -> Option<AttributeEntry<Self::Object>>;
+}
+pub trait DynamicValue {
+    type Object;
+    fn entry(&self, name: &'static str) -> Option<AttributeEntry<Self::Object>>;
+}
+pub trait NotDynamic {
+    type Object;
+    fn entry(&self, _name: &'static str) -> Option<AttributeEntry<Self::Object>> { None }
+}
+
+fn dynamic_get<'a, A, T>(object: &ItemWithData<'a, T>) -> Option<Value>
+    where A: Default + Attribute<Object=T> + for<'b> OptionGetter<'b>,
+          for<'b> <A as OptionGetter<'b>>::IntoItem: IntoValue {
+    A::default().get_opt(object).map(IntoValue::into_value)
+}
+
+fn dynamic_count<'a, A, T>(object: &ItemWithData<'a, T>) -> Option<usize>
+    where A: Default + Attribute<Object=T> + for<'b> OptionCountable<'b> {
+    A::default().count(object)
+}
+
+impl<A, T> DynamicCollection for &&Dynamic<A>
+    where A: Default + Attribute<Object=T> + for<'b> OptionGetter<'b> + for<'b> OptionCountable<'b>,
+          for<'b> <A as OptionGetter<'b>>::IntoItem: IntoValue {
+    type Object = T;
+    fn entry(&self, name: &'static str) -> Option<AttributeEntry<T>> {
+        Some(AttributeEntry {
+            name,
+            value_type: <<A as OptionGetter<'static>>::IntoItem as IntoValue>::value_type(),
+            getter: dynamic_get::<A, T>,
+            counter: Some(dynamic_count::<A, T>),
+        })
+    }
+}
+impl<A, T> DynamicValue for &Dynamic<A>
+    where A: Default + Attribute<Object=T> + for<'b> OptionGetter<'b>,
+          for<'b> <A as OptionGetter<'b>>::IntoItem: IntoValue {
+    type Object = T;
+    fn entry(&self, name: &'static str) -> Option<AttributeEntry<T>> {
+        Some(AttributeEntry {
+            name,
+            value_type: <<A as OptionGetter<'static>>::IntoItem as IntoValue>::value_type(),
+            getter: dynamic_get::<A, T>,
+            counter: None,
+        })
+    }
+}
+impl<A> NotDynamic for Dynamic<A> where A: Attribute {
+    type Object = A::Object;
+}
+
+fn boxed<T: 'static>(entry: Option<AttributeEntry<T>>) -> Option<(Type, Box<dyn Any + Send + Sync>)> {
+    entry.map(|entry| (entry.value_type.clone(), Box::new(entry) as Box<dyn Any + Send + Sync>))
+}
+
+macro_rules! dynamic {
+    ($attribute:path, $name:expr) => {
+        boxed((&&&Dynamic::<$attribute>(PhantomData)).entry($name))
+    };
+}
+
+// The registry is generated by build.rs from the declarations in lib.rs.
+fn declared() -> Vec<AttributeInfo> {
+    include!(concat!(env!("OUT_DIR"), "/attributes.rs"))
+}
+
+static ATTRIBUTES: OnceLock<Vec<AttributeInfo>> = OnceLock::new();
+
+/** All attributes declared in the modules of `lib.rs`, in the order they are declared in. */
+pub fn all() -> &'static [AttributeInfo] {
+    ATTRIBUTES.get_or_init(declared)
+}
+
+/** The attributes of one kind of objects, eg. `of("project")`. */
+pub fn of(entity: &str) -> Vec<&'static AttributeInfo> {
+    all().iter().filter(|info| info.entity == entity).collect()
+}
+
+/** Looks up an attribute by its full name, eg. `find("project::Stars")`. */
+pub fn find(path: &str) -> Option<&'static AttributeInfo> {
+    all().iter().find(|info| info.path() == path)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::attributes::*;
+
+    #[test]
+    fn test_all() {
+        let attributes = all();
+
+        let stars = attributes.iter().find(|info| info.path() == "project::Stars").unwrap();
+        assert_eq!(stars.return_type, "usize");
+        assert!(stars.optional && !stars.collection && stars.parameters.is_empty());
+        assert!(stars.doc.as_ref().unwrap().starts_with("Returns the number of stars"));
+        assert_eq!(stars.value_type(), Some(&Type::Int));
+
+        let parents = attributes.iter().find(|info| info.path() == "commit::ParentIds").unwrap();
+        assert_eq!(parents.return_type, "objects::CommitId");
+        assert!(!parents.optional && parents.collection);
+        assert_eq!(parents.value_type(), Some(&Type::List(Box::new(Type::Id(Entity::Commit)))));
+
+        let between = attributes.iter().find(|info| info.path() == "user::CollaboratorIdsBetween").unwrap();
+        assert_eq!(between.parameters, vec!["Timestamp", "Timestamp"]);
+        assert!(!between.has_getter());
+
+        let trees = attributes.iter().find(|info| info.path() == "project::HeadTrees").unwrap();
+        assert_eq!(trees.return_type, "(String, Vec<(objects::PathId, objects::SnapshotId)>)");
+
+        let default_commit = find("project::DefaultCommit").unwrap();
+        assert_eq!(default_commit.value_type(), Some(&Type::Id(Entity::Commit)));
+        assert!(objects::Project::attributes().iter().any(|entry| entry.name == "DefaultCommit"));
+
+        let category_with = find("commit::CategoryWith").unwrap();
+        assert_eq!(category_with.parameters, vec!["crate::classification::CommitRules"]);
+        assert_eq!(category_with.return_type, "objects::CommitCategory");
+        assert!(category_with.optional && !category_with.has_getter());
+        assert!(category_with.doc.unwrap().starts_with("Category of the commit"));
+
+        // The registry is built once.
+        assert!(std::ptr::eq(all(), attributes));
+    }
+}
@@ -4,7 +4,7 @@ use djanco::*;
 use djanco::database::*;
 use djanco::objects::*;
 use djanco::csv::*;
-use djanco::log::*;
+use djanco::log::{Log, Verbosity};
 
 // rm -rf ~/djanco_cache && cargo run --bin clones --release -- -o ~/output -d /home/peta/devel/codedj-2/datasets/java-1k5-merged -c ~/djanco_cache --data-dump ~/output/dump > out.txt
 
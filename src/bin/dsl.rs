@@ -3,7 +3,7 @@ use clap::Parser;
 use djanco::*;
 use djanco::objects::*;
 use djanco::csv::*;
-use djanco::log::*;
+use djanco::log::{Log, Verbosity};
 
 // `cargo run --bin dsl --release -- -o ~/output -d /mnt/data/dataset -c /mnt/data/cache --data-dump=~/output/dump`
 fn main() {
@@ -33,18 +33,31 @@ fn main() {
     database.commits().sample(Random(100, Seed(42))).into_csv_in_dir(&config.output_path,  "sample_100_commits").unwrap();
     database.projects().map_into(Ratio(project::Authors, project::Users)).into_csv_in_dir(&config.output_path,  "select_project_ratio_of_authors_to_users").unwrap();
     database.projects().map_into(Select!(project::Id, project::URL)).into_csv_in_dir(&config.output_path,  "select_project_ids_and_urls").unwrap();
-    database.commits().map_into(commit::Author)/*TODO .unique().map_into_attrib(user::IdTODO Experience*/.into_csv_in_dir(&config.output_path,  "commit_author_experience").unwrap();
-    database.commits().map_into(commit::Committer)/*TODO .unique().map_into_attrib(user::IdTODO Experience*/.into_csv_in_dir(&config.output_path,  "commit_committer_experience").unwrap();
+    database.commits().expand(commit::Author).distinct().map_into(Select!(user::Id, user::Experience)).into_csv_in_dir(&config.output_path,  "commit_author_experience").unwrap();
+    database.commits().expand(commit::Committer).distinct().map_into(Select!(user::Id, user::Experience)).into_csv_in_dir(&config.output_path,  "commit_committer_experience").unwrap();
+    database.projects().filter_by(Equal(project::Language, Language::Haskell)).expand(project::Commits).distinct().distinct_by(commit::Message).into_csv_in_dir(&config.output_path,  "haskell_commits_with_unique_messages").unwrap();
+    database.projects().filter_by(Equal(project::Language, Language::Haskell)).expand(project::Commits).expand(commit::Author).distinct()
+        .join(database.projects(), (Each(user::ProjectIds), project::Id))
+        .map(|(user, project)| (user.item.id(), project.item.id())).into_csv_in_dir(&config.output_path,  "haskell_authors_and_their_projects").unwrap();
     database.commits().map_into(commit::Parents).into_csv_in_dir(&config.output_path,  "commit_parents").unwrap();
     database.projects().map_into(FromEach(project::Commits, commit::MessageLength)).into_csv_in_dir(&config.output_path,  "project_commit_message_length").unwrap();
     database.users().sort_by(user::Experience).map_into(user::Experience).into_csv_in_dir(&config.output_path,  "user_experience").unwrap();
+    database.projects().sort_by_keys(Select!(project::Language, project::Stars, project::Created), [Asc, Desc, Asc]).map_into(Select!(project::Id, project::Language, project::Stars)).into_csv_in_dir(&config.output_path,  "projects_by_language_and_stars").unwrap();
+    database.projects().group_by(project::Language).sort_by_keys_with_nulls(Select!(project::Stars), [Desc], Nulls::First).map_into(project::Id).into_csv_in_dir(&config.output_path,  "language/projects_by_stars").unwrap();
+    database.projects().group_by(project::Language).aggregate(Select!(Count, Median(project::Stars), Max(project::Stars))).into_csv_in_dir(&config.output_path,  "language/stars").unwrap();
+    database.projects().group_by(project::Language).filter_key(|language| language.is_some()).sample_key(Random(3, Seed(42))).sort_key().aggregate(Mean(project::Stars)).into_csv_in_dir(&config.output_path,  "language/mean_stars_sample").unwrap();
     database.projects().group_by(project::Language).map_into(FromEach(project::Commits, commit::MessageLength)).into_csv_in_dir(&config.output_path,  "language/project_commit_message_length").unwrap();
     database.projects().filter_by(Member(project::Homepage, vec!["http://manasource.org/"].iter().map(|e| e.to_string()).collect::<Vec<String>>()));
     database.projects().filter_by(AnyIn(FromEach(project::Commits, commit::Id), vec![objects::CommitId::from(42u64), objects::CommitId::from(666u64)]));
     database.projects().filter_by(AllIn(FromEach(project::Commits, commit::Id), vec![objects::CommitId::from(42u64), objects::CommitId::from(666u64)]));
     database.projects().filter_by(Within(FromEach(project::Commits, commit::Id), objects::CommitId::from(666u64)));
+    database.projects().filter_by(Computed(|project: &ItemWithData<Project>| project.url().contains("github.com/microsoft/"))).into_csv_in_dir(&config.output_path,  "filter_by_custom_owner").unwrap();
+    database.projects().sort_by(Cached("issues_per_star", Computed(|project: &ItemWithData<Project>| project.issue_count().unwrap_or(0) / project.star_count().unwrap_or(0).max(1)))).into_csv_in_dir(&config.output_path,  "sort_by_cached_issues_per_star").unwrap();
+    database.projects().filter_by(Greater(Count(project::Authors), Count(project::Committers))).into_csv_in_dir(&config.output_path,  "filter_by_more_authors_than_committers").unwrap();
+    database.projects().map_into(Select!(project::Id, Div(Cast::<f64, _>(project::Stars), Cast::<f64, _>(Count(project::Commits))), djanco::Log(project::Stars))).into_csv_in_dir(&config.output_path,  "stars_per_commit").unwrap();
     database.snapshots_with_data().sample(Random(10, Seed(42))).into_files_in_dir(&config.output_path).unwrap();
-    // database.projects().map_into_attrib(Bucket(Count(project::Commits), Interval(1000))).into_csv_in_dir(&config.output_path,  "bucket_1000").unwrap();
+    database.projects().map_into(Select!(project::Id, Bucket(Count(project::Commits), Interval(1000)))).into_csv_in_dir(&config.output_path,  "bucket_1000").unwrap();
+    database.projects().group_by(Bucket(project::Stars, Quantile(4))).map_into(project::Id).into_csv_in_dir(&config.output_path,  "star_quartiles").unwrap();
     database.commits().map_into(commit::Tree);//.into_csv_in_dir(&config.output_path, "commit_trees.csv").unwrap();
     database.projects().sample(Stratified(project::Size, Strata!("big" -> Random(5, Seed(42)), "small" -> Random(10, Seed(42))), Custom(|size: Option<&usize>| match size { None => "NA", Some(n) if *n >= 10000 => "big", Some(_n) => "small" }))).into_csv_in_dir(&config.output_path,  "stratified_1").unwrap();
     database.projects().sample(Stratified(project::Size, Strata!("big" -> Random(5, Seed(42)), "small" -> Random(10, Seed(42))), Threshold::Inclusive(10000, "big", "small"))).into_csv_in_dir(&config.output_path,  "stratified_2").unwrap();
@@ -5,7 +5,7 @@ use djanco::database::*;
 use djanco::time::*;
 use djanco::objects::*;
 use djanco::csv::*;
-use djanco::log::*;
+use djanco::log::{Log, Verbosity};
 use djanco::fraction::Fraction;
 
 fn stars<'a>(_config: &Configuration, _log: &Log, database: &'a Database) -> impl Iterator<Item=ItemWithData<'a, Project>> {
@@ -2,7 +2,7 @@ use clap::Parser;
 
 use djanco::*;
 use djanco::csv::*;
-use djanco::log::*;
+use djanco::log::{Log, Verbosity};
 use djanco::objects::*;
 
 fn main() {
@@ -0,0 +1,112 @@
+use std::io::{BufRead, Write};
+
+use clap::Parser;
+
+use djanco::*;
+use djanco::log::Log;
+use djanco::database::Database;
+use djanco::attributes::{self, Entity};
+use djanco::query::*;
+
+const PAGE_SIZE: usize = 20;
+const MAX_WIDTH: usize = 60;
+
+const HELP: &'static str = "\
+Queries mirror the DSL, eg.:
+
+    projects | filter_by AtLeast(Stars, 100) | sort_by Commits | sample Random(50, 42) | map_into Id, URL
+
+Commands:
+    :describe <projects|commits|users|paths>   list the attributes that can be used in queries
+    :more                                      show the next page of the last result (or press enter)
+    :page <n>                                  show n rows per page
+    :save <file>                               save the last result as <file>.csv in the output directory
+    :help                                      show this message
+    :quit                                      exit";
+
+struct Session {
+    result: Option<Table>,
+    shown: usize,
+    page_size: usize,
+}
+
+impl Session {
+    fn show_page(&mut self) {
+        match &self.result {
+            None => println!("There is no result to show."),
+            Some(table) if self.shown >= table.len() && self.shown > 0 => println!("(end of result)"),
+            Some(table) => {
+                let end = (self.shown + self.page_size).min(table.len());
+                println!("{}", table.to_text(self.shown..end, MAX_WIDTH));
+                println!("({}-{} of {} rows{})", (self.shown + 1).min(end), end, table.len(),
+                         if end < table.len() { ", :more for more" } else { "" });
+                self.shown = end;
+            }
+        }
+    }
+
+    fn run(&mut self, database: &Database, config: &Configuration, line: &str) -> anyhow::Result<bool> {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            None | Some(":more") => self.show_page(),
+            Some(":quit") | Some(":q") | Some(":exit") => return Ok(false),
+            Some(":help") | Some(":h") => println!("{}", HELP),
+            Some(":describe") => {
+                let source = words.next().unwrap_or("projects");
+                let entity = Entity::from_source(source)
+                    .ok_or_else(|| anyhow::anyhow!("expected `projects`, `commits`, `users`, or `paths`, but found `{}`", source))?;
+                for attribute in attributes::of(entity.module()) {
+                    if let Some(value_type) = attribute.value_type() {
+                        let doc = attribute.doc.as_ref().and_then(|doc| doc.lines().next()).unwrap_or("");
+                        println!("{:<44} {:<24} {}", attribute.path(), value_type.to_string(), doc);
+                    }
+                }
+            }
+            Some(":page") => {
+                let size = words.next().ok_or_else(|| anyhow::anyhow!("expected a page size"))?;
+                self.page_size = size.parse::<usize>()?.max(1);
+            }
+            Some(":save") => {
+                let file = words.next().ok_or_else(|| anyhow::anyhow!("expected a file name"))?;
+                let table = self.result.clone().ok_or_else(|| anyhow::anyhow!("there is no result to save"))?;
+                table.into_csv(config.path_in_output_dir_as_str(file, "csv")?)?;
+            }
+            Some(command) if command.starts_with(':') => anyhow::bail!("unknown command `{}`, see :help", command),
+            Some(_) => {
+                let query = Query::parse(line)?;
+                let start = std::time::Instant::now();
+                let table = query.execute(database)?;
+                eprintln!("{} rows in {:.2}s", table.len(), start.elapsed().as_secs_f64());
+                self.result = Some(table);
+                self.shown = 0;
+                self.show_page();
+            }
+        }
+        Ok(true)
+    }
+}
+
+// `cargo run --bin repl --release -- -o ~/output -d /mnt/data/dataset -c /mnt/data/cache`
+fn main() {
+    let config = Configuration::parse();
+    let database =
+        Djanco::from_config(&config, timestamp!(December 2020), vec![], Log::new(config.verbosity)).unwrap();
+
+    println!("Djanco {}. Type :help for help.", env!("CARGO_PKG_VERSION"));
+    let mut session = Session { result: None, shown: 0, page_size: PAGE_SIZE };
+    let stdin = std::io::stdin();
+    loop {
+        print!("djanco> ");
+        std::io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break
+        }
+        match session.run(&database, &config, line.trim()) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(error) => eprintln!("error: {:#}", error),
+        }
+    }
+}
@@ -4,7 +4,7 @@ use djanco::*;
 use djanco::database::*;
 use djanco::objects::*;
 use djanco::csv::*;
-use djanco::log::*;
+use djanco::log::{Log, Verbosity};
 
 // rm -rf ~/djanco_cache && cargo run --bin stefanie --release -- -o ~/output -d ~//Documents/prague/work/codedj-parasite/example-dataset -c ~/djanco_cache --data-dump ~/output/dump > out.txt
 
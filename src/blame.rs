@@ -0,0 +1,360 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+use crate::objects::{CommitId, PathId, SnapshotId};
+use crate::diff;
+
+/**
+ * Access to the history of files, as needed for blaming lines and finding bug-introducing commits.
+ * Implemented over the database and over the raw maps used by extractors.
+ */
+pub(crate) trait PathHistory {
+    fn parents(&self, commit: &CommitId) -> Vec<CommitId>;
+    /** How the commit changed the path: `None` if it did not change it, `Some(None)` if it deleted it,
+        and `Some(Some(snapshot))` if it created or modified it. */
+    fn change(&self, commit: &CommitId, path: &PathId) -> Option<Option<SnapshotId>>;
+    fn lines(&self, snapshot: &SnapshotId) -> Option<Vec<String>>;
+
+    /**
+     * Finds the version of the path that the commit started from: the last change to the path among
+     * the commit's ancestors, following first parents only (like `git blame --first-parent`).
+     * Returns the commit that made that change and the resulting snapshot, or `None` if the path did
+     * not exist.
+     */
+    fn previous_version(&self, commit: &CommitId, path: &PathId) -> Option<(CommitId, SnapshotId)> {
+        let mut current = self.parents(commit).first().copied()?;
+        loop {
+            match self.change(&current, path) {
+                Some(Some(snapshot)) => return Some((current, snapshot)),
+                Some(None) => return None,
+                None => current = self.parents(&current).first().copied()?,
+            }
+        }
+    }
+
+    /**
+     * How the version of the path that the commit created (`snapshot`) relates to the version it
+     * started from. Returns `None` if there was no previous version.
+     */
+    fn line_map(&self, commit: &CommitId, path: &PathId, snapshot: &SnapshotId) -> Option<LineMap> {
+        compute_line_map(self, commit, path, snapshot)
+    }
+}
+
+/**
+ * The previous version of a path (the commit that created it and its snapshot), and for each line
+ * of the current version the line of the previous version it is unchanged from, if any.
+ */
+#[derive(Clone, Debug)]
+pub(crate) struct LineMap {
+    pub previous_commit: CommitId,
+    pub previous_snapshot: SnapshotId,
+    pub mapping: Rc<Vec<Option<usize>>>,
+}
+
+fn compute_line_map<H: PathHistory + ?Sized>(history: &H, commit: &CommitId, path: &PathId, snapshot: &SnapshotId) -> Option<LineMap> {
+    let (previous_commit, previous_snapshot) = history.previous_version(commit, path)?;
+    let previous_contents = history.lines(&previous_snapshot)?;
+    let contents = history.lines(snapshot)?;
+    let ops = diff::diff_lines(&previous_contents, &contents);
+    Some(LineMap { previous_commit, previous_snapshot, mapping: Rc::new(diff::new_to_old(&ops, contents.len())) })
+}
+
+/**
+ * A history that remembers the contents of snapshots and the line maps between versions, so that
+ * analyses which revisit the same files (eg. SZZ over many fixes to one file) read and diff each
+ * version only once. Each cache holds at most `capacity` entries and is emptied when it fills up.
+ */
+pub(crate) struct MemoizedHistory<'a, H: PathHistory> {
+    history: &'a H,
+    capacity: usize,
+    lines: RefCell<BTreeMap<SnapshotId, Option<Rc<Vec<String>>>>>,
+    line_maps: RefCell<BTreeMap<(CommitId, PathId), Option<LineMap>>>,
+}
+
+impl<'a, H: PathHistory> MemoizedHistory<'a, H> {
+    pub fn new(history: &'a H, capacity: usize) -> Self {
+        MemoizedHistory { history, capacity, lines: RefCell::new(BTreeMap::new()), line_maps: RefCell::new(BTreeMap::new()) }
+    }
+
+    fn remember<K: Ord, V>(&self, cache: &RefCell<BTreeMap<K, V>>, key: K, value: V) {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= self.capacity { cache.clear() }
+        cache.insert(key, value);
+    }
+}
+
+impl<'a, H: PathHistory> PathHistory for MemoizedHistory<'a, H> {
+    fn parents(&self, commit: &CommitId) -> Vec<CommitId> { self.history.parents(commit) }
+    fn change(&self, commit: &CommitId, path: &PathId) -> Option<Option<SnapshotId>> { self.history.change(commit, path) }
+    fn previous_version(&self, commit: &CommitId, path: &PathId) -> Option<(CommitId, SnapshotId)> { self.history.previous_version(commit, path) }
+
+    fn lines(&self, snapshot: &SnapshotId) -> Option<Vec<String>> {
+        if let Some(lines) = self.lines.borrow().get(snapshot) {
+            return lines.as_ref().map(|lines| lines.as_ref().clone())
+        }
+        let lines = self.history.lines(snapshot).map(Rc::new);
+        self.remember(&self.lines, *snapshot, lines.clone());
+        lines.map(|lines| lines.as_ref().clone())
+    }
+
+    fn line_map(&self, commit: &CommitId, path: &PathId, snapshot: &SnapshotId) -> Option<LineMap> {
+        if let Some(line_map) = self.line_maps.borrow().get(&(*commit, *path)) {
+            return line_map.clone()
+        }
+        let line_map = compute_line_map(self, commit, path, snapshot);
+        self.remember(&self.line_maps, (*commit, *path), line_map.clone());
+        line_map
+    }
+}
+
+/**
+ * The version of the path as of the commit: the snapshot the commit created, or else the version it
+ * started from.
+ */
+pub(crate) fn version_at<H: PathHistory>(history: &H, commit: &CommitId, path: &PathId) -> Option<(CommitId, SnapshotId)> {
+    match history.change(commit, path) {
+        Some(Some(snapshot)) => Some((*commit, snapshot)),
+        Some(None) => None,
+        None => history.previous_version(commit, path),
+    }
+}
+
+/**
+ * The contents of the path as of the commit, each line paired with the commit that last changed it.
+ * Returns `None` if the path does not exist at that commit.
+ */
+pub(crate) fn blame_file<H: PathHistory>(history: &H, commit: &CommitId, path: &PathId) -> Option<Vec<(CommitId, String)>> {
+    let (changed_in, snapshot) = version_at(history, commit, path)?;
+    let contents = history.lines(&snapshot)?;
+    let all_lines: Vec<usize> = (0..contents.len()).collect();
+    let origins = blame_lines(history, &changed_in, path, &snapshot, &all_lines);
+    Some(contents.into_iter().enumerate()
+        .map(|(line, text)| (origins[&line], text))
+        .collect())
+}
+
+/**
+ * A history restricted to the first-parent chain of one commit, with the changes to each path along
+ * the chain indexed up front. Blaming many files at the same commit would otherwise walk the whole
+ * chain again for every version of every file.
+ */
+pub(crate) struct FirstParentHistory<'a, H: PathHistory> {
+    history: &'a H,
+    positions: BTreeMap<CommitId, usize>,
+    // For each path, the changes along the chain, newest first: (position, commit, snapshot).
+    versions: BTreeMap<PathId, Vec<(usize, CommitId, Option<SnapshotId>)>>,
+}
+
+impl<'a, H: PathHistory> FirstParentHistory<'a, H> {
+    pub fn new(history: &'a H, head: &CommitId, changes: impl Fn(&CommitId) -> Vec<(PathId, Option<SnapshotId>)>) -> Self {
+        let mut positions = BTreeMap::new();
+        let mut versions: BTreeMap<PathId, Vec<(usize, CommitId, Option<SnapshotId>)>> = BTreeMap::new();
+        let mut current = Some(*head);
+        while let Some(commit) = current {
+            if positions.contains_key(&commit) { break }
+            let position = positions.len();
+            positions.insert(commit, position);
+            for (path, snapshot) in changes(&commit) {
+                versions.entry(path).or_insert_with(Vec::new).push((position, commit, snapshot));
+            }
+            current = history.parents(&commit).first().copied();
+        }
+        FirstParentHistory { history, positions, versions }
+    }
+
+    /** The paths that exist at the head of the chain, with the commits that last changed them. */
+    pub fn tree(&self) -> Vec<(PathId, CommitId, SnapshotId)> {
+        self.versions.iter()
+            .filter_map(|(path, changes)| {
+                let (_, commit, snapshot) = changes.first()?;
+                snapshot.map(|snapshot| (*path, *commit, snapshot))
+            })
+            .collect()
+    }
+}
+
+impl<'a, H: PathHistory> PathHistory for FirstParentHistory<'a, H> {
+    fn parents(&self, commit: &CommitId) -> Vec<CommitId> { self.history.parents(commit) }
+    fn change(&self, commit: &CommitId, path: &PathId) -> Option<Option<SnapshotId>> { self.history.change(commit, path) }
+    fn lines(&self, snapshot: &SnapshotId) -> Option<Vec<String>> { self.history.lines(snapshot) }
+
+    fn previous_version(&self, commit: &CommitId, path: &PathId) -> Option<(CommitId, SnapshotId)> {
+        let position = match self.positions.get(commit) {
+            Some(position) => *position,
+            None => return self.history.previous_version(commit, path),
+        };
+        let changes = self.versions.get(path)?;
+        let older = changes.partition_point(|(change_position, _, _)| *change_position <= position);
+        changes.get(older).and_then(|(_, commit, snapshot)| snapshot.map(|snapshot| (*commit, snapshot)))
+    }
+}
+
+/**
+ * Counts the non-blank lines of every file that exists at the commit by the commit that last changed
+ * them, following first parents only.
+ */
+pub(crate) fn surviving_lines<H: PathHistory>(history: &H, head: &CommitId, changes: impl Fn(&CommitId) -> Vec<(PathId, Option<SnapshotId>)>) -> BTreeMap<CommitId, usize> {
+    let history = FirstParentHistory::new(history, head, changes);
+    let mut counts: BTreeMap<CommitId, usize> = BTreeMap::new();
+    for (path, commit, snapshot) in history.tree() {
+        let contents = match history.lines(&snapshot) { Some(contents) => contents, None => continue };
+        let non_blank: Vec<usize> = (0..contents.len()).filter(|line| !contents[*line].trim().is_empty()).collect();
+        for (_, origin) in blame_lines(&history, &commit, &path, &snapshot, &non_blank) {
+            *counts.entry(origin).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/**
+ * Determines the commit that introduced each of the given lines of a snapshot of the path. The
+ * snapshot is the version of the path created by the commit. Lines are followed back through
+ * successive versions of the path for as long as they remain unchanged; a line is attributed to
+ * the commit whose version first contains it.
+ */
+pub(crate) fn blame_lines<H: PathHistory>(history: &H, commit: &CommitId, path: &PathId, snapshot: &SnapshotId, lines: &[usize]) -> BTreeMap<usize, CommitId> {
+    let mut origins: BTreeMap<usize, CommitId> = BTreeMap::new();
+
+    // Lines being followed: line number in the current version -> line number in the original.
+    let mut tracked: BTreeMap<usize, usize> = lines.iter().map(|line| (*line, *line)).collect();
+    let (mut commit, mut snapshot) = (*commit, *snapshot);
+
+    while !tracked.is_empty() {
+        let line_map = match history.line_map(&commit, path, &snapshot) {
+            Some(line_map) => line_map,
+            None => {
+                origins.extend(tracked.values().map(|original| (*original, commit)));
+                break;
+            }
+        };
+
+        let mut still_tracked = BTreeMap::new();
+        for (line, original) in tracked {
+            match line_map.mapping.get(line).copied().flatten() {
+                Some(previous_line) => { still_tracked.insert(previous_line, original); }
+                None => { origins.insert(original, commit); }
+            }
+        }
+
+        tracked = still_tracked;
+        commit = line_map.previous_commit;
+        snapshot = line_map.previous_snapshot;
+    }
+    origins
+}
+
+/**
+ * The SZZ algorithm (Śliwerski, Zimmermann, and Zeller, 2005): the commits that introduced the bug
+ * fixed by the given commit. For each file the fix modifies, the lines the fix deletes or changes
+ * are blamed on the commits that last touched them before the fix. Blank lines are ignored, and so
+ * are files that the fix creates, since they contain no lines to blame. Merge commits are not
+ * analyzed.
+ */
+pub(crate) fn bug_introducing_commits<H: PathHistory>(history: &H, fix: &CommitId, changes: &[(PathId, Option<SnapshotId>)]) -> Vec<CommitId> {
+    if history.parents(fix).len() != 1 { return Vec::new() }
+
+    let mut introducing: Vec<CommitId> = Vec::new();
+    for (path, snapshot) in changes {
+        let (previous_commit, previous_snapshot, mapping) = match snapshot {
+            Some(snapshot) => match history.line_map(fix, path, snapshot) {
+                Some(line_map) => (line_map.previous_commit, line_map.previous_snapshot, Some(line_map.mapping)),
+                None => continue,
+            },
+            None => match history.previous_version(fix, path) {
+                Some((previous_commit, previous_snapshot)) => (previous_commit, previous_snapshot, None),
+                None => continue,
+            },
+        };
+        let previous_contents = match history.lines(&previous_snapshot) { Some(contents) => contents, None => continue };
+
+        let kept: BTreeSet<usize> = mapping.iter().flat_map(|mapping| mapping.iter().flatten().copied()).collect();
+        let deleted: Vec<usize> = (0..previous_contents.len())
+            .filter(|line| !kept.contains(line) && !previous_contents[*line].trim().is_empty())
+            .collect();
+        if deleted.is_empty() { continue }
+
+        let origins = blame_lines(history, &previous_commit, path, &previous_snapshot, &deleted);
+        introducing.extend(origins.into_iter().map(|(_, commit)| commit));
+    }
+    introducing.sort();
+    introducing.dedup();
+    introducing
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use crate::blame::*;
+    use crate::objects::{CommitId, PathId, SnapshotId};
+
+    // A linear history of one file: each commit i has parent i - 1 and sets the file to snapshot i.
+    struct Linear { versions: BTreeMap<u64, Vec<&'static str>> }
+    impl PathHistory for Linear {
+        fn parents(&self, commit: &CommitId) -> Vec<CommitId> {
+            if commit.0 == 0 { vec![] } else { vec![CommitId(commit.0 - 1)] }
+        }
+        fn change(&self, commit: &CommitId, _: &PathId) -> Option<Option<SnapshotId>> {
+            self.versions.get(&commit.0).map(|_| Some(SnapshotId(commit.0)))
+        }
+        fn lines(&self, snapshot: &SnapshotId) -> Option<Vec<String>> {
+            self.versions.get(&snapshot.0).map(|lines| lines.iter().map(|line| line.to_string()).collect())
+        }
+    }
+
+    fn history() -> Linear {
+        let mut versions = BTreeMap::new();
+        versions.insert(0, vec!["fn f() {", "}"]);
+        versions.insert(1, vec!["fn f() {", "    let x = 1;", "}"]);
+        versions.insert(2, vec!["fn f() {", "    let x = 1;", "    x / 0", "}"]);
+        versions.insert(3, vec!["// f", "fn f() {", "    let x = 1;", "    x / 0", "}"]);
+        versions.insert(4, vec!["// f", "fn f() {", "    let x = 1;", "    x / 1", "}"]);
+        Linear { versions }
+    }
+
+    #[test] fn test_blame_lines() {
+        let origins = blame_lines(&history(), &CommitId(3), &PathId(0), &SnapshotId(3), &[0, 1, 2, 3, 4]);
+        let origins: Vec<u64> = origins.values().map(|commit| commit.0).collect();
+        assert_eq!(origins, vec![3, 0, 1, 2, 0]);
+    }
+
+    #[test] fn test_bug_introducing_commits() {
+        let fix = vec![(PathId(0), Some(SnapshotId(4)))];
+        assert_eq!(bug_introducing_commits(&history(), &CommitId(4), &fix), vec![CommitId(2)]);
+    }
+
+    // Counts how many times snapshots are read.
+    struct Counting { history: Linear, reads: std::cell::Cell<usize> }
+    impl PathHistory for Counting {
+        fn parents(&self, commit: &CommitId) -> Vec<CommitId> { self.history.parents(commit) }
+        fn change(&self, commit: &CommitId, path: &PathId) -> Option<Option<SnapshotId>> { self.history.change(commit, path) }
+        fn lines(&self, snapshot: &SnapshotId) -> Option<Vec<String>> {
+            self.reads.set(self.reads.get() + 1);
+            self.history.lines(snapshot)
+        }
+    }
+
+    #[test] fn test_memoized_history() {
+        let counting = Counting { history: history(), reads: std::cell::Cell::new(0) };
+        let memoized = MemoizedHistory::new(&counting, 100);
+        let fix = vec![(PathId(0), Some(SnapshotId(4)))];
+        assert_eq!(bug_introducing_commits(&memoized, &CommitId(4), &fix), vec![CommitId(2)]);
+        let reads = counting.reads.get();
+        assert_eq!(bug_introducing_commits(&memoized, &CommitId(4), &fix), vec![CommitId(2)]);
+        assert_eq!(counting.reads.get(), reads);
+        assert!(reads <= 5);
+
+        let tiny = MemoizedHistory::new(&counting, 1);
+        assert_eq!(bug_introducing_commits(&tiny, &CommitId(4), &fix), vec![CommitId(2)]);
+    }
+
+    #[test] fn test_blame_file() {
+        let history = history();
+        let blamed: Vec<u64> = blame_file(&history, &CommitId(4), &PathId(0)).unwrap().iter().map(|(commit, _)| commit.0).collect();
+        assert_eq!(blamed, vec![3, 0, 1, 4, 0]);
+        let changes = |commit: &CommitId| vec![(PathId(0), Some(SnapshotId(commit.0)))];
+        let counts: Vec<(u64, usize)> = surviving_lines(&history, &CommitId(4), changes).into_iter().map(|(commit, count)| (commit.0, count)).collect();
+        assert_eq!(counts, vec![(0, 2), (1, 1), (3, 1), (4, 1)]);
+    }
+}
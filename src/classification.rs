@@ -0,0 +1,171 @@
+use anyhow::*;
+use itertools::Itertools;
+use regex::Regex;
+
+use crate::objects::CommitCategory;
+
+/** A classification rule: commits whose message matches the pattern belong to the category. */
+#[derive(Clone, Debug)]
+pub struct CommitRule {
+    pub category: CommitCategory,
+    pub pattern: Regex,
+}
+
+/**
+ * An ordered list of rules for classifying commits by their messages. The first rule whose pattern
+ * matches a message determines the category; messages that match no rule are `Other`.
+ *
+ * The default rules recognize, in this order: reverts (`Revert "..."`, `This reverts commit ...`),
+ * merges (`Merge pull request #N`, `Merge branch ...`), Conventional Commits prefixes (`fix:`,
+ * `feat(parser)!:`, ...), and finally keywords in the message, bug fixes first. Words like "error"
+ * or "wrong" only mark a bug fix if nothing else matched, so "Add error handling" is a feature but
+ * "Fix error handling" is a fix. Custom rule sets can be built from scratch with `CommitRules::new`
+ * or by adding rules to the defaults:
+ *
+ * ```ignore
+ * let rules = CommitRules::new()
+ *     .with_rule(CommitCategory::BugFix, r"(?i)\bhotfix\b")?
+ *     .with_rules(CommitRules::default());
+ * ```
+ */
+#[derive(Clone, Debug)]
+pub struct CommitRules {
+    rules: Vec<CommitRule>,
+}
+
+static DEFAULT_RULES: [(CommitCategory, &'static str); 25] = [
+    (CommitCategory::Revert,        r#"(?i)^revert\b|\bthis reverts commit [0-9a-f]{7,40}"#),
+    (CommitCategory::Merge,         r#"^Merge (pull request|branch|remote-tracking branch|tag|commit)\b"#),
+
+    (CommitCategory::BugFix,        r#"(?i)^(fix|bugfix|hotfix)(\([^)]*\))?!?:"#),
+    (CommitCategory::Feature,       r#"(?i)^(feat|feature)(\([^)]*\))?!?:"#),
+    (CommitCategory::Refactor,      r#"(?i)^refactor(\([^)]*\))?!?:"#),
+    (CommitCategory::Documentation, r#"(?i)^docs?(\([^)]*\))?!?:"#),
+    (CommitCategory::Test,          r#"(?i)^tests?(\([^)]*\))?!?:"#),
+    (CommitCategory::Build,         r#"(?i)^(build|deps)(\([^)]*\))?!?:"#),
+    (CommitCategory::CI,            r#"(?i)^ci(\([^)]*\))?!?:"#),
+    (CommitCategory::Style,         r#"(?i)^style(\([^)]*\))?!?:"#),
+    (CommitCategory::Performance,   r#"(?i)^perf(\([^)]*\))?!?:"#),
+    (CommitCategory::Chore,         r#"(?i)^(chore|release)(\([^)]*\))?!?:"#),
+    (CommitCategory::Revert,        r#"(?i)^revert(\([^)]*\))?!?:"#),
+
+    (CommitCategory::BugFix,        r#"(?i)\b(fix(es|ed|ing)?|bugs?|bugfix|hotfix|defects?|faults?|crash(es|ed)?|broken|regression)\b"#),
+    (CommitCategory::BugFix,        r#"(?i)\b(close[sd]?|resolve[sd]?)\s+#\d+"#),
+    (CommitCategory::Performance,   r#"(?i)\b(perf|performance|speed ?up|faster|optimi[sz](e|ed|es|ation))\b"#),
+    (CommitCategory::Refactor,      r#"(?i)\b(refactor(ed|ing|s)?|restructur(e|ed|ing)|clean ?up|cleaned up|simplif(y|ied|ies)|renam(e|ed|ing))\b"#),
+    (CommitCategory::Test,          r#"(?i)\b(unit ?tests?|tests?|testing|specs?|coverage)\b"#),
+    (CommitCategory::Documentation, r#"(?i)\b(docs?|documentation|readme|changelog|typos?|comments?)\b"#),
+    (CommitCategory::CI,            r#"(?i)\b(ci|travis|jenkins|github actions|workflows?|circleci)\b"#),
+    (CommitCategory::Build,         r#"(?i)\b(build|bump(ed)?|upgrade[sd]?|dependenc(y|ies)|makefile|pom\.xml|package\.json)\b"#),
+    (CommitCategory::Style,         r#"(?i)\b(format(ted|ting)?|whitespace|indentation|lint(ing)?|style)\b"#),
+    (CommitCategory::Feature,       r#"(?i)\b(add(s|ed|ing)?|implement(s|ed|ing)?|introduc(e|es|ed|ing)|support(s|ed)?|new|feature)\b"#),
+    (CommitCategory::BugFix,        r#"(?i)\b(errors?|fail(s|ed|ure|ing)?|incorrect|wrong)\b"#),
+    (CommitCategory::Chore,         r#"(?i)\b(chore|release|version|merge)\b"#),
+];
+
+impl CommitRules {
+    /** An empty rule set, which classifies every commit as `Other`. */
+    pub fn new() -> Self {
+        CommitRules { rules: Vec::new() }
+    }
+
+    /** Adds a rule after all the existing ones. Fails if the pattern is not a valid regex. */
+    pub fn with_rule(mut self, category: CommitCategory, pattern: &str) -> Result<Self> {
+        let pattern = Regex::new(pattern)
+            .with_context(|| format!("Invalid pattern for commit category {}: {}", category, pattern))?;
+        self.rules.push(CommitRule { category, pattern });
+        Ok(self)
+    }
+
+    /** Adds all the rules of another rule set after the existing ones. */
+    pub fn with_rules(mut self, other: CommitRules) -> Self {
+        self.rules.extend(other.rules);
+        self
+    }
+
+    pub fn rules(&self) -> &Vec<CommitRule> { &self.rules }
+
+    pub fn classify(&self, message: &str) -> CommitCategory {
+        let message = message.trim();
+        self.rules.iter()
+            .find(|rule| rule.pattern.is_match(message))
+            .map_or(CommitCategory::Other, |rule| rule.category)
+    }
+}
+
+impl Default for CommitRules {
+    fn default() -> Self {
+        let rules = DEFAULT_RULES.iter()
+            .map(|(category, pattern)| CommitRule { category: *category, pattern: Regex::new(pattern).unwrap() })
+            .collect();
+        CommitRules { rules }
+    }
+}
+
+/**
+ * Extracts references to issues and pull requests from a commit message: `#123`,
+ * `owner/repo#123`, and `GH-123`. References are returned in order of appearance, without
+ * duplicates.
+ */
+pub struct IssueReferences { pattern: Regex }
+
+impl IssueReferences {
+    pub fn new() -> Self {
+        IssueReferences { pattern: Regex::new(r"(?:^|[^\w&/#])((?:[\w.-]+/[\w.-]+)?#\d+|GH-\d+)\b").unwrap() }
+    }
+    pub fn find(&self, message: &str) -> Vec<String> {
+        self.pattern.captures_iter(message)
+            .map(|captures| captures[1].to_string())
+            .unique()
+            .collect()
+    }
+}
+
+/**
+ * Whether a commit fixes a bug, for the purposes of SZZ: either its message classifies it as a
+ * bug fix, or it references an issue and is not otherwise recognizable (most referenced issues are
+ * bug reports).
+ */
+pub fn is_bug_fix(category: CommitCategory, issue_refs: &[String]) -> bool {
+    category == CommitCategory::BugFix || (category == CommitCategory::Other && !issue_refs.is_empty())
+}
+
+pub fn classify(message: &str) -> CommitCategory {
+    CommitRules::default().classify(message)
+}
+
+pub fn issue_refs(message: &str) -> Vec<String> {
+    IssueReferences::new().find(message)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::classification::*;
+    use crate::objects::CommitCategory;
+
+    #[test] fn test_classify() {
+        assert_eq!(classify("Revert \"Add parser\"\n\nThis reverts commit 1234abcd."), CommitCategory::Revert);
+        assert_eq!(classify("Merge pull request #12 from someone/branch"), CommitCategory::Merge);
+        assert_eq!(classify("feat(parser)!: support comments"), CommitCategory::Feature);
+        assert_eq!(classify("docs: fix typo"), CommitCategory::Documentation);
+        assert_eq!(classify("Fix crash when the input is empty"), CommitCategory::BugFix);
+        assert_eq!(classify("Refactor the lexer"), CommitCategory::Refactor);
+        assert_eq!(classify("Add error handling"), CommitCategory::Feature);
+        assert_eq!(classify("Fix error handling"), CommitCategory::BugFix);
+        assert_eq!(classify("Handle incorrect input"), CommitCategory::BugFix);
+        assert_eq!(classify("Initial commit"), CommitCategory::Other);
+    }
+
+    #[test] fn test_custom_rules() {
+        let rules = CommitRules::new().with_rule(CommitCategory::BugFix, r"(?i)\bhotfix\b").unwrap();
+        assert_eq!(rules.classify("Hotfix for release"), CommitCategory::BugFix);
+        assert_eq!(rules.classify("fix: something"), CommitCategory::Other);
+        assert!(CommitRules::new().with_rule(CommitCategory::BugFix, "(").is_err());
+    }
+
+    #[test] fn test_issue_refs() {
+        assert_eq!(issue_refs("Fixes #12, closes other/repo#3 and GH-7 (see #12)"),
+                   vec!["#12".to_string(), "other/repo#3".to_string(), "GH-7".to_string()]);
+        assert!(issue_refs("Use &#123; and color #fff").is_empty());
+    }
+}
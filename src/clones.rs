@@ -0,0 +1,439 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::objects::Language;
+use crate::sloc;
+
+/**
+ * Near-duplicate (type-2 clone) detection.
+ *
+ * Files are tokenized using the comment and string syntax of their language. Comments and
+ * whitespace are dropped, identifiers are replaced by `<id>` (keywords are kept), and literals by
+ * `<lit>`, so files that differ only in layout, comments, naming, or constants produce the same
+ * tokens. The tokens are split into overlapping shingles of `SHINGLE_SIZE` tokens and each file is
+ * summarized by a MinHash signature of its set of shingles. Two signatures agree on any position
+ * with probability equal to the Jaccard similarity of the shingle sets.
+ *
+ * Signatures are indexed with locality-sensitive hashing: the signature is cut into `BANDS` bands
+ * of `ROWS` rows, and files that agree on all rows of at least one band become candidates.
+ * Candidates are confirmed if their estimated similarity is at least `SIMILARITY_THRESHOLD`.
+ */
+pub const SHINGLE_SIZE: usize = 5;
+pub const BANDS: usize = 16;
+pub const ROWS: usize = 8;
+pub const SIGNATURE_SIZE: usize = BANDS * ROWS;
+pub const SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/** Files with fewer tokens than this are too small to say anything meaningful about. */
+pub const MIN_TOKENS: usize = 50;
+
+/**
+ * Buckets holding more distinct signatures than this are not searched for candidates. Files that
+ * are similar enough to be near duplicates almost always share more than one band, so they are
+ * still found through a smaller bucket.
+ */
+pub const MAX_BUCKET_SIZE: usize = 1000;
+
+pub const IDENTIFIER: &'static str = "<id>";
+pub const LITERAL: &'static str = "<lit>";
+
+const C_KEYWORDS: &'static [&'static str] = &[
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else", "enum",
+    "extern", "float", "for", "goto", "if", "inline", "int", "long", "register", "return", "short",
+    "signed", "sizeof", "static", "struct", "switch", "typedef", "union", "unsigned", "void",
+    "volatile", "while", "bool", "true", "false", "class", "namespace", "template", "typename",
+    "public", "private", "protected", "virtual", "override", "new", "delete", "this", "try", "catch",
+    "throw", "using", "nullptr", "operator", "friend", "explicit", "constexpr", "include",
+    "define", "ifdef", "ifndef", "endif", "pragma",
+];
+const JAVA_KEYWORDS: &'static [&'static str] = &[
+    "abstract", "assert", "boolean", "break", "byte", "case", "catch", "char", "class", "const",
+    "continue", "default", "do", "double", "else", "enum", "extends", "final", "finally", "float",
+    "for", "if", "implements", "import", "instanceof", "int", "interface", "long", "native", "new",
+    "package", "private", "protected", "public", "return", "short", "static", "super", "switch",
+    "synchronized", "this", "throw", "throws", "transient", "try", "void", "volatile", "while",
+    "true", "false", "null", "var", "record", "yield", "fun", "val", "object", "when", "is", "in",
+    "override", "def", "namespace", "using", "string", "bool", "readonly", "async", "await", "get", "set",
+];
+const JAVASCRIPT_KEYWORDS: &'static [&'static str] = &[
+    "async", "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default",
+    "delete", "do", "else", "export", "extends", "finally", "for", "from", "function", "if", "import",
+    "in", "instanceof", "let", "new", "of", "return", "super", "switch", "this", "throw", "try",
+    "typeof", "var", "void", "while", "with", "yield", "true", "false", "null", "undefined",
+    "interface", "type", "enum", "implements", "private", "public", "protected", "readonly",
+    "static", "abstract", "as", "any", "number", "string", "boolean",
+];
+const PYTHON_KEYWORDS: &'static [&'static str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+    "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda",
+    "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield", "True",
+    "False", "None", "self",
+];
+const RUBY_KEYWORDS: &'static [&'static str] = &[
+    "alias", "and", "begin", "break", "case", "class", "def", "defined", "do", "else", "elsif", "end",
+    "ensure", "false", "for", "if", "in", "module", "next", "nil", "not", "or", "redo", "rescue",
+    "retry", "return", "self", "super", "then", "true", "undef", "unless", "until", "when", "while",
+    "yield", "require", "attr_accessor", "attr_reader",
+];
+const GO_KEYWORDS: &'static [&'static str] = &[
+    "break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough", "for",
+    "func", "go", "goto", "if", "import", "interface", "map", "package", "range", "return", "select",
+    "struct", "switch", "type", "var", "nil", "true", "false", "int", "string", "bool", "error",
+    "byte", "make", "len", "append",
+];
+const RUST_KEYWORDS: &'static [&'static str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+    "use", "where", "while", "Some", "None", "Ok", "Err",
+];
+const PHP_KEYWORDS: &'static [&'static str] = &[
+    "abstract", "array", "as", "break", "case", "catch", "class", "const", "continue", "default",
+    "do", "echo", "else", "elseif", "extends", "final", "finally", "fn", "for", "foreach", "function",
+    "global", "if", "implements", "include", "interface", "isset", "namespace", "new", "null",
+    "private", "protected", "public", "require", "return", "static", "switch", "this", "throw",
+    "trait", "try", "use", "while", "true", "false",
+];
+// For other languages: words that are keywords in many of them.
+const COMMON_KEYWORDS: &'static [&'static str] = &[
+    "if", "then", "else", "elif", "elsif", "end", "for", "foreach", "while", "do", "loop", "break",
+    "continue", "return", "function", "func", "fn", "def", "let", "var", "val", "const", "class",
+    "struct", "module", "import", "export", "package", "new", "this", "self", "true", "false",
+    "null", "nil", "none", "case", "switch", "match", "try", "catch", "finally", "throw", "raise",
+    "and", "or", "not", "in", "is", "public", "private", "static", "begin", "local", "where", "with",
+];
+
+/** The keywords of a language, which are kept as they are when identifiers are normalized. */
+pub fn keywords(language: Language) -> &'static [&'static str] {
+    use Language::*;
+    match language {
+        C | Cpp | ObjectiveC | ObjectiveCpp | Cuda | Arduino => C_KEYWORDS,
+        Java | Kotlin | Scala | Groovy | CSharp | Dart | Swift | Apex | AspectJ | Processing => JAVA_KEYWORDS,
+        JavaScript | TypeScript | CoffeeScript | Svelte | Vue => JAVASCRIPT_KEYWORDS,
+        Python | Starlark | Sage => PYTHON_KEYWORDS,
+        Ruby | Crystal => RUBY_KEYWORDS,
+        Go => GO_KEYWORDS,
+        Rust => RUST_KEYWORDS,
+        PHP | Hack => PHP_KEYWORDS,
+        _ => COMMON_KEYWORDS,
+    }
+}
+
+/**
+ * Splits the contents of a file into normalized tokens: keywords and punctuation are kept,
+ * identifiers become `<id>` and number and string literals become `<lit>`. Comments and whitespace
+ * are dropped.
+ */
+pub fn tokenize(contents: &str, language: Language) -> Vec<String> {
+    let syntax = sloc::comment_syntax(language);
+    let keywords = keywords(language);
+    let mut tokens = Vec::new();
+    let mut rest = contents;
+
+    'next: while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        for (start, end) in syntax.block.iter() {
+            if rest.starts_with(start) {
+                rest = &rest[start.len()..];
+                rest = rest.find(end).map_or("", |index| &rest[index + end.len()..]);
+                continue 'next;
+            }
+        }
+        if syntax.line.iter().any(|marker| rest.starts_with(marker)) {
+            rest = rest.find('\n').map_or("", |index| &rest[index..]);
+            continue;
+        }
+        if syntax.strings.contains(&c) {
+            rest = skip_string(&rest[c.len_utf8()..], c);
+            tokens.push(LITERAL.to_owned());
+            continue;
+        }
+        if c.is_ascii_digit() {
+            let length = rest.find(|c: char| !(c.is_alphanumeric() || c == '.' || c == '_')).unwrap_or(rest.len());
+            rest = &rest[length..];
+            tokens.push(LITERAL.to_owned());
+            continue;
+        }
+        if c.is_alphabetic() || c == '_' || c == '$' {
+            let length = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$')).unwrap_or(rest.len());
+            let word = &rest[..length];
+            rest = &rest[length..];
+            tokens.push(if keywords.contains(&word) { word.to_owned() } else { IDENTIFIER.to_owned() });
+            continue;
+        }
+        tokens.push(c.to_string());
+        rest = &rest[c.len_utf8()..];
+    }
+    tokens
+}
+
+// Skips to just after the closing quote, honoring backslash escapes.
+fn skip_string(rest: &str, quote: char) -> &str {
+    let mut characters = rest.char_indices();
+    while let Some((index, c)) = characters.next() {
+        if c == '\\' {
+            characters.next();
+        } else if c == quote {
+            return &rest[index + c.len_utf8()..];
+        }
+    }
+    ""
+}
+
+// FNV-1a: stable across platforms and compiler versions, unlike the standard library's hasher.
+fn fnv1a(bytes: impl Iterator<Item=u8>) -> u64 {
+    bytes.fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+// The finalizer of SplitMix64, used to derive independent hash functions from one shingle hash.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/**
+ * The MinHash signature of a sequence of tokens, or `None` if there are fewer than `MIN_TOKENS`
+ * tokens.
+ */
+pub fn signature(tokens: &[String]) -> Option<Vec<u64>> {
+    if tokens.len() < MIN_TOKENS { return None }
+    let shingles: BTreeSet<u64> = tokens.windows(SHINGLE_SIZE)
+        .map(|shingle| fnv1a(shingle.iter().flat_map(|token| token.bytes().chain(std::iter::once(0)))))
+        .collect();
+    Some((0..SIGNATURE_SIZE as u64).map(|seed| {
+        let seed = mix(seed.wrapping_add(0x9e3779b97f4a7c15));
+        shingles.iter().map(|shingle| mix(shingle ^ seed)).min().unwrap_or(u64::MAX)
+    }).collect())
+}
+
+/** Estimated Jaccard similarity of the shingle sets of two signatures. */
+pub fn similarity(a: &[u64], b: &[u64]) -> f64 {
+    similarity_of(a, b)
+}
+
+fn similarity_of<T>(a: &[T], b: &[T]) -> f64 where T: PartialEq {
+    if a.is_empty() { return 0.0 }
+    a.iter().zip(b.iter()).filter(|(a, b)| a == b).count() as f64 / a.len() as f64
+}
+
+/**
+ * Estimated Jaccard similarity of the shingle sets of two signatures that keep only the lowest
+ * byte of each row, corrected for rows that agree by accident.
+ */
+pub fn compact_similarity(a: &[u8], b: &[u8]) -> f64 {
+    let accidental = 1.0 / 256.0;
+    ((similarity_of(a, b) - accidental) / (1.0 - accidental)).max(0.0)
+}
+
+/**
+ * A locality-sensitive hashing index of MinHash signatures.
+ *
+ * Keys with identical signatures (boilerplate such as licenses or empty `__init__.py` files, which
+ * can be copied into tens of thousands of projects) are stored once, as a group, so that the index
+ * only ever compares distinct signatures. Only the lowest byte of every row of a signature is
+ * kept for comparison, which cuts the memory held per distinct signature from
+ * `SIGNATURE_SIZE` 64-bit hashes to `SIGNATURE_SIZE` bytes.
+ */
+pub struct NearDuplicateIndex<K> where K: Ord + Copy {
+    groups: HashMap<u64, usize>,
+    members: Vec<Vec<K>>,
+    signatures: Vec<Vec<u8>>,
+    buckets: HashMap<(usize, u64), Vec<usize>>,
+}
+
+impl<K> NearDuplicateIndex<K> where K: Ord + Copy {
+    pub fn new() -> Self {
+        NearDuplicateIndex {
+            groups: HashMap::new(), members: Vec::new(), signatures: Vec::new(), buckets: HashMap::new()
+        }
+    }
+
+    pub fn insert(&mut self, key: K, signature: Vec<u64>) {
+        let hash = fnv1a(signature.iter().flat_map(|row| row.to_le_bytes()));
+        let compact: Vec<u8> = signature.iter().map(|row| *row as u8).collect();
+        if let Some(&group) = self.groups.get(&hash) {
+            if self.signatures[group] == compact {
+                self.members[group].push(key);
+                return
+            }
+        }
+        let group = self.members.len();
+        self.groups.entry(hash).or_insert(group);
+        self.members.push(vec![key]);
+        self.signatures.push(compact);
+        for (band, rows) in signature.chunks(ROWS).enumerate() {
+            let hash = fnv1a(rows.iter().flat_map(|row| row.to_le_bytes()));
+            self.buckets.entry((band, hash)).or_insert_with(Vec::new).push(group);
+        }
+    }
+
+    /**
+     * For each key, the other keys whose estimated similarity to it is at least
+     * `SIMILARITY_THRESHOLD`, in order. Keys without near duplicates are left out.
+     *
+     * Signatures are only compared once per pair of groups of identical signatures, and the
+     * result is then expanded to every member of both groups.
+     */
+    pub fn near_duplicates(&self) -> BTreeMap<K, Vec<K>> {
+        let mut similar: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); self.members.len()];
+        let buckets = self.buckets.values()
+            .filter(|groups| groups.len() > 1 && groups.len() <= MAX_BUCKET_SIZE);
+        for groups in buckets {
+            for (i, a) in groups.iter().enumerate() {
+                for b in groups[i + 1..].iter() {
+                    if similar[*a].contains(b) { continue }
+                    if compact_similarity(&self.signatures[*a], &self.signatures[*b]) >= SIMILARITY_THRESHOLD {
+                        similar[*a].insert(*b);
+                        similar[*b].insert(*a);
+                    }
+                }
+            }
+        }
+
+        let mut near_duplicates: BTreeMap<K, Vec<K>> = BTreeMap::new();
+        for (group, members) in self.members.iter().enumerate() {
+            let mut keys: Vec<K> = std::iter::once(group).chain(similar[group].iter().map(|other| *other))
+                .flat_map(|group| self.members[group].iter().map(|key| *key))
+                .collect();
+            keys.sort();
+            keys.dedup();
+            for key in members.iter() {
+                let others: Vec<K> = keys.iter().filter(|other| *other != key).map(|other| *other).collect();
+                if !others.is_empty() {
+                    near_duplicates.insert(*key, others);
+                }
+            }
+        }
+        near_duplicates
+    }
+}
+
+/**
+ * Groups keys into clone classes: the connected components of the near-duplicate relation. Each
+ * key is mapped to the smallest key in its class.
+ */
+pub fn clone_classes<K>(near_duplicates: &BTreeMap<K, Vec<K>>) -> BTreeMap<K, K> where K: Ord + Copy {
+    let mut classes: BTreeMap<K, K> = BTreeMap::new();
+    for start in near_duplicates.keys() {
+        if classes.contains_key(start) { continue }
+        let mut members: Vec<K> = vec![*start];
+        let mut frontier: Vec<K> = vec![*start];
+        let mut visited: BTreeSet<K> = BTreeSet::new();
+        visited.insert(*start);
+        while let Some(key) = frontier.pop() {
+            for other in near_duplicates.get(&key).into_iter().flatten() {
+                if visited.insert(*other) {
+                    members.push(*other);
+                    frontier.push(*other);
+                }
+            }
+        }
+        let representative = *members.iter().min().unwrap();
+        classes.extend(members.into_iter().map(|member| (member, representative)));
+    }
+    classes
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use crate::clones::*;
+    use crate::objects::Language;
+
+    const ORIGINAL: &'static str = r#"
+        // Sums the positive elements of an array.
+        public static int sumPositive(int[] values) {
+            int total = 0;
+            for (int i = 0; i < values.length; i++) {
+                if (values[i] > 0) {
+                    total += values[i];
+                }
+            }
+            System.out.println("total: " + total);
+            return total;
+        }
+        public static int countPositive(int[] values) {
+            int count = 0;
+            for (int i = 0; i < values.length; i++) {
+                if (values[i] > 0) { count++; }
+            }
+            return count;
+        }
+    "#;
+
+    // The same code with different names, comments, constants, and layout.
+    const RENAMED: &'static str = r#"
+        /* Adds up the positives. */
+        public static int addPositives(int[] xs) { int acc = 0;
+            for (int j = 1; j < xs.length; j++) { if (xs[j] > 0) { acc += xs[j]; } }
+            System.out.println("sum = " + acc); return acc;
+        }
+        public static int positives(int[] xs) {
+            int n = 0;
+            for (int j = 0; j < xs.length; j++) { if (xs[j] > 0) { n++; } }
+            return n;
+        }
+    "#;
+
+    const UNRELATED: &'static str = r#"
+        class Point {
+            private final double x;
+            private final double y;
+            public Point(double x, double y) { this.x = x; this.y = y; }
+            public double distance(Point other) {
+                double dx = x - other.x, dy = y - other.y;
+                return Math.sqrt(dx * dx + dy * dy);
+            }
+            @Override public String toString() { return "(" + x + ", " + y + ")"; }
+        }
+    "#;
+
+    #[test] fn test_tokenize() {
+        let tokens = tokenize("int x = 42; // answer\nreturn \"x\" + x;", Language::Java);
+        assert_eq!(tokens, vec!["int", "<id>", "=", "<lit>", ";", "return", "<lit>", "+", "<id>", ";"]);
+        assert_eq!(tokenize(ORIGINAL, Language::Java), tokenize(RENAMED, Language::Java));
+    }
+
+    #[test] fn test_near_duplicates() {
+        let mut index = NearDuplicateIndex::new();
+        let modified = ORIGINAL.replace("return count;", "System.out.println(count);\n return count;");
+        for (key, contents) in vec![(1, ORIGINAL), (2, RENAMED), (3, UNRELATED), (4, modified.as_str())] {
+            index.insert(key, signature(&tokenize(contents, Language::Java)).unwrap());
+        }
+        let near_duplicates = index.near_duplicates();
+        assert_eq!(near_duplicates.get(&1), Some(&vec![2, 4]));
+        assert_eq!(near_duplicates.get(&3), None);
+    }
+
+    #[test] fn test_identical_signatures() {
+        let mut index = NearDuplicateIndex::new();
+        let modified = ORIGINAL.replace("return count;", "System.out.println(count);\n return count;");
+        for key in vec![4, 2, 1, 3] {
+            index.insert(key, signature(&tokenize(ORIGINAL, Language::Java)).unwrap());
+        }
+        for key in vec![6, 5] {
+            index.insert(key, signature(&tokenize(&modified, Language::Java)).unwrap());
+        }
+        let near_duplicates = index.near_duplicates();
+        for key in 1..=6 {
+            let others: Vec<u64> = (1..=6).filter(|other| *other != key).collect();
+            assert_eq!(near_duplicates.get(&key), Some(&others));
+        }
+        let classes: Vec<(u64, u64)> = clone_classes(&near_duplicates).into_iter().collect();
+        assert_eq!(classes, vec![(1, 1), (2, 1), (3, 1), (4, 1), (5, 1), (6, 1)]);
+    }
+
+    #[test] fn test_clone_classes() {
+        let mut near_duplicates = BTreeMap::new();
+        near_duplicates.insert(5, vec![3]);
+        near_duplicates.insert(3, vec![5, 8]);
+        near_duplicates.insert(8, vec![3]);
+        near_duplicates.insert(2, vec![9]);
+        near_duplicates.insert(9, vec![2]);
+        let classes: Vec<(u64, u64)> = clone_classes(&near_duplicates).into_iter().collect();
+        assert_eq!(classes, vec![(2, 2), (3, 3), (5, 3), (8, 3), (9, 2)]);
+    }
+}
@@ -12,9 +12,12 @@ use parasite;
 
 use crate::objects::*;
 use crate::fraction::*;
+use crate::ordf64::OrdF64;
 use crate::product::*;
 use crate::time::Duration;
 use crate::Store;
+use crate::Bin;
+use crate::attributes::Value;
 
 use crate::database::metadata::ProjectMetadata;
 
@@ -349,6 +352,11 @@ macro_rules! impl_csv_item_tuple {
 
 //--- generic CSV items ----------------------------------------------------------------------------
 
+impl CSVItem for () {
+    fn column_headers() -> Vec<&'static str> { vec![] }
+    fn row(&self) -> Vec<String> { vec![] }
+}
+
 impl<T> CSVItem for Option<T> where T: CSVItem {
     fn column_headers() -> Vec<&'static str> { T::column_headers() }
     fn row(&self) -> Vec<String> {
@@ -414,9 +422,56 @@ impl_csv_item_to_string!(f32, "n");
 impl_csv_item_quoted!(String, "string");
 
 impl_csv_item_to_string!(Language, "language");
+impl_csv_item_to_string!(LanguageKind, "language_kind");
+impl_csv_item_to_string!(Ecosystem, "ecosystem");
+impl_csv_item_to_string!(Tool, "tool");
+impl_csv_item_to_string!(CommitCategory, "category");
+impl_csv_item_to_string!(ToolKind, "tool_kind");
+impl CSVItem for Dependency {
+    fn column_headers() -> Vec<&'static str> { vec!["dependency", "version", "ecosystem"] }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.escape_quotes().quoted(),
+            self.version.as_ref().map_or_else(String::new, |version| version.escape_quotes().quoted()),
+            self.ecosystem.to_string(),
+        ]
+    }
+}
+impl CSVItem for DetectedLicense {
+    fn column_headers() -> Vec<&'static str> { vec!["license", "confidence", "path_id"] }
+    fn row(&self) -> Vec<String> {
+        vec![self.spdx.escape_quotes().quoted(), self.confidence.to_string(), self.path.to_string()]
+    }
+}
+impl CSVItem for LineCounts {
+    fn column_headers() -> Vec<&'static str> { vec!["code", "comment", "blank"] }
+    fn row(&self) -> Vec<String> {
+        vec![self.code.to_string(), self.comment.to_string(), self.blank.to_string()]
+    }
+}
+impl CSVItem for Diff {
+    fn column_headers() -> Vec<&'static str> { vec!["added", "removed", "similarity", "hunks"] }
+    fn row(&self) -> Vec<String> {
+        vec![self.added.to_string(), self.removed.to_string(), self.similarity.to_string(), self.hunks.len().to_string()]
+    }
+}
+// Rows of query results, whose columns are named by the query (see `query::Table::into_csv`).
+impl CSVItem for Vec<Value> {
+    fn column_headers() -> Vec<&'static str> { vec![] }
+    fn row(&self) -> Vec<String> {
+        self.iter().map(|value| match value {
+            Value::Null => String::new(),
+            Value::String(_) | Value::List(_) | Value::Tuple(_) => value.to_string().escape_quotes().quoted(),
+            value => value.to_string(),
+        }).collect()
+    }
+    fn rows(&self) -> Vec<Vec<String>> { vec![self.row()] }
+}
 impl_csv_item_to_string!(Store, "store");
 impl_csv_item_to_string!(Duration, "duration");
 impl_csv_item!(Fraction<N> where N: Fractionable -> "n", |selfie: &Fraction<N>| vec![selfie.as_fraction_string()]);
+impl_csv_item_to_string!(OrdF64, "n");
+impl_csv_item_to_string!(Bin, "bin");
 
 //--- parasite CSV items ---------------------------------------------------------------------------
 
@@ -445,8 +500,19 @@ impl_csv_item_with_data_inner!(f32);
 impl_csv_item_with_data_inner!(String);
 
 impl_csv_item_with_data_inner!(Language);
+impl_csv_item_with_data_inner!(LanguageKind);
+impl_csv_item_with_data_inner!(Ecosystem);
+impl_csv_item_with_data_inner!(Tool);
+impl_csv_item_with_data_inner!(CommitCategory);
+impl_csv_item_with_data_inner!(ToolKind);
+impl_csv_item_with_data_inner!(Dependency);
+impl_csv_item_with_data_inner!(DetectedLicense);
+impl_csv_item_with_data_inner!(LineCounts);
+impl_csv_item_with_data_inner!(Diff);
 impl_csv_item_with_data_inner!(Duration);
 impl_csv_item_with_data_inner!(Fraction<N> where N: Fractionable, Clone);
+impl_csv_item_with_data_inner!(OrdF64);
+impl_csv_item_with_data_inner!(Bin);
 
 //--- IDs as CSV items -----------------------------------------------------------------------------
 
@@ -680,6 +746,42 @@ impl<'a> CSVItem for ItemWithData<'a, Tree> {
     }
 }
 
+impl CSVItem for ForkNetwork {
+    fn column_headers() -> Vec<&'static str> { 
+        vec!["upstream_id", "fork_id", "divergence_commit_id", "unique_commits", "contributed_back"] 
+    }
+    fn row(&self) -> Vec<String> {
+        panic!("Attempting to convert a ForkNetwork object into a CSV row, but ForkNetwork cannot be expressed as a single row.");
+    }
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.forks.iter().map(|fork| {
+            vec![
+                self.upstream.to_string(),
+                fork.project.to_string(),
+                fork.divergence.to_string_or_empty(),
+                fork.unique_commits.to_string(),
+                fork.contributed_back.to_string(),
+            ]
+        }).collect()
+    }
+}
+impl_csv_item_with_data_inner!(ForkNetwork);
+
+impl CSVItem for Fork {
+    fn column_headers() -> Vec<&'static str> { 
+        vec!["fork_id", "divergence_commit_id", "unique_commits", "contributed_back"] 
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.project.to_string(),
+            self.divergence.to_string_or_empty(),
+            self.unique_commits.to_string(),
+            self.contributed_back.to_string(),
+        ]
+    }
+}
+impl_csv_item_with_data_inner!(Fork);
+
 impl CSVItem for Change {
     fn column_headers() -> Vec<&'static str> { vec!["path_id", "snapshot_id"] }
     fn row(&self) -> Vec<String> {
@@ -54,6 +54,17 @@ pub static CACHE_FILE_PROJECT_MAJOR_LANGUAGE_RATIO:   &'static str = "project_ma
 pub static CACHE_FILE_PROJECT_MAJOR_LANGUAGE_CHANGES: &'static str = "project_major_language_changes";
 pub static CACHE_FILE_PROJECT_ALL_FORKS:              &'static str = "project_all_forks";
 pub static CACHE_FILE_PROJECT_ALL_FORKS_COUNT:        &'static str = "project_all_forks_count";
+pub static CACHE_FILE_PROJECT_FORK_UPSTREAMS:         &'static str = "project_fork_upstreams";
+pub static CACHE_FILE_FORK_NETWORKS:                  &'static str = "fork_networks";
+pub static CACHE_FILE_PROJECT_TEAM_STRUCTURES:        &'static str = "project_team_structures";
+pub static CACHE_FILE_PROJECT_DEPENDENCIES:           &'static str = "project_dependencies";
+pub static CACHE_FILE_PROJECT_DEPENDENCIES_COUNT:     &'static str = "project_dependencies_count";
+pub static CACHE_FILE_DEPENDENCY_DEPENDENTS:          &'static str = "dependency_dependents";
+pub static CACHE_FILE_PROJECT_DETECTED_LICENSE:       &'static str = "project_detected_license";
+pub static CACHE_FILE_PROJECT_HEADER_LICENSES:        &'static str = "project_header_licenses";
+pub static CACHE_FILE_PROJECT_TOOLING:                &'static str = "project_tooling";
+pub static CACHE_FILE_PROJECT_TOOLING_COUNT:          &'static str = "project_tooling_count";
+pub static CACHE_FILE_PROJECT_TOOL_ADOPTION:          &'static str = "project_tool_adoption";
 pub static CACHE_FILE_PROJECT_HEAD_TREES:             &'static str = "project_head_trees";
 pub static CACHE_FILE_PROJECT_HEAD_TREES_COUNT:       &'static str = "project_head_trees_count";
 pub static CACHE_FILE_USERS:                          &'static str = "users";
@@ -70,10 +81,23 @@ pub static CACHE_FILE_USER_HINDEX1:                   &'static str = "user_h_ind
 pub static CACHE_FILE_USER_HINDEX2:                   &'static str = "user_h_index2";
 pub static CACHE_FILE_USER_PROJECT_IDS:               &'static str = "user_project_ids";
 pub static CACHE_FILE_USER_PROJECT_IDS_COUNT:         &'static str = "user_project_ids_count";
+pub static CACHE_FILE_USER_COLLABORATORS:             &'static str = "user_collaborators";
+pub static CACHE_FILE_USER_COLLABORATORS_COUNT:       &'static str = "user_collaborators_count";
+pub static CACHE_FILE_USER_PAGE_RANK:                 &'static str = "user_page_rank";
 pub static CACHE_FILE_PATHS:                          &'static str = "paths";
+pub static CACHE_FILE_PATH_LANGUAGES:                 &'static str = "path_languages";
+pub static CACHE_FILE_PATH_IS_TEST:                   &'static str = "path_is_test";
+pub static CACHE_FILE_COMMIT_TOUCHES_TESTS:           &'static str = "commit_touches_tests";
+pub static CACHE_FILE_PROJECT_TEST_CODE:              &'static str = "project_test_code";
 pub static CACHE_FILE_COMMITS:                        &'static str = "commits";
 pub static CACHE_FILE_COMMIT_HASHES:                  &'static str = "commit_hashes";
 pub static CACHE_FILE_COMMIT_MESSAGES:                &'static str = "commit_messages";
+pub static CACHE_FILE_COMMIT_CATEGORIES:              &'static str = "commit_categories";
+pub static CACHE_FILE_COMMIT_ISSUE_REFS:              &'static str = "commit_issue_refs";
+pub static CACHE_FILE_COMMIT_INTRODUCED_BUGS:         &'static str = "commit_introduced_bugs";
+pub static CACHE_FILE_PROJECT_BUG_FIX_RATIO:          &'static str = "project_bug_fix_ratio";
+pub static CACHE_FILE_PROJECT_LINE_AUTHORS:           &'static str = "project_line_authors";
+pub static CACHE_FILE_USER_SURVIVING_LINES:           &'static str = "user_surviving_lines";
 pub static CACHE_FILE_COMMIT_AUTHOR_TIMESTAMPS:       &'static str = "commit_author_timestamps";
 pub static CACHE_FILE_COMMIT_COMMITTER_TIMESTAMPS:    &'static str = "commit_committer_timestamps";
 pub static CACHE_FILE_COMMIT_CHANGES:                 &'static str = "commit_changes";
@@ -95,6 +119,11 @@ pub static CACHE_FILE_IS_ABANDONED:                   &'static str = "is_abandon
 pub static CACHE_FILE_SNAPSHOT_LOCS:                  &'static str = "snapshot_locs";  
 pub static CACHE_FILE_SNAPSHOT_HAS_CONTENTS:          &'static str = "snapshot_has_contents";  
 pub static CACHE_FILE_PROJECT_LOCS:                   &'static str = "project_locs";  
+pub static CACHE_FILE_SNAPSHOT_LINE_COUNTS:           &'static str = "snapshot_line_counts";
+pub static CACHE_FILE_PROJECT_LINE_COUNTS:            &'static str = "project_line_counts";
+pub static CACHE_FILE_SNAPSHOT_NEAR_DUPLICATES:       &'static str = "snapshot_near_duplicates";
+pub static CACHE_FILE_SNAPSHOT_CLONE_CLASSES:         &'static str = "snapshot_clone_classes";
+pub static CACHE_FILE_PROJECT_NEAR_DUPLICATION_RATIO: &'static str = "project_near_duplication_ratio";
 pub static CACHE_FILE_DUPLICATED_CODE:                &'static str = "duplicated_code";  
 pub static CACHE_FILE_PROJECT_IS_VALID:               &'static str = "project_is_valid";
 pub static CACHE_FILE_PROJECT_MAX_EXPERIENCE:         &'static str = "project_max_experience";
@@ -103,4 +132,5 @@ pub static CACHE_FILE_PROJECT_MAX_HINDEX2:            &'static str = "project_ma
 pub static CACHE_FILE_PROJECT_MAX_USER_LIFETIME:      &'static str = "project_max_user_lifetime";
 pub static CACHE_FILE_PROJECT_EXPERIENCE:             &'static str = "project_experience";
 pub static CACHE_COMMIT_TREES:                        &'static str = "commit_trees";
-pub static CACHE_COMMIT_PRECEDING_COMMITS:            &'static str = "commit_preceding_commits";
\ No newline at end of file
+pub static CACHE_COMMIT_PRECEDING_COMMITS:            &'static str = "commit_preceding_commits";
+pub static CACHE_FILE_CUSTOM_PREFIX:                  &'static str = "custom_";
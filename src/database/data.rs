@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use anyhow::*;
 
@@ -54,6 +54,17 @@ pub(crate) struct Data {
     project_major_language_changes: PersistentMap<ProjectMajorLanguageChangesExtractor>,
     project_all_forks:              PersistentMap<ProjectAllForksExtractor>,
     project_all_forks_count:        PersistentMap<CountPerKeyExtractor<ProjectId, ProjectId>>,
+    project_fork_upstreams:         PersistentMap<ProjectForkUpstreamExtractor>,
+    fork_networks:                  PersistentMap<ForkNetworkExtractor>,
+    project_team_structures:        PersistentMap<ProjectTeamStructureExtractor>,
+    project_dependencies:           PersistentMap<ProjectDependenciesExtractor>,
+    project_dependencies_count:     PersistentMap<CountPerKeyExtractor<ProjectId, Dependency>>,
+    dependency_dependents:          PersistentMap<DependencyDependentsExtractor>,
+    project_detected_license:       PersistentMap<ProjectDetectedLicenseExtractor>,
+    project_header_licenses:        PersistentMap<ProjectHeaderLicensesExtractor>,
+    project_tooling:                PersistentMap<ProjectToolingExtractor>,
+    project_tooling_count:          PersistentMap<CountPerKeyExtractor<ProjectId, Tool>>,
+    project_tool_adoption:          PersistentMap<ProjectToolAdoptionExtractor>,
     project_head_trees:             PersistentMap<ProjectHeadTreesExtractor>,
     project_head_trees_count:       PersistentMap<CountPerKeyExtractor<ProjectId, (String, Vec<(PathId, SnapshotId)>)>>,
 
@@ -93,16 +104,29 @@ pub(crate) struct Data {
     user_h_index2:               PersistentMap<UserHIndex2Extractor>,
     user_project_ids:            PersistentMap<UserProjectIdsExtractor>,
     user_project_ids_count:      PersistentMap<CountPerKeyExtractor<UserId, ProjectId>>,
+    user_collaborators:          PersistentMap<UserCollaboratorsExtractor>,
+    user_collaborators_count:    PersistentMap<CountPerKeyExtractor<UserId, UserId>>,
+    user_page_rank:              PersistentMap<UserPageRankExtractor>,
 
     user_authored_commit_count:  PersistentMap<CountPerKeyExtractor<UserId, CommitId>>,
     user_committed_commit_count: PersistentMap<CountPerKeyExtractor<UserId, CommitId>>,
 
     paths:                       PersistentMap<PathExtractor>,
+    path_languages:              PersistentMap<PathLanguagesExtractor>,
+    path_is_test:                PersistentMap<PathIsTestExtractor>,
+    commit_touches_tests:        PersistentMap<CommitTouchesTestsExtractor>,
+    project_test_code:           PersistentMap<ProjectTestCodeExtractor>,
     //snapshots:                   PersistentMap<SnapshotExtractor>,
 
     commits:                     PersistentMap<CommitExtractor>,
     commit_hashes:               PersistentMap<CommitHashExtractor>,
     commit_messages:             PersistentMap<CommitMessageExtractor>,
+    commit_categories:           PersistentMap<CommitCategoryExtractor>,
+    commit_issue_refs:           PersistentMap<CommitIssueRefsExtractor>,
+    commit_introduced_bugs:      PersistentMap<CommitIntroducedBugsExtractor>,
+    project_bug_fix_ratio:       PersistentMap<ProjectBugFixRatioExtractor>,
+    project_line_authors:        PersistentMap<ProjectLineAuthorsExtractor>,
+    user_surviving_lines:        PersistentMap<UserSurvivingLinesExtractor>,
     commit_author_timestamps:    PersistentMap<AuthorTimestampExtractor>,
     commit_committer_timestamps: PersistentMap<CommitterTimestampExtractor>,
     commit_changes:              PersistentMap<CommitChangesExtractor>,
@@ -134,6 +158,11 @@ pub(crate) struct Data {
     is_abandoned:                 PersistentMap<IsAbandonedExtractor>,
     snapshot_locs:                PersistentMap<SnapshotLocsExtractor>,
     project_locs:                 PersistentMap<ProjectLocsExtractor>,
+    snapshot_line_counts:         PersistentMap<SnapshotLineCountsExtractor>,
+    project_line_counts:          PersistentMap<ProjectLineCountsExtractor>,
+    snapshot_near_duplicates:     PersistentMap<SnapshotNearDuplicatesExtractor>,
+    snapshot_clone_classes:       PersistentMap<SnapshotCloneClassExtractor>,
+    project_near_duplication_ratio: PersistentMap<ProjectNearDuplicationRatioExtractor>,
     duplicated_code:              PersistentMap<DuplicatedCodeExtractor>,
     project_is_valid:             PersistentMap<ProjectIsValidExtractor>,
     project_latest_update_time:   PersistentMap<ProjectLatestUpdateTimeExtractor>,
@@ -206,6 +235,17 @@ impl Data {
             project_major_language_changes: PersistentMap::new(CACHE_FILE_PROJECT_MAJOR_LANGUAGE_CHANGES, log.clone(), dir.clone()),
             project_all_forks:              PersistentMap::new(CACHE_FILE_PROJECT_ALL_FORKS,              log.clone(), dir.clone()),
             project_all_forks_count:        PersistentMap::new(CACHE_FILE_PROJECT_ALL_FORKS_COUNT,        log.clone(), dir.clone()),
+            project_fork_upstreams:         PersistentMap::new(CACHE_FILE_PROJECT_FORK_UPSTREAMS,         log.clone(), dir.clone()),
+            fork_networks:                  PersistentMap::new(CACHE_FILE_FORK_NETWORKS,                  log.clone(), dir.clone()),
+            project_team_structures:        PersistentMap::new(CACHE_FILE_PROJECT_TEAM_STRUCTURES,        log.clone(), dir.clone()),
+            project_dependencies:           PersistentMap::new(CACHE_FILE_PROJECT_DEPENDENCIES,           log.clone(), dir.clone()),
+            project_dependencies_count:     PersistentMap::new(CACHE_FILE_PROJECT_DEPENDENCIES_COUNT,     log.clone(), dir.clone()),
+            dependency_dependents:          PersistentMap::new(CACHE_FILE_DEPENDENCY_DEPENDENTS,          log.clone(), dir.clone()),
+            project_detected_license:       PersistentMap::new(CACHE_FILE_PROJECT_DETECTED_LICENSE,       log.clone(), dir.clone()),
+            project_header_licenses:        PersistentMap::new(CACHE_FILE_PROJECT_HEADER_LICENSES,        log.clone(), dir.clone()),
+            project_tooling:                PersistentMap::new(CACHE_FILE_PROJECT_TOOLING,                log.clone(), dir.clone()),
+            project_tooling_count:          PersistentMap::new(CACHE_FILE_PROJECT_TOOLING_COUNT,          log.clone(), dir.clone()),
+            project_tool_adoption:          PersistentMap::new(CACHE_FILE_PROJECT_TOOL_ADOPTION,          log.clone(), dir.clone()),
             project_head_trees:             PersistentMap::new(CACHE_FILE_PROJECT_HEAD_TREES,             log.clone(), dir.clone()),
             project_head_trees_count:       PersistentMap::new(CACHE_FILE_PROJECT_HEAD_TREES_COUNT,       log.clone(), dir.clone()),
             users:                          PersistentMap::new(CACHE_FILE_USERS,                          log.clone(),dir.clone()).without_cache(),
@@ -222,10 +262,23 @@ impl Data {
             user_h_index2:                   PersistentMap::new(CACHE_FILE_USER_HINDEX2,                   log.clone(),dir.clone()),
             user_project_ids:               PersistentMap::new(CACHE_FILE_USER_PROJECT_IDS,               log.clone(),dir.clone()),
             user_project_ids_count:         PersistentMap::new(CACHE_FILE_USER_PROJECT_IDS_COUNT,         log.clone(),dir.clone()),
+            user_collaborators:             PersistentMap::new(CACHE_FILE_USER_COLLABORATORS,             log.clone(),dir.clone()),
+            user_collaborators_count:       PersistentMap::new(CACHE_FILE_USER_COLLABORATORS_COUNT,       log.clone(),dir.clone()),
+            user_page_rank:                 PersistentMap::new(CACHE_FILE_USER_PAGE_RANK,                 log.clone(),dir.clone()),
             paths:                          PersistentMap::new(CACHE_FILE_PATHS,                          log.clone(),dir.clone()).without_cache(),
+            path_languages:                 PersistentMap::new(CACHE_FILE_PATH_LANGUAGES,                 log.clone(),dir.clone()),
+            path_is_test:                   PersistentMap::new(CACHE_FILE_PATH_IS_TEST,                   log.clone(),dir.clone()),
+            commit_touches_tests:           PersistentMap::new(CACHE_FILE_COMMIT_TOUCHES_TESTS,           log.clone(),dir.clone()),
+            project_test_code:              PersistentMap::new(CACHE_FILE_PROJECT_TEST_CODE,              log.clone(),dir.clone()),
             commits:                        PersistentMap::new(CACHE_FILE_COMMITS,                        log.clone(),dir.clone()),
             commit_hashes:                  PersistentMap::new(CACHE_FILE_COMMIT_HASHES,                  log.clone(),dir.clone()).without_cache(),
             commit_messages:                PersistentMap::new(CACHE_FILE_COMMIT_MESSAGES,                log.clone(),dir.clone()).without_cache(),
+            commit_categories:              PersistentMap::new(CACHE_FILE_COMMIT_CATEGORIES,              log.clone(),dir.clone()),
+            commit_issue_refs:              PersistentMap::new(CACHE_FILE_COMMIT_ISSUE_REFS,              log.clone(),dir.clone()),
+            commit_introduced_bugs:         PersistentMap::new(CACHE_FILE_COMMIT_INTRODUCED_BUGS,         log.clone(),dir.clone()),
+            project_bug_fix_ratio:          PersistentMap::new(CACHE_FILE_PROJECT_BUG_FIX_RATIO,          log.clone(),dir.clone()),
+            project_line_authors:           PersistentMap::new(CACHE_FILE_PROJECT_LINE_AUTHORS,           log.clone(),dir.clone()),
+            user_surviving_lines:           PersistentMap::new(CACHE_FILE_USER_SURVIVING_LINES,           log.clone(),dir.clone()),
             commit_author_timestamps:       PersistentMap::new(CACHE_FILE_COMMIT_AUTHOR_TIMESTAMPS,       log.clone(),dir.clone()),
             commit_committer_timestamps:    PersistentMap::new(CACHE_FILE_COMMIT_COMMITTER_TIMESTAMPS,    log.clone(),dir.clone()),
             commit_changes:                 PersistentMap::new(CACHE_FILE_COMMIT_CHANGES,                 log.clone(),dir.clone()).without_cache(),
@@ -248,6 +301,11 @@ impl Data {
             snapshot_locs:                  PersistentMap::new(CACHE_FILE_SNAPSHOT_LOCS, log.clone(), dir.clone()),
             snapshot_has_contents:          PersistentMap::new(CACHE_FILE_SNAPSHOT_HAS_CONTENTS, log.clone(), dir.clone()),
             project_locs:                   PersistentMap::new(CACHE_FILE_PROJECT_LOCS, log.clone(), dir.clone()),
+            snapshot_line_counts:           PersistentMap::new(CACHE_FILE_SNAPSHOT_LINE_COUNTS, log.clone(), dir.clone()),
+            project_line_counts:            PersistentMap::new(CACHE_FILE_PROJECT_LINE_COUNTS, log.clone(), dir.clone()),
+            snapshot_near_duplicates:       PersistentMap::new(CACHE_FILE_SNAPSHOT_NEAR_DUPLICATES, log.clone(), dir.clone()),
+            snapshot_clone_classes:         PersistentMap::new(CACHE_FILE_SNAPSHOT_CLONE_CLASSES, log.clone(), dir.clone()),
+            project_near_duplication_ratio: PersistentMap::new(CACHE_FILE_PROJECT_NEAR_DUPLICATION_RATIO, log.clone(), dir.clone()),
             duplicated_code:                PersistentMap::new(CACHE_FILE_DUPLICATED_CODE, log.clone(), dir.clone()),
             project_is_valid:               PersistentMap::new(CACHE_FILE_PROJECT_IS_VALID, log.clone(), dir.clone()),
             project_max_experience:         PersistentMap::new(CACHE_FILE_PROJECT_MAX_EXPERIENCE, log.clone(), dir.clone()),
@@ -274,6 +332,12 @@ impl Data { // Prequincunx, sort of
     pub fn all_commit_ids(&mut self, source: &Source) -> Vec<CommitId> {
         self.smart_load_commits(source).keys().collect::<Vec<&CommitId>>().pirate()
     }
+    pub fn all_fork_networks(&mut self, source: &Source) -> Vec<ForkNetwork> {
+        self.smart_load_fork_networks(source).values().collect::<Vec<&ForkNetwork>>().pirate()
+    }
+    pub fn dependent_project_ids(&mut self, name: &String, source: &Source) -> Vec<ProjectId> {
+        self.smart_load_dependency_dependents(source).get(name).pirate().unwrap_or_default()
+    }
 }
 
 impl Data { // Quincunx, sort of
@@ -586,6 +650,50 @@ impl Data {
         self.smart_load_project_all_forks_count(source).get(id)
             .pirate()
     }
+    pub fn project_team_structure(& mut self, id: &ProjectId, source: &Source) -> Option<TeamStructure> {
+        self.smart_load_project_team_structures(source).get(id)
+            .pirate()
+    }
+    pub fn project_dependencies(& mut self, id: &ProjectId, source: &Source) -> Option<Vec<Dependency>> {
+        self.smart_load_project_dependencies(source).get(id)
+            .pirate()
+    }
+    pub fn project_dependency_count(& mut self, id: &ProjectId, source: &Source) -> Option<usize> {
+        self.smart_load_project_dependencies_count(source).get(id)
+            .pirate()
+    }
+    pub fn project_detected_license(& mut self, id: &ProjectId, source: &Source) -> Option<DetectedLicense> {
+        self.smart_load_project_detected_license(source).get(id)
+            .pirate()
+    }
+    pub fn project_tooling(& mut self, id: &ProjectId, source: &Source) -> Option<Vec<Tool>> {
+        self.smart_load_project_tooling(source).get(id)
+            .pirate()
+    }
+    pub fn project_tooling_count(& mut self, id: &ProjectId, source: &Source) -> Option<usize> {
+        self.smart_load_project_tooling_count(source).get(id)
+            .pirate()
+    }
+    pub fn project_tool_adoption(& mut self, id: &ProjectId, source: &Source) -> Option<Vec<(Tool, Timestamp)>> {
+        self.smart_load_project_tool_adoption(source).get(id)
+            .pirate()
+    }
+    pub fn project_header_licenses(& mut self, id: &ProjectId, source: &Source) -> Option<Vec<(String, usize)>> {
+        self.smart_load_project_header_licenses(source).get(id)
+            .pirate()
+    }
+    pub fn project_fork_upstream(& mut self, id: &ProjectId, source: &Source) -> Option<ProjectId> {
+        self.smart_load_project_fork_upstreams(source).get(id)
+            .pirate()
+    }
+    pub fn project_fork_network(& mut self, id: &ProjectId, source: &Source) -> Option<ForkNetwork> {
+        self.project_fork_upstream(id, source)
+            .and_then(|upstream| self.fork_network(&upstream, source))
+    }
+    pub fn fork_network(& mut self, upstream: &ProjectId, source: &Source) -> Option<ForkNetwork> {
+        self.smart_load_fork_networks(source).get(upstream)
+            .pirate()
+    }
     pub fn project_head_trees(& mut self, id: &ProjectId, source: &Source) -> Option<Vec<(String, Vec<(PathId, SnapshotId)>)>> {
         self.smart_load_project_head_trees(source).get(id)
             .pirate()
@@ -600,6 +708,12 @@ impl Data {
     pub fn path(&mut self, id: &PathId, source: &Source) -> Option<Path> {
         self.smart_load_paths(source).get(id).pirate()
     }
+    pub fn path_language(&mut self, id: &PathId, source: &Source) -> Option<Language> {
+        self.smart_load_path_languages(source).get(id).pirate()
+    }
+    pub fn path_is_test(&mut self, id: &PathId, source: &Source) -> Option<bool> {
+        self.smart_load_path_is_test(source).get(id).pirate()
+    }
     pub fn commit(&mut self, id: &CommitId, source: &Source) -> Option<Commit> {
         self.smart_load_commits(source).get(id).pirate()
     }
@@ -609,6 +723,29 @@ impl Data {
     pub fn commit_message(&mut self, id: &CommitId, source: &Source) -> Option<String> {
         self.smart_load_commit_messages(source).get(id).pirate()
     }
+    pub fn commit_category(&mut self, id: &CommitId, source: &Source) -> Option<CommitCategory> {
+        self.smart_load_commit_categories(source).get(id).pirate()
+    }
+    pub fn commit_issue_refs(&mut self, id: &CommitId, source: &Source) -> Option<Vec<String>> {
+        self.smart_load_commit_issue_refs(source).get(id).pirate()
+    }
+    pub fn commit_introduced_bug_fix_ids(&mut self, id: &CommitId, source: &Source) -> Vec<CommitId> {
+        self.smart_load_commit_introduced_bugs(source).get(id).pirate().unwrap_or_default()
+    }
+    pub fn project_bug_fix_ratio(&mut self, id: &ProjectId, source: &Source) -> Option<f64> {
+        self.smart_load_project_bug_fix_ratio(source).get(id).pirate()
+    }
+    pub fn project_line_author_ids(&mut self, id: &ProjectId, source: &Source) -> Option<Vec<(UserId, usize)>> {
+        self.smart_load_project_line_authors(source).get(id).pirate()
+    }
+    pub fn project_authorship_shares(&mut self, id: &ProjectId, source: &Source) -> Option<Vec<(User, f64)>> {
+        self.smart_load_project_line_authors(source).get(id).pirate().map(|line_authors| {
+            let total: usize = line_authors.iter().map(|(_, lines)| *lines).sum();
+            line_authors.iter().flat_map(|(user_id, lines)| {
+                self.user(user_id, source).map(|user| (user.clone(), *lines as f64 / total as f64))
+            }).collect()
+        })
+    }
     pub fn commit_author_timestamp(&mut self, id: &CommitId, source: &Source) -> Option<Timestamp> {
         self.smart_load_commit_author_timestamps(source).get(id).pirate()
     }
@@ -618,14 +755,14 @@ impl Data {
     pub fn commit_changes(&mut self, id: &CommitId, source: &Source) -> Option<Vec<Change>> {
         self.smart_load_commit_changes(source).get(id).map(|vector| {
             vector.iter().map(|(path_id, snapshot_id)| {
-                Change::new(path_id.clone(), snapshot_id.clone())
+                Change::in_commit(*id, path_id.clone(), snapshot_id.clone())
             }).collect()
         })
     }
     pub fn commit_changes_with_contents(&mut self, id: &CommitId, source: &Source) -> Option<Vec<Change>> {
         self.smart_load_commit_changes_with_contents(source).get(id).map(|vector| {
             vector.iter().map(|(path_id, snapshot_id)| {
-                Change::new(path_id.clone(), snapshot_id.clone())
+                Change::in_commit(*id, path_id.clone(), snapshot_id.clone())
             }).collect()
         })
     }
@@ -654,6 +791,9 @@ impl Data {
     pub fn commit_languages(&mut self, id: &CommitId, source: &Source) -> Option<Vec<Language>> {
         self.smart_load_commit_languages(source).get(id).pirate()   
     }
+    pub fn commit_touches_tests(&mut self, id: &CommitId, source: &Source) -> Option<bool> {
+        self.smart_load_commit_touches_tests(source).get(id).pirate()
+    }
     pub fn commit_preceding_commit_ids(&mut self, id: &CommitId, source: &Source) -> Vec<CommitId> {
         if let Some(value) = self.commit_preceding_commits.get_if_loaded(id.clone()) {
             return value.clone()
@@ -724,6 +864,9 @@ impl Data {
     pub fn user_lifetime(& mut self, id: &UserId, source: &Source) -> Option<(i64,i64)> {
         self.smart_load_user_lifetime(source).get(id).pirate()
     }
+    pub fn user_surviving_lines(&mut self, id: &UserId, source: &Source) -> Option<usize> {
+        self.smart_load_user_surviving_lines(source).get(id).pirate()
+    }
     pub fn user_h_index1(& mut self, id: &UserId, source: &Source) -> Option<u64> {
         self.smart_load_user_h_index1(source).get(id).pirate()
     }
@@ -736,6 +879,64 @@ impl Data {
     pub fn user_project_ids_count(& mut self, id : &UserId, source: &Source) -> Option<usize> {
         self.smart_load_user_project_ids_count(source).get(id).pirate()
     }
+    pub fn user_collaborator_ids(& mut self, id: &UserId, source: &Source) -> Option<Vec<UserId>> {
+        self.smart_load_user_collaborators(source).get(id).pirate()
+    }
+    pub fn user_collaborators(& mut self, id: &UserId, source: &Source) -> Option<Vec<User>> {
+        self.user_collaborator_ids(id, source).map(|ids| {
+            ids.iter().flat_map(|id| self.user(id, source)).collect()
+        })
+    }
+    pub fn user_collaborator_count(& mut self, id: &UserId, source: &Source) -> Option<usize> {
+        self.smart_load_user_collaborators_count(source).get(id).pirate()
+    }
+    pub fn user_collaborator_ids_between(& mut self, id: &UserId, since: Timestamp, until: Timestamp, source: &Source) -> Option<Vec<UserId>> {
+        self.smart_load_user_authored_commits(source);
+        self.smart_load_commit_projects(source);
+        self.smart_load_project_commits(source);
+        self.smart_load_commit_author_timestamps(source);
+        self.smart_load_commits(source);
+
+        let timestamps = self.commit_author_timestamps.grab_collection();
+        let in_window = |commit_id: &CommitId| -> bool {
+            timestamps.get(commit_id).map_or(false, |timestamp| *timestamp >= since && *timestamp < until)
+        };
+
+        let commit_projects = self.commit_projects.grab_collection();
+        let project_ids: BTreeSet<ProjectId> = self.user_authored_commits.grab_collection().get(id)?.iter()
+            .filter(|commit_id| in_window(*commit_id))
+            .flat_map(|commit_id| commit_projects.get(commit_id))
+            .flatten()
+            .map(|project_id| *project_id)
+            .collect();
+
+        let project_commits = self.project_commits.grab_collection();
+        let commits = self.commits.grab_collection();
+        let collaborators: BTreeSet<UserId> = project_ids.iter()
+            .flat_map(|project_id| project_commits.get(project_id))
+            .flatten()
+            .filter(|commit_id| in_window(*commit_id))
+            .flat_map(|commit_id| commits.get(commit_id))
+            .map(|commit| commit.author)
+            .filter(|author| author != id)
+            .collect();
+
+        Some(collaborators.into_iter().collect())
+    }
+    pub fn user_centrality(& mut self, id: &UserId, measure: CentralityMeasure, source: &Source) -> Option<f64> {
+        match measure {
+            CentralityMeasure::Degree => {
+                let user_collaborators = self.smart_load_user_collaborators(source);
+                let others = user_collaborators.len().saturating_sub(1);
+                user_collaborators.get(id).map(|collaborators| {
+                    if others == 0 { 0f64 } else { collaborators.len() as f64 / others as f64 }
+                })
+            }
+            CentralityMeasure::PageRank => {
+                self.smart_load_user_page_rank(source).get(id).pirate()
+            }
+        }
+    }
     pub fn project_max_commit_delta(&mut self, id: &ProjectId, source: &Source) -> Option<i64> {
         self.smart_load_project_max_commit_delta(source).get(id).pirate()
     }
@@ -793,6 +994,24 @@ impl Data {
     pub fn project_locs(&mut self, id: &ProjectId, source: &Source) -> Option<usize> {
         self.smart_load_project_locs(source).get(id).pirate()
     }
+    pub fn snapshot_line_counts(&mut self, id: &SnapshotId, source: &Source) -> Option<LineCounts> {
+        self.smart_load_snapshot_line_counts(source).get(id).pirate()
+    }
+    pub fn project_line_counts(&mut self, id: &ProjectId, source: &Source) -> Option<LineCounts> {
+        self.smart_load_project_line_counts(source).get(id).pirate()
+    }
+    pub fn snapshot_near_duplicate_ids(&mut self, id: &SnapshotId, source: &Source) -> Vec<SnapshotId> {
+        self.smart_load_snapshot_near_duplicates(source).get(id).pirate().unwrap_or_default()
+    }
+    pub fn snapshot_clone_class(&mut self, id: &SnapshotId, source: &Source) -> Option<SnapshotId> {
+        self.smart_load_snapshot_clone_classes(source).get(id).pirate()
+    }
+    pub fn project_near_duplication_ratio(&mut self, id: &ProjectId, source: &Source) -> Option<f64> {
+        self.smart_load_project_near_duplication_ratio(source).get(id).pirate()
+    }
+    pub fn project_test_code(&mut self, id: &ProjectId, source: &Source) -> Option<(usize, usize, usize)> {
+        self.smart_load_project_test_code(source).get(id).pirate()
+    }
     pub fn project_duplicated_code(&mut self, id: &ProjectId, source: &Source) -> Option<f64> {
         self.smart_load_project_duplicated_code(source).get(id).pirate()
     }
@@ -912,7 +1131,7 @@ impl Data {
         load_with_prerequisites!(self, project_files, source, two, project_commits, commit_changes)
     }
     fn smart_load_project_languages(& mut self, source: &Source) -> &BTreeMap<ProjectId, Vec<(Language,usize)>> {
-        load_with_prerequisites!(self, project_languages, source, three, project_commits, commit_changes, paths)
+        load_with_prerequisites!(self, project_languages, source, three, project_commits, commit_changes, path_languages)
     }
     fn smart_load_project_languages_count(& mut self, source: &Source) -> &BTreeMap<ProjectId, usize> {
         load_with_prerequisites!(self, project_languages_count, source, one, project_languages)
@@ -932,6 +1151,39 @@ impl Data {
     fn smart_load_project_all_forks_count(& mut self, source: &Source) -> &BTreeMap<ProjectId, usize> {
         load_with_prerequisites!(self, project_all_forks_count, source, one, project_all_forks)
     }
+    fn smart_load_project_team_structures(& mut self, source: &Source) -> &BTreeMap<ProjectId, TeamStructure> {
+        load_with_prerequisites!(self, project_team_structures, source, three, project_commits, commits, commit_changes)
+    }
+    fn smart_load_project_dependencies(& mut self, source: &Source) -> &BTreeMap<ProjectId, Vec<Dependency>> {
+        load_with_prerequisites!(self, project_dependencies, source, three, project_head_trees, project_default_branch, paths)
+    }
+    fn smart_load_project_dependencies_count(& mut self, source: &Source) -> &BTreeMap<ProjectId, usize> {
+        load_with_prerequisites!(self, project_dependencies_count, source, one, project_dependencies)
+    }
+    fn smart_load_dependency_dependents(& mut self, source: &Source) -> &BTreeMap<String, Vec<ProjectId>> {
+        load_with_prerequisites!(self, dependency_dependents, source, one, project_dependencies)
+    }
+    fn smart_load_project_detected_license(& mut self, source: &Source) -> &BTreeMap<ProjectId, DetectedLicense> {
+        load_with_prerequisites!(self, project_detected_license, source, three, project_head_trees, project_default_branch, paths)
+    }
+    fn smart_load_project_header_licenses(& mut self, source: &Source) -> &BTreeMap<ProjectId, Vec<(String, usize)>> {
+        load_with_prerequisites!(self, project_header_licenses, source, three, project_head_trees, project_default_branch, paths)
+    }
+    fn smart_load_project_tooling(& mut self, source: &Source) -> &BTreeMap<ProjectId, Vec<Tool>> {
+        load_with_prerequisites!(self, project_tooling, source, three, project_head_trees, project_default_branch, paths)
+    }
+    fn smart_load_project_tooling_count(& mut self, source: &Source) -> &BTreeMap<ProjectId, usize> {
+        load_with_prerequisites!(self, project_tooling_count, source, one, project_tooling)
+    }
+    fn smart_load_project_tool_adoption(& mut self, source: &Source) -> &BTreeMap<ProjectId, Vec<(Tool, Timestamp)>> {
+        load_with_prerequisites!(self, project_tool_adoption, source, four, project_commits, commit_changes, commit_author_timestamps, paths)
+    }
+    fn smart_load_project_fork_upstreams(& mut self, source: &Source) -> &BTreeMap<ProjectId, ProjectId> {
+        load_with_prerequisites!(self, project_fork_upstreams, source, two, commit_projects, project_created)
+    }
+    fn smart_load_fork_networks(& mut self, source: &Source) -> &BTreeMap<ProjectId, ForkNetwork> {
+        load_with_prerequisites!(self, fork_networks, source, four, project_fork_upstreams, project_commits, commits, commit_committer_timestamps)
+    }
     fn smart_load_project_head_trees(& mut self, source: &Source) -> &BTreeMap<ProjectId, Vec<(String, Vec<(PathId, SnapshotId)>)>> {
         load_with_prerequisites!(self, project_head_trees, source, three, project_heads, commits, commit_changes)
     }
@@ -984,10 +1236,25 @@ impl Data {
     fn smart_load_user_project_ids_count(& mut self, source: &Source) -> &BTreeMap<UserId, usize> {
         load_with_prerequisites!(self, user_project_ids_count, source, one, user_project_ids)
     }
+    fn smart_load_user_collaborators(& mut self, source: &Source) -> &BTreeMap<UserId, Vec<UserId>> {
+        load_with_prerequisites!(self, user_collaborators, source, one, project_authors)
+    }
+    fn smart_load_user_collaborators_count(& mut self, source: &Source) -> &BTreeMap<UserId, usize> {
+        load_with_prerequisites!(self, user_collaborators_count, source, one, user_collaborators)
+    }
+    fn smart_load_user_page_rank(& mut self, source: &Source) -> &BTreeMap<UserId, f64> {
+        load_with_prerequisites!(self, user_page_rank, source, one, user_collaborators)
+    }
     
     fn smart_load_paths(&mut self, source: &Source) -> &BTreeMap<PathId, Path> {
         load_from_source!(self, paths, source)
     }
+    fn smart_load_path_languages(&mut self, source: &Source) -> &BTreeMap<PathId, Language> {
+        load_with_prerequisites!(self, path_languages, source, two, commit_changes, paths)
+    }
+    fn smart_load_path_is_test(&mut self, source: &Source) -> &BTreeMap<PathId, bool> {
+        load_with_prerequisites!(self, path_is_test, source, two, paths, path_languages)
+    }
     // fn smart_load_snapshots(&mut self, source: &DataSource) -> &BTreeMap<SnapshotId, Snapshot> {
     //     load_from_source!(self, snapshots, source)
     // }
@@ -1000,6 +1267,24 @@ impl Data {
     fn smart_load_commit_messages(&mut self, source: &Source) -> &BTreeMap<CommitId, String> {
         load_from_source!(self, commit_messages, source)
     }
+    fn smart_load_commit_categories(&mut self, source: &Source) -> &BTreeMap<CommitId, CommitCategory> {
+        load_with_prerequisites!(self, commit_categories, source, one, commit_messages)
+    }
+    fn smart_load_commit_issue_refs(&mut self, source: &Source) -> &BTreeMap<CommitId, Vec<String>> {
+        load_with_prerequisites!(self, commit_issue_refs, source, one, commit_messages)
+    }
+    fn smart_load_commit_introduced_bugs(&mut self, source: &Source) -> &BTreeMap<CommitId, Vec<CommitId>> {
+        load_with_prerequisites!(self, commit_introduced_bugs, source, four, commits, commit_changes, commit_categories, commit_issue_refs)
+    }
+    fn smart_load_project_bug_fix_ratio(&mut self, source: &Source) -> &BTreeMap<ProjectId, f64> {
+        load_with_prerequisites!(self, project_bug_fix_ratio, source, three, project_commits, commit_categories, commit_issue_refs)
+    }
+    fn smart_load_project_line_authors(&mut self, source: &Source) -> &BTreeMap<ProjectId, Vec<(UserId, usize)>> {
+        load_with_prerequisites!(self, project_line_authors, source, four, project_heads, project_default_branch, commits, commit_changes)
+    }
+    fn smart_load_user_surviving_lines(&mut self, source: &Source) -> &BTreeMap<UserId, usize> {
+        load_with_prerequisites!(self, user_surviving_lines, source, one, project_line_authors)
+    }
     fn smart_load_commit_committer_timestamps(&mut self, source: &Source) -> &BTreeMap<CommitId, Timestamp> {
         load_from_source!(self, commit_committer_timestamps, source)
     }
@@ -1064,11 +1349,32 @@ impl Data {
     fn smart_load_project_locs(&mut self, source: &Source) -> &BTreeMap<ProjectId, usize> {
         load_with_prerequisites!(self, project_locs, source, three, project_head_trees,  project_default_branch, snapshot_locs)
     }
+    fn smart_load_snapshot_line_counts(&mut self, source: &Source) -> &BTreeMap<SnapshotId, LineCounts> {
+        load_with_prerequisites!(self, snapshot_line_counts, source, two, commit_changes, path_languages)
+    }
+    fn smart_load_project_line_counts(&mut self, source: &Source) -> &BTreeMap<ProjectId, LineCounts> {
+        load_with_prerequisites!(self, project_line_counts, source, four, project_head_trees, project_default_branch, path_languages, snapshot_line_counts)
+    }
+    fn smart_load_snapshot_near_duplicates(&mut self, source: &Source) -> &BTreeMap<SnapshotId, Vec<SnapshotId>> {
+        load_with_prerequisites!(self, snapshot_near_duplicates, source, two, commit_changes, path_languages)
+    }
+    fn smart_load_snapshot_clone_classes(&mut self, source: &Source) -> &BTreeMap<SnapshotId, SnapshotId> {
+        load_with_prerequisites!(self, snapshot_clone_classes, source, one, snapshot_near_duplicates)
+    }
+    fn smart_load_project_near_duplication_ratio(&mut self, source: &Source) -> &BTreeMap<ProjectId, f64> {
+        load_with_prerequisites!(self, project_near_duplication_ratio, source, three, project_head_trees, project_default_branch, snapshot_clone_classes)
+    }
+    fn smart_load_project_test_code(&mut self, source: &Source) -> &BTreeMap<ProjectId, (usize, usize, usize)> {
+        load_with_prerequisites!(self, project_test_code, source, four, project_head_trees, project_default_branch, path_is_test, snapshot_locs)
+    }
     fn smart_load_project_duplicated_code(&mut self, source: &Source) -> &BTreeMap<ProjectId, f64> {
         load_with_prerequisites!(self, duplicated_code, source, three, project_commits,  commit_changes_with_contents, snapshot_projects)
     }
     fn smart_load_commit_languages(&mut self, source: &Source) -> &BTreeMap<CommitId, Vec<Language>> {
-        load_with_prerequisites!(self, commit_languages, source, two, commit_changes, paths)
+        load_with_prerequisites!(self, commit_languages, source, two, commit_changes, path_languages)
+    }
+    fn smart_load_commit_touches_tests(&mut self, source: &Source) -> &BTreeMap<CommitId, bool> {
+        load_with_prerequisites!(self, commit_touches_tests, source, two, commit_changes, path_is_test)
     }
     fn smart_load_commit_languages_count(&mut self, source: &Source) -> &BTreeMap<CommitId, usize> {
         load_with_prerequisites!(self, commit_languages_count, source, one, commit_languages)
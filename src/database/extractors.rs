@@ -6,6 +6,15 @@ use std::iter::FromIterator;
 use itertools::{Itertools, MinMaxResult};
 
 use crate::objects::*;
+use crate::manifests;
+use crate::licenses;
+use crate::languages;
+use crate::sloc;
+use crate::testcode;
+use crate::tooling;
+use crate::classification;
+use crate::clones;
+use crate::blame::{self, PathHistory};
 use crate::piracy::*;
 use crate::weights_and_measures::{Weighed};
 use crate::{Store, Percentage, Timestamp};
@@ -760,6 +769,161 @@ impl DoubleMapExtractor for UserProjectIdsExtractor {
     }
 }
 
+pub(crate) struct UserCollaboratorsExtractor {}
+impl MapExtractor for UserCollaboratorsExtractor {
+    type Key = UserId;
+    type Value = Vec<UserId>;
+}
+
+impl SingleMapExtractor for UserCollaboratorsExtractor {
+    type A = BTreeMap<ProjectId, Vec<UserId>>;
+
+    // Two users are collaborators if they both authored commits in the same project.
+    fn extract(_: &Source, project_authors: &Self::A) -> BTreeMap<Self::Key, Self::Value> {
+        let mut collaborators: BTreeMap<UserId, BTreeSet<UserId>> = BTreeMap::new();
+        for authors in project_authors.values() {
+            for author in authors {
+                collaborators.entry(*author)
+                    .or_insert_with(BTreeSet::new)
+                    .extend(authors.iter().filter(|other| *other != author).map(|other| *other));
+            }
+        }
+        collaborators.into_iter()
+            .map(|(user_id, collaborators)| (user_id, collaborators.into_iter().collect()))
+            .collect()
+    }
+}
+
+pub(crate) struct UserPageRankExtractor {}
+impl MapExtractor for UserPageRankExtractor {
+    type Key = UserId;
+    type Value = f64;
+}
+
+impl UserPageRankExtractor {
+    const DAMPING: f64 = 0.85;
+    const MAX_ITERATIONS: usize = 100;
+    const TOLERANCE: f64 = 1e-9;
+}
+
+impl SingleMapExtractor for UserPageRankExtractor {
+    type A = BTreeMap<UserId, Vec<UserId>>;
+
+    fn extract(_: &Source, user_collaborators: &Self::A) -> BTreeMap<Self::Key, Self::Value> {
+        Self::page_rank(user_collaborators)
+    }
+}
+
+impl UserPageRankExtractor {
+    // PageRank over the (undirected) collaboration graph. Users without collaborators are dangling
+    // nodes: their rank is spread evenly over the whole graph.
+    pub(crate) fn page_rank(user_collaborators: &BTreeMap<UserId, Vec<UserId>>) -> BTreeMap<UserId, f64> {
+        let node_count = user_collaborators.len();
+        if node_count == 0 {
+            return BTreeMap::new();
+        }
+
+        let index: BTreeMap<UserId, usize> = user_collaborators.keys()
+            .enumerate()
+            .map(|(i, user_id)| (*user_id, i))
+            .collect();
+        let neighbours: Vec<Vec<usize>> = user_collaborators.values()
+            .map(|collaborators| collaborators.iter().flat_map(|user_id| index.get(user_id)).map(|i| *i).collect())
+            .collect();
+
+        let mut rank = vec![1f64 / node_count as f64; node_count];
+        for _ in 0..Self::MAX_ITERATIONS {
+            let dangling: f64 = neighbours.iter().zip(rank.iter())
+                .filter(|(neighbours, _)| neighbours.is_empty())
+                .map(|(_, rank)| *rank)
+                .sum();
+            let base = (1f64 - Self::DAMPING + Self::DAMPING * dangling) / node_count as f64;
+
+            let mut next = vec![base; node_count];
+            for (node, neighbours) in neighbours.iter().enumerate().filter(|(_, neighbours)| !neighbours.is_empty()) {
+                let share = Self::DAMPING * rank[node] / neighbours.len() as f64;
+                for neighbour in neighbours {
+                    next[*neighbour] += share;
+                }
+            }
+
+            let delta: f64 = next.iter().zip(rank.iter()).map(|(next, previous)| (next - previous).abs()).sum();
+            rank = next;
+            if delta < Self::TOLERANCE {
+                break;
+            }
+        }
+
+        index.into_iter().map(|(user_id, i)| (user_id, rank[i])).collect()
+    }
+}
+
+pub(crate) struct ProjectTeamStructureExtractor {}
+impl MapExtractor for ProjectTeamStructureExtractor {
+    type Key = ProjectId;
+    type Value = TeamStructure;
+}
+
+impl ProjectTeamStructureExtractor {
+    fn team_structure(neighbours: &BTreeMap<UserId, BTreeSet<UserId>>) -> TeamStructure {
+        let size = neighbours.len();
+        let collaborations = neighbours.values().map(|neighbours| neighbours.len()).sum::<usize>() / 2;
+
+        let density = if size > 1 {
+            (2 * collaborations) as f64 / (size * (size - 1)) as f64
+        } else {
+            0f64
+        };
+
+        // Average local clustering coefficient, users with fewer than two neighbours count as 0.
+        let clustering = if size > 0 {
+            neighbours.values().map(|user_neighbours| {
+                let degree = user_neighbours.len();
+                if degree < 2 {
+                    return 0f64;
+                }
+                let links = user_neighbours.iter()
+                    .tuple_combinations()
+                    .filter(|(a, b)| neighbours.get(*a).map_or(false, |a_neighbours| a_neighbours.contains(*b)))
+                    .count();
+                (2 * links) as f64 / (degree * (degree - 1)) as f64
+            }).sum::<f64>() / size as f64
+        } else {
+            0f64
+        };
+
+        TeamStructure::new(size, collaborations, density, clustering)
+    }
+}
+
+impl TripleMapExtractor for ProjectTeamStructureExtractor {
+    type A = BTreeMap<ProjectId, Vec<CommitId>>;
+    type B = BTreeMap<CommitId, Commit>;
+    type C = BTreeMap<CommitId, Vec<ChangeTuple>>;
+
+    // Within a project, two authors work together if they both modified the same path.
+    fn extract(_: &Source, project_commits: &Self::A, commits: &Self::B, commit_changes: &Self::C) -> BTreeMap<Self::Key, Self::Value> {
+        project_commits.iter().map(|(project_id, commit_ids)| {
+            let mut neighbours: BTreeMap<UserId, BTreeSet<UserId>> = BTreeMap::new();
+            let mut path_authors: BTreeMap<PathId, BTreeSet<UserId>> = BTreeMap::new();
+            for commit in commit_ids.iter().flat_map(|commit_id| commits.get(commit_id)) {
+                neighbours.entry(commit.author).or_insert_with(BTreeSet::new);
+                for (path_id, _) in commit_changes.get(&commit.id).into_iter().flatten() {
+                    path_authors.entry(*path_id).or_insert_with(BTreeSet::new).insert(commit.author);
+                }
+            }
+            for authors in path_authors.values() {
+                for author in authors {
+                    neighbours.entry(*author)
+                        .or_insert_with(BTreeSet::new)
+                        .extend(authors.iter().filter(|other| *other != author).map(|other| *other));
+                }
+            }
+            (*project_id, Self::team_structure(&neighbours))
+        }).collect()
+    }
+}
+
 pub(crate) struct UserLifetimeExtractor {}
 impl MapExtractor for UserLifetimeExtractor {
     type Key = UserId;
@@ -925,6 +1089,185 @@ impl SourceMapExtractor for CommitMessageExtractor {
     }
 }
 
+pub(crate) struct CommitCategoryExtractor {}
+impl MapExtractor for CommitCategoryExtractor {
+    type Key = CommitId;
+    type Value = CommitCategory;
+}
+impl SingleMapExtractor for CommitCategoryExtractor {
+    type A = BTreeMap<CommitId, String>;
+    fn extract(_: &Source, commit_messages: &Self::A) -> BTreeMap<Self::Key, Self::Value> {
+        let rules = classification::CommitRules::default();
+        commit_messages.iter()
+            .map(|(commit_id, message)| (*commit_id, rules.classify(message)))
+            .collect()
+    }
+}
+
+pub(crate) struct CommitIssueRefsExtractor {}
+impl MapExtractor for CommitIssueRefsExtractor {
+    type Key = CommitId;
+    type Value = Vec<String>;
+}
+impl SingleMapExtractor for CommitIssueRefsExtractor {
+    type A = BTreeMap<CommitId, String>;
+    fn extract(_: &Source, commit_messages: &Self::A) -> BTreeMap<Self::Key, Self::Value> {
+        let references = classification::IssueReferences::new();
+        commit_messages.iter()
+            .map(|(commit_id, message)| (*commit_id, references.find(message)))
+            .collect()
+    }
+}
+
+pub(crate) struct ProjectBugFixRatioExtractor {}
+impl MapExtractor for ProjectBugFixRatioExtractor {
+    type Key = ProjectId;
+    type Value = f64;
+}
+impl TripleMapExtractor for ProjectBugFixRatioExtractor {
+    type A = BTreeMap<ProjectId, Vec<CommitId>>;
+    type B = BTreeMap<CommitId, CommitCategory>;
+    type C = BTreeMap<CommitId, Vec<String>>;
+
+    // Bug fixes are counted the same way as by the SZZ analysis (`classification::is_bug_fix`).
+    // Merge commits do not make changes of their own, so they count neither as fixes nor as
+    // other commits.
+    fn extract(_: &Source, project_commits: &Self::A, commit_categories: &Self::B, commit_issue_refs: &Self::C) -> BTreeMap<Self::Key, Self::Value> {
+        let no_refs: Vec<String> = Vec::new();
+        project_commits.iter().filter_map(|(project_id, commit_ids)| {
+            let categories: Vec<(CommitCategory, &Vec<String>)> = commit_ids.iter()
+                .filter_map(|commit_id| {
+                    commit_categories.get(commit_id)
+                        .map(|category| (*category, commit_issue_refs.get(commit_id).unwrap_or(&no_refs)))
+                })
+                .filter(|(category, _)| *category != CommitCategory::Merge)
+                .collect();
+            if categories.is_empty() { return None }
+            let bug_fixes = categories.iter()
+                .filter(|(category, issue_refs)| classification::is_bug_fix(*category, issue_refs))
+                .count();
+            Some((*project_id, bug_fixes as f64 / categories.len() as f64))
+        }).collect()
+    }
+}
+
+struct ExtractedHistory<'a> {
+    source: &'a Source,
+    commits: &'a BTreeMap<CommitId, Commit>,
+    // Changes of each path, so that looking up one change does not scan everything a commit changed.
+    path_changes: BTreeMap<PathId, BTreeMap<CommitId, Option<SnapshotId>>>,
+}
+impl<'a> ExtractedHistory<'a> {
+    fn new(source: &'a Source, commits: &'a BTreeMap<CommitId, Commit>, commit_changes: &BTreeMap<CommitId, Vec<ChangeTuple>>) -> Self {
+        let mut path_changes: BTreeMap<PathId, BTreeMap<CommitId, Option<SnapshotId>>> = BTreeMap::new();
+        for (commit_id, changes) in commit_changes {
+            for (path_id, snapshot_id) in changes {
+                path_changes.entry(*path_id).or_insert_with(BTreeMap::new).insert(*commit_id, *snapshot_id);
+            }
+        }
+        ExtractedHistory { source, commits, path_changes }
+    }
+}
+impl<'a> PathHistory for ExtractedHistory<'a> {
+    fn parents(&self, commit: &CommitId) -> Vec<CommitId> {
+        self.commits.get(commit).map_or(Vec::new(), |commit| commit.parents.clone())
+    }
+    fn change(&self, commit: &CommitId, path: &PathId) -> Option<Option<SnapshotId>> {
+        self.path_changes.get(path)?.get(commit).copied()
+    }
+    fn lines(&self, snapshot: &SnapshotId) -> Option<Vec<String>> {
+        self.source.get_snapshot(*snapshot).map(|bytes| {
+            Snapshot::new(*snapshot, bytes).contents_owned().lines().map(|line| line.to_string()).collect()
+        })
+    }
+}
+
+pub(crate) struct CommitIntroducedBugsExtractor {}
+impl MapExtractor for CommitIntroducedBugsExtractor {
+    type Key = CommitId;
+    type Value = Vec<CommitId>;
+}
+
+impl CommitIntroducedBugsExtractor {
+    // Snapshots and line maps kept in memory while blaming the lines that fixes change.
+    const MEMOIZED_VERSIONS: usize = 10_000;
+}
+
+// Maps each bug-introducing commit to the commits that fixed the bugs it introduced.
+impl QuadrupleMapExtractor for CommitIntroducedBugsExtractor {
+    type A = BTreeMap<CommitId, Commit>;
+    type B = BTreeMap<CommitId, Vec<ChangeTuple>>;
+    type C = BTreeMap<CommitId, CommitCategory>;
+    type D = BTreeMap<CommitId, Vec<String>>;
+    fn extract(source: &Source, commits: &Self::A, commit_changes: &Self::B, commit_categories: &Self::C, commit_issue_refs: &Self::D) -> BTreeMap<Self::Key, Self::Value> {
+        let extracted_history = ExtractedHistory::new(source, commits, commit_changes);
+        let history = blame::MemoizedHistory::new(&extracted_history, Self::MEMOIZED_VERSIONS);
+        let no_refs = Vec::new();
+        let mut introduced: BTreeMap<CommitId, Vec<CommitId>> = BTreeMap::new();
+        commit_categories.iter()
+            .filter(|(commit_id, category)| {
+                let issue_refs = commit_issue_refs.get(commit_id).unwrap_or(&no_refs);
+                classification::is_bug_fix(**category, issue_refs)
+            })
+            .for_each(|(fix_id, _)| {
+                let changes = commit_changes.get(fix_id).map_or(&[][..], |changes| changes.as_slice());
+                for bug_introducing_id in blame::bug_introducing_commits(&history, fix_id, changes) {
+                    introduced.entry(bug_introducing_id).or_insert_with(Vec::new).push(*fix_id);
+                }
+            });
+        introduced
+    }
+}
+
+pub(crate) struct ProjectLineAuthorsExtractor {}
+impl MapExtractor for ProjectLineAuthorsExtractor {
+    type Key = ProjectId;
+    type Value = Vec<(UserId, usize)>;
+}
+
+// For each project: the number of non-blank lines in the head of the default branch that each
+// author wrote (ie. that were last changed by a commit they authored), most lines first.
+impl QuadrupleMapExtractor for ProjectLineAuthorsExtractor {
+    type A = BTreeMap<ProjectId, Vec<Head>>;
+    type B = BTreeMap<ProjectId, String>;
+    type C = BTreeMap<CommitId, Commit>;
+    type D = BTreeMap<CommitId, Vec<ChangeTuple>>;
+    fn extract(source: &Source, project_heads: &Self::A, project_default_branch: &Self::B, commits: &Self::C, commit_changes: &Self::D) -> BTreeMap<Self::Key, Self::Value> {
+        let history = ExtractedHistory::new(source, commits, commit_changes);
+        project_heads.iter().filter_map(|(project_id, heads)| {
+            let default_branch_name = project_default_branch.get(project_id)?;
+            let ref_name = format!("refs/heads/{}", default_branch_name);
+            let head = heads.iter().filter(|head| head.name() == ref_name).next()?;
+            let changes = |commit_id: &CommitId| commit_changes.get(commit_id).cloned().unwrap_or_default();
+            let line_authors = blame::surviving_lines(&history, &head.commit_id(), changes).into_iter()
+                .filter_map(|(commit_id, lines)| commits.get(&commit_id).map(|commit| (commit.author, lines)))
+                .into_group_map()
+                .into_iter()
+                .map(|(author_id, lines)| (author_id, lines.into_iter().sum()))
+                .sorted_by_key(|(_, lines): &(UserId, usize)| *lines)
+                .rev()
+                .collect();
+            Some((*project_id, line_authors))
+        }).collect()
+    }
+}
+
+pub(crate) struct UserSurvivingLinesExtractor {}
+impl MapExtractor for UserSurvivingLinesExtractor {
+    type Key = UserId;
+    type Value = usize;
+}
+impl SingleMapExtractor for UserSurvivingLinesExtractor {
+    type A = BTreeMap<ProjectId, Vec<(UserId, usize)>>;
+    fn extract(_: &Source, project_line_authors: &Self::A) -> BTreeMap<Self::Key, Self::Value> {
+        let mut surviving_lines: BTreeMap<UserId, usize> = BTreeMap::new();
+        project_line_authors.values().flatten().for_each(|(user_id, lines)| {
+            *surviving_lines.entry(*user_id).or_insert(0) += *lines;
+        });
+        surviving_lines
+    }
+}
+
 pub(crate) struct CommitterTimestampExtractor {}
 impl MapExtractor for CommitterTimestampExtractor {
     type Key = CommitId;
@@ -1013,6 +1356,207 @@ impl SourceMapExtractor for SnapshotHasContentsExtractor {
 }
 
 
+pub(crate) struct ProjectDependenciesExtractor{}
+impl MapExtractor for ProjectDependenciesExtractor {
+    type Key = ProjectId;
+    type Value = Vec<Dependency>;
+}
+
+impl TripleMapExtractor for ProjectDependenciesExtractor {
+    type A = BTreeMap<ProjectId, Vec<(String, Vec<(PathId, SnapshotId)>)>>;
+    type B = BTreeMap<ProjectId, String>;
+    type C = BTreeMap<PathId, Path>;
+
+    // Dependencies declared by the manifests in the head tree of the default branch.
+    fn extract(source: &Source, project_head_trees: &Self::A, project_default_branch: &Self::B, paths: &Self::C) -> BTreeMap<Self::Key, Self::Value> {
+        project_head_trees.iter().filter_map(|(pid, heads)| {
+            let default_branch_name = project_default_branch.get(pid)?;
+            let ref_name = format!("refs/heads/{}", default_branch_name);
+            let (_, tree) = heads.iter().filter(|(name, _)| *name == ref_name).next()?;
+            let dependencies = tree.iter()
+                .filter_map(|(path_id, snapshot_id)| {
+                    let path = paths.get(path_id)?;
+                    if !manifests::is_manifest(&path.location) {
+                        return None;
+                    }
+                    let snapshot = Snapshot::new(*snapshot_id, source.get_snapshot(*snapshot_id)?);
+                    Some(manifests::parse_manifest(&path.location, &snapshot.contents()))
+                })
+                .flatten()
+                .sorted()
+                .dedup()
+                .collect();
+            Some((*pid, dependencies))
+        }).collect()
+    }
+}
+
+pub(crate) struct DependencyDependentsExtractor{}
+impl MapExtractor for DependencyDependentsExtractor {
+    type Key = String;
+    type Value = Vec<ProjectId>;
+}
+
+impl SingleMapExtractor for DependencyDependentsExtractor {
+    type A = BTreeMap<ProjectId, Vec<Dependency>>;
+
+    fn extract(_: &Source, project_dependencies: &Self::A) -> BTreeMap<Self::Key, Self::Value> {
+        project_dependencies.iter()
+            .flat_map(|(pid, dependencies)| {
+                dependencies.iter().map(move |dependency| (dependency.name.clone(), *pid))
+            })
+            .into_group_map()
+            .into_iter()
+            .map(|(name, projects)| (name, projects.into_iter().sorted().dedup().collect()))
+            .collect()
+    }
+}
+
+pub(crate) struct ProjectDetectedLicenseExtractor{}
+impl MapExtractor for ProjectDetectedLicenseExtractor {
+    type Key = ProjectId;
+    type Value = DetectedLicense;
+}
+
+impl TripleMapExtractor for ProjectDetectedLicenseExtractor {
+    type A = BTreeMap<ProjectId, Vec<(String, Vec<(PathId, SnapshotId)>)>>;
+    type B = BTreeMap<ProjectId, String>;
+    type C = BTreeMap<PathId, Path>;
+
+    // License files (LICENSE, COPYING) in the head tree of the default branch take precedence over
+    // READMEs, and files closer to the root of the repository take precedence over nested ones.
+    fn extract(source: &Source, project_head_trees: &Self::A, project_default_branch: &Self::B, paths: &Self::C) -> BTreeMap<Self::Key, Self::Value> {
+        project_head_trees.iter().filter_map(|(pid, heads)| {
+            let default_branch_name = project_default_branch.get(pid)?;
+            let ref_name = format!("refs/heads/{}", default_branch_name);
+            let (_, tree) = heads.iter().filter(|(name, _)| *name == ref_name).next()?;
+            tree.iter()
+                .filter_map(|(path_id, snapshot_id)| {
+                    let path = paths.get(path_id)?;
+                    let priority = if licenses::is_license_file(&path.location) { 0 }
+                        else if licenses::is_readme_file(&path.location) { 1 }
+                        else { return None };
+                    let depth = path.location.matches('/').count();
+                    Some(((priority, depth, path.location.clone()), *path_id, *snapshot_id))
+                })
+                .sorted_by(|(a, _, _), (b, _, _)| a.cmp(b))
+                .filter_map(|((_, _, location), path_id, snapshot_id)| {
+                    let snapshot = Snapshot::new(snapshot_id, source.get_snapshot(snapshot_id)?);
+                    let (spdx, confidence) = licenses::detect_license(&location, &snapshot.contents())?;
+                    Some(DetectedLicense::new(spdx, confidence, path_id))
+                })
+                .next()
+                .map(|license| (*pid, license))
+        }).collect()
+    }
+}
+
+pub(crate) struct ProjectHeaderLicensesExtractor{}
+impl MapExtractor for ProjectHeaderLicensesExtractor {
+    type Key = ProjectId;
+    type Value = Vec<(String, usize)>;
+}
+
+impl TripleMapExtractor for ProjectHeaderLicensesExtractor {
+    type A = BTreeMap<ProjectId, Vec<(String, Vec<(PathId, SnapshotId)>)>>;
+    type B = BTreeMap<ProjectId, String>;
+    type C = BTreeMap<PathId, Path>;
+
+    // Licenses declared in the headers of files in the head tree of the default branch, with the 
+    // number of files declaring each. License files and READMEs are not counted.
+    fn extract(source: &Source, project_head_trees: &Self::A, project_default_branch: &Self::B, paths: &Self::C) -> BTreeMap<Self::Key, Self::Value> {
+        project_head_trees.iter().filter_map(|(pid, heads)| {
+            let default_branch_name = project_default_branch.get(pid)?;
+            let ref_name = format!("refs/heads/{}", default_branch_name);
+            let (_, tree) = heads.iter().filter(|(name, _)| *name == ref_name).next()?;
+            let header_licenses = tree.iter()
+                .filter(|(path_id, _)| {
+                    paths.get(path_id).map_or(false, |path| {
+                        !licenses::is_license_file(&path.location) && !licenses::is_readme_file(&path.location)
+                    })
+                })
+                .filter_map(|(_, snapshot_id)| {
+                    let snapshot = Snapshot::new(*snapshot_id, source.get_snapshot(*snapshot_id)?);
+                    snapshot.license_header()
+                })
+                .sorted()
+                .group_by(|spdx| spdx.clone())
+                .into_iter()
+                .map(|(spdx, group)| (spdx, group.count()))
+                .collect();
+            Some((*pid, header_licenses))
+        }).collect()
+    }
+}
+
+pub(crate) struct ProjectToolingExtractor{}
+impl MapExtractor for ProjectToolingExtractor {
+    type Key = ProjectId;
+    type Value = Vec<Tool>;
+}
+
+impl TripleMapExtractor for ProjectToolingExtractor {
+    type A = BTreeMap<ProjectId, Vec<(String, Vec<(PathId, SnapshotId)>)>>;
+    type B = BTreeMap<ProjectId, String>;
+    type C = BTreeMap<PathId, Path>;
+    fn extract(_: &Source, project_head_trees: &Self::A, project_default_branch: &Self::B, paths: &Self::C) -> BTreeMap<Self::Key, Self::Value> {
+        project_head_trees.iter().filter_map(|(pid, heads)| {
+            let default_branch_name = project_default_branch.get(pid)?;
+            let ref_name = format!("refs/heads/{}", default_branch_name);
+            let (_, tree) = heads.iter().filter(|(name, _)| *name == ref_name).next()?;
+            let tools: Vec<Tool> = tree.iter()
+                .filter_map(|(path_id, _)| paths.get(path_id))
+                .filter_map(|path| tooling::path_tool(&path.location))
+                .sorted()
+                .dedup()
+                .collect();
+            Some((*pid, tools))
+        }).collect()
+    }
+}
+
+pub(crate) struct ProjectToolAdoptionExtractor{}
+impl MapExtractor for ProjectToolAdoptionExtractor {
+    type Key = ProjectId;
+    type Value = Vec<(Tool, Timestamp)>;
+}
+
+// For each tool ever used by the project, the author timestamp of the earliest commit that adds or
+// modifies its configuration file. Tools are ordered by adoption time.
+impl QuadrupleMapExtractor for ProjectToolAdoptionExtractor {
+    type A = BTreeMap<ProjectId, Vec<CommitId>>;
+    type B = BTreeMap<CommitId, Vec<ChangeTuple>>;
+    type C = BTreeMap<CommitId, Timestamp>;
+    type D = BTreeMap<PathId, Path>;
+    fn extract(_: &Source, project_commits: &Self::A, commit_changes: &Self::B, commit_author_timestamps: &Self::C, paths: &Self::D) -> BTreeMap<Self::Key, Self::Value> {
+        let path_tools: BTreeMap<PathId, Tool> = paths.iter()
+            .filter_map(|(path_id, path)| tooling::path_tool(&path.location).map(|tool| (*path_id, tool)))
+            .collect();
+
+        project_commits.iter().map(|(pid, commit_ids)| {
+            let mut adoption: BTreeMap<Tool, Timestamp> = BTreeMap::new();
+            for commit_id in commit_ids {
+                let timestamp = match commit_author_timestamps.get(commit_id) {
+                    Some(timestamp) => *timestamp,
+                    None => continue,
+                };
+                let changes = commit_changes.get(commit_id).into_iter().flat_map(|changes| changes.iter());
+                for (path_id, snapshot_id) in changes {
+                    if snapshot_id.is_none() { continue } // deletions do not introduce tools
+                    if let Some(tool) = path_tools.get(path_id) {
+                        let first = adoption.entry(*tool).or_insert(timestamp);
+                        if timestamp < *first { *first = timestamp }
+                    }
+                }
+            }
+            let adoption = adoption.into_iter()
+                .sorted_by_key(|(tool, timestamp)| (*timestamp, *tool))
+                .collect();
+            (*pid, adoption)
+        }).collect()
+    }
+}
+
 pub(crate) struct ProjectLocsExtractor{} 
 impl MapExtractor for ProjectLocsExtractor{
     type Key = ProjectId;
@@ -1171,6 +1715,245 @@ impl SingleMapExtractor for CommitProjectsExtractor {
     }
 }
 
+// The language of each snapshot is the language of the first path it is encountered at.
+fn snapshot_languages(changes: &BTreeMap<CommitId, Vec<ChangeTuple>>, path_languages: &BTreeMap<PathId, Language>) -> BTreeMap<SnapshotId, Language> {
+    let mut snapshot_languages: BTreeMap<SnapshotId, Language> = BTreeMap::new();
+    changes.iter()
+        .flat_map(|(_, commit_changes)| commit_changes.iter())
+        .filter_map(|(path_id, snapshot_id)| snapshot_id.map(|snapshot_id| (path_id, snapshot_id)))
+        .for_each(|(path_id, snapshot_id)| {
+            let language = path_languages.get(path_id).copied().unwrap_or(Language::Other);
+            snapshot_languages.entry(snapshot_id).or_insert(language);
+        });
+    snapshot_languages
+}
+
+pub(crate) struct SnapshotLineCountsExtractor {}
+impl MapExtractor for SnapshotLineCountsExtractor {
+    type Key = SnapshotId;
+    type Value = LineCounts;
+}
+
+impl DoubleMapExtractor for SnapshotLineCountsExtractor {
+    type A = BTreeMap<CommitId, Vec<ChangeTuple>>;
+    type B = BTreeMap<PathId, Language>;
+
+    // Snapshots at paths of unknown language are counted without comments.
+    fn extract(source: &Source, changes: &Self::A, path_languages: &Self::B) -> BTreeMap<Self::Key, Self::Value> {
+        let snapshot_languages = snapshot_languages(changes, path_languages);
+        source.snapshot_bytes().map(|(id, contents)| {
+            let language = snapshot_languages.get(&id).copied().unwrap_or(Language::Other);
+            let contents = Snapshot::new(id, contents).contents_owned();
+            (id, sloc::count_lines(&contents, &sloc::comment_syntax(language)))
+        }).collect()
+    }
+}
+
+pub(crate) struct SnapshotNearDuplicatesExtractor {}
+impl MapExtractor for SnapshotNearDuplicatesExtractor {
+    type Key = SnapshotId;
+    type Value = Vec<SnapshotId>;
+}
+
+// Only snapshots in programming languages are indexed: near-duplicate data and prose files are
+// rarely interesting and there are many of them. Snapshots without near duplicates are left out.
+impl DoubleMapExtractor for SnapshotNearDuplicatesExtractor {
+    type A = BTreeMap<CommitId, Vec<ChangeTuple>>;
+    type B = BTreeMap<PathId, Language>;
+    fn extract(source: &Source, changes: &Self::A, path_languages: &Self::B) -> BTreeMap<Self::Key, Self::Value> {
+        let snapshot_languages = snapshot_languages(changes, path_languages);
+        let mut index = clones::NearDuplicateIndex::new();
+        source.snapshot_bytes().for_each(|(id, contents)| {
+            let language = match snapshot_languages.get(&id) {
+                Some(language) if language.is_programming() => *language,
+                _ => return,
+            };
+            let contents = Snapshot::new(id, contents).contents_owned();
+            if let Some(signature) = clones::signature(&clones::tokenize(&contents, language)) {
+                index.insert(id, signature);
+            }
+        });
+        index.near_duplicates()
+    }
+}
+
+pub(crate) struct SnapshotCloneClassExtractor {}
+impl MapExtractor for SnapshotCloneClassExtractor {
+    type Key = SnapshotId;
+    type Value = SnapshotId;
+}
+impl SingleMapExtractor for SnapshotCloneClassExtractor {
+    type A = BTreeMap<SnapshotId, Vec<SnapshotId>>;
+    fn extract(_: &Source, snapshot_near_duplicates: &Self::A) -> BTreeMap<Self::Key, Self::Value> {
+        clones::clone_classes(snapshot_near_duplicates)
+    }
+}
+
+pub(crate) struct ProjectNearDuplicationRatioExtractor {}
+impl MapExtractor for ProjectNearDuplicationRatioExtractor {
+    type Key = ProjectId;
+    type Value = f64;
+}
+
+// The fraction of files in the head tree of the default branch that are identical to or near
+// duplicates of (ie. in the same clone class as) a file in the head tree of another project.
+impl TripleMapExtractor for ProjectNearDuplicationRatioExtractor {
+    type A = BTreeMap<ProjectId, Vec<(String, Vec<(PathId, SnapshotId)>)>>;
+    type B = BTreeMap<ProjectId, String>;
+    type C = BTreeMap<SnapshotId, SnapshotId>;
+    fn extract(_: &Source, project_head_trees: &Self::A, project_default_branch: &Self::B, snapshot_clone_classes: &Self::C) -> BTreeMap<Self::Key, Self::Value> {
+        let project_classes: BTreeMap<ProjectId, Vec<SnapshotId>> = project_head_trees.iter().filter_map(|(pid, heads)| {
+            let default_branch_name = project_default_branch.get(pid)?;
+            let ref_name = format!("refs/heads/{}", default_branch_name);
+            let (_, tree) = heads.iter().filter(|(name, _)| *name == ref_name).next()?;
+            let classes = tree.iter()
+                .map(|(_, snapshot_id)| *snapshot_clone_classes.get(snapshot_id).unwrap_or(snapshot_id))
+                .collect();
+            Some((*pid, classes))
+        }).collect();
+
+        let mut class_projects: BTreeMap<SnapshotId, BTreeSet<ProjectId>> = BTreeMap::new();
+        project_classes.iter().for_each(|(pid, classes)| {
+            classes.iter().for_each(|class| { class_projects.entry(*class).or_insert_with(BTreeSet::new).insert(*pid); })
+        });
+
+        project_classes.iter().filter(|(_, classes)| !classes.is_empty()).map(|(pid, classes)| {
+            let duplicated = classes.iter().filter(|class| class_projects[class].len() > 1).count();
+            (*pid, duplicated as f64 / classes.len() as f64)
+        }).collect()
+    }
+}
+
+pub(crate) struct ProjectLineCountsExtractor {}
+impl MapExtractor for ProjectLineCountsExtractor {
+    type Key = ProjectId;
+    type Value = LineCounts;
+}
+
+impl QuadrupleMapExtractor for ProjectLineCountsExtractor {
+    type A = BTreeMap<ProjectId, Vec<(String, Vec<(PathId, SnapshotId)>)>>;
+    type B = BTreeMap<ProjectId, String>;
+    type C = BTreeMap<PathId, Language>;
+    type D = BTreeMap<SnapshotId, LineCounts>;
+
+    // Only files in programming languages count towards the project, so that data and prose files
+    // do not inflate the number of lines of code.
+    fn extract(_: &Source, project_head_trees: &Self::A, project_default_branch: &Self::B, path_languages: &Self::C, snapshot_line_counts: &Self::D) -> BTreeMap<Self::Key, Self::Value> {
+        project_head_trees.iter().filter_map(|(pid, heads)| {
+            let default_branch_name = project_default_branch.get(pid)?;
+            let ref_name = format!("refs/heads/{}", default_branch_name);
+            let (_, tree) = heads.iter().filter(|(name, _)| *name == ref_name).next()?;
+            let line_counts = tree.iter()
+                .filter(|(path_id, _)| path_languages.get(path_id).map_or(false, |language| language.is_programming()))
+                .filter_map(|(_, snapshot_id)| snapshot_line_counts.get(snapshot_id))
+                .fold(LineCounts::default(), |total, counts| total + *counts);
+            Some((*pid, line_counts))
+        }).collect()
+    }
+}
+
+pub(crate) struct ProjectTestCodeExtractor {}
+impl MapExtractor for ProjectTestCodeExtractor {
+    type Key = ProjectId;
+    type Value = (usize, usize, usize);
+}
+
+// For each project: the number of test files, the locs in test files, and the locs in all files in
+// the head tree of the default branch.
+impl QuadrupleMapExtractor for ProjectTestCodeExtractor {
+    type A = BTreeMap<ProjectId, Vec<(String, Vec<(PathId, SnapshotId)>)>>;
+    type B = BTreeMap<ProjectId, String>;
+    type C = BTreeMap<PathId, bool>;
+    type D = BTreeMap<SnapshotId, usize>;
+    fn extract(_: &Source, project_head_trees: &Self::A, project_default_branch: &Self::B, path_is_test: &Self::C, snapshot_locs: &Self::D) -> BTreeMap<Self::Key, Self::Value> {
+        project_head_trees.iter().filter_map(|(pid, heads)| {
+            let default_branch_name = project_default_branch.get(pid)?;
+            let ref_name = format!("refs/heads/{}", default_branch_name);
+            let (_, tree) = heads.iter().filter(|(name, _)| *name == ref_name).next()?;
+            let (mut test_files, mut test_locs, mut locs) = (0, 0, 0);
+            for (path_id, snapshot_id) in tree.iter() {
+                let snapshot_locs = snapshot_locs.get(snapshot_id).copied().unwrap_or(0);
+                if path_is_test.get(path_id).copied().unwrap_or(false) {
+                    test_files += 1;
+                    test_locs += snapshot_locs;
+                }
+                locs += snapshot_locs;
+            }
+            Some((*pid, (test_files, test_locs, locs)))
+        }).collect()
+    }
+}
+
+pub(crate) struct PathLanguagesExtractor {}
+impl MapExtractor for PathLanguagesExtractor {
+    type Key = PathId;
+    type Value = Language;
+}
+
+impl DoubleMapExtractor for PathLanguagesExtractor {
+    type A = BTreeMap<CommitId, Vec<ChangeTuple>>;
+    type B = BTreeMap<PathId, Path>;
+
+    // Paths whose language is clear from the extension are classified by the extension alone. For
+    // the others, we look at the contents of the last snapshot of the path that we encounter, if
+    // its contents are available.
+    fn extract(source: &Source, changes: &Self::A, paths: &Self::B) -> BTreeMap<Self::Key, Self::Value> {
+        let mut snapshots: BTreeMap<PathId, SnapshotId> = BTreeMap::new();
+        changes.iter()
+            .flat_map(|(_, commit_changes)| commit_changes.iter())
+            .filter_map(|(path_id, snapshot_id)| snapshot_id.map(|snapshot_id| (*path_id, snapshot_id)))
+            .filter(|(path_id, _)| paths.get(path_id).map_or(false, |path| languages::needs_contents(&path.location)))
+            .for_each(|(path_id, snapshot_id)| { snapshots.insert(path_id, snapshot_id); });
+
+        paths.iter().filter_map(|(path_id, path)| {
+            let language = match snapshots.get(path_id) {
+                Some(snapshot_id) => {
+                    let contents = source.get_snapshot(*snapshot_id)
+                        .map(|bytes| Snapshot::new(*snapshot_id, bytes).contents_owned());
+                    languages::detect_language(&path.location, contents.as_ref().map(|contents| contents.as_str()))
+                }
+                None => path.language(),
+            };
+            language.map(|language| (*path_id, language))
+        }).collect()
+    }
+}
+
+pub(crate) struct PathIsTestExtractor {}
+impl MapExtractor for PathIsTestExtractor {
+    type Key = PathId;
+    type Value = bool;
+}
+
+impl DoubleMapExtractor for PathIsTestExtractor {
+    type A = BTreeMap<PathId, Path>;
+    type B = BTreeMap<PathId, Language>;
+    fn extract(_: &Source, paths: &Self::A, path_languages: &Self::B) -> BTreeMap<Self::Key, Self::Value> {
+        paths.iter().map(|(path_id, path)| {
+            let language = path_languages.get(path_id).copied();
+            (*path_id, testcode::is_test_path(&path.location, language))
+        }).collect()
+    }
+}
+
+pub(crate) struct CommitTouchesTestsExtractor {}
+impl MapExtractor for CommitTouchesTestsExtractor {
+    type Key = CommitId;
+    type Value = bool;
+}
+
+impl DoubleMapExtractor for CommitTouchesTestsExtractor {
+    type A = BTreeMap<CommitId, Vec<ChangeTuple>>;
+    type B = BTreeMap<PathId, bool>;
+    fn extract(_: &Source, commit_changes: &Self::A, path_is_test: &Self::B) -> BTreeMap<Self::Key, Self::Value> {
+        commit_changes.iter().map(|(commit_id, changes)| {
+            let touches_tests = changes.iter()
+                .any(|(path_id, _)| path_is_test.get(path_id).copied().unwrap_or(false));
+            (*commit_id, touches_tests)
+        }).collect()
+    }
+}
+
 pub(crate) struct CommitLanguagesExtractor {}
 impl MapExtractor for CommitLanguagesExtractor {
     type Key = CommitId;
@@ -1179,12 +1962,12 @@ impl MapExtractor for CommitLanguagesExtractor {
 
 impl DoubleMapExtractor for CommitLanguagesExtractor {
     type A = BTreeMap<CommitId, Vec<ChangeTuple>>;
-    type B = BTreeMap<PathId, Path>;
-    fn extract(_source: &Source, changes: &Self::A, paths: &Self::B) -> BTreeMap<Self::Key, Self::Value> {
+    type B = BTreeMap<PathId, Language>;
+    fn extract(_source: &Source, changes: &Self::A, path_languages: &Self::B) -> BTreeMap<Self::Key, Self::Value> {
         changes.iter().map(|(commit_id, commit_changes)| {
             (commit_id.clone(), 
              commit_changes.iter().flat_map(|(path_id, _snapshot_id)| {
-                paths.get(path_id).map(|path| path.language()).flatten()
+                path_languages.get(path_id).copied()
              }).unique().collect::<Vec<Language>>())
         }).collect()
     }
@@ -1497,24 +2280,17 @@ impl MapExtractor for ProjectLanguagesExtractor {
 impl TripleMapExtractor for ProjectLanguagesExtractor {
     type A = BTreeMap<ProjectId, Vec<CommitId>>;
     type B = BTreeMap<CommitId, Vec<ChangeTuple>>;
-    type C = BTreeMap<PathId, Path>;
+    type C = BTreeMap<PathId, Language>;
 
-    fn extract (_: &Source, project_commits : &Self::A, commit_changes : &Self::B, paths : &Self::C) -> BTreeMap<ProjectId, Vec<(Language,usize)>> {
-        let mut cached_paths = BTreeMap::<PathId, Language>::new();
+    fn extract (_: &Source, project_commits : &Self::A, commit_changes : &Self::B, path_languages : &Self::C) -> BTreeMap<ProjectId, Vec<(Language,usize)>> {
         project_commits.iter().map(|(pid, commits)| {
             let mut languages = BTreeMap::<Language, usize>::new();
             for cid in commits {
                 if let Some(commits) = commit_changes.get(cid) {
                     for (path_id, hash) in commits {
                         if let Some(_) = hash {
-                            let lang = cached_paths.entry(*path_id).or_insert_with(|| {
-                                if let Some(language) = paths.get(path_id).unwrap().language() {
-                                    language
-                                } else {
-                                    Language::Other
-                                }
-                            });
-                            match languages.entry(*lang) {
+                            let lang = path_languages.get(path_id).copied().unwrap_or(Language::Other);
+                            match languages.entry(lang) {
                                 Entry::Occupied(mut e) => { *e.get_mut() += 1; },
                                 Entry::Vacant(e) => { e.insert(1); },
                             }
@@ -1651,6 +2427,172 @@ impl TripleMapExtractor for ProjectAllForksExtractor {
     }
 }
 
+pub(crate) struct ProjectForkUpstreamExtractor {}
+impl MapExtractor for ProjectForkUpstreamExtractor {
+    type Key = ProjectId;
+    type Value = ProjectId;
+}
+
+impl ProjectForkUpstreamExtractor {
+    fn find(parents: &mut BTreeMap<ProjectId, ProjectId>, project: ProjectId) -> ProjectId {
+        let mut root = *parents.entry(project).or_insert(project);
+        while let Some(parent) = parents.get(&root) {
+            if *parent == root { break }
+            root = *parent;
+        }
+        let mut current = project;
+        while current != root {
+            current = parents.insert(current, root).unwrap_or(root);
+        }
+        root
+    }
+}
+
+impl DoubleMapExtractor for ProjectForkUpstreamExtractor {
+    type A = BTreeMap<CommitId, Vec<ProjectId>>;
+    type B = BTreeMap<ProjectId, Timestamp>;
+
+    fn extract (_: &Source, commit_projects: &Self::A, project_created: &Self::B) -> BTreeMap<ProjectId, ProjectId> {
+        Self::upstreams(commit_projects, project_created)
+    }
+}
+
+impl ProjectForkUpstreamExtractor {
+    // Projects that share at least one commit end up in the same network. The oldest project in
+    // each network is its upstream (ties broken by smaller id, projects without a creation time
+    // go last). Projects that do not share commits with anyone are not part of any network.
+    pub(crate) fn upstreams(commit_projects: &BTreeMap<CommitId, Vec<ProjectId>>, project_created: &BTreeMap<ProjectId, Timestamp>) -> BTreeMap<ProjectId, ProjectId> {
+        let mut parents = BTreeMap::<ProjectId, ProjectId>::new();
+        for projects in commit_projects.values().filter(|projects| projects.len() > 1) {
+            let first = Self::find(&mut parents, projects[0]);
+            for project in projects.iter().skip(1) {
+                let other = Self::find(&mut parents, *project);
+                if other != first {
+                    parents.insert(other, first);
+                }
+            }
+        }
+
+        let members: Vec<ProjectId> = parents.keys().map(|project| *project).collect();
+        members.into_iter()
+            .map(|project| (Self::find(&mut parents, project), project))
+            .into_group_map()
+            .into_iter()
+            .flat_map(|(_, network)| {
+                let upstream = *network.iter()
+                    .min_by_key(|project| (project_created.get(project).map_or(Timestamp::MAX, |t| *t), **project))
+                    .unwrap();
+                network.into_iter().map(move |project| (project, upstream))
+            })
+            .collect()
+    }
+}
+
+pub(crate) struct ForkNetworkExtractor {}
+impl MapExtractor for ForkNetworkExtractor {
+    type Key = ProjectId;
+    type Value = ForkNetwork;
+}
+
+impl QuadrupleMapExtractor for ForkNetworkExtractor {
+    type A = BTreeMap<ProjectId, ProjectId>;
+    type B = BTreeMap<ProjectId, Vec<CommitId>>;
+    type C = BTreeMap<CommitId, Commit>;
+    type D = BTreeMap<CommitId, Timestamp>;
+
+    fn extract (_: &Source, project_fork_upstreams: &Self::A, project_commits: &Self::B, commits: &Self::C, committer_timestamps: &Self::D) -> BTreeMap<ProjectId, ForkNetwork> {
+        Self::networks(project_fork_upstreams, project_commits, commits, committer_timestamps)
+    }
+}
+
+impl ForkNetworkExtractor {
+    pub(crate) fn networks(project_fork_upstreams: &BTreeMap<ProjectId, ProjectId>, project_commits: &BTreeMap<ProjectId, Vec<CommitId>>, commits: &BTreeMap<CommitId, Commit>, committer_timestamps: &BTreeMap<CommitId, Timestamp>) -> BTreeMap<ProjectId, ForkNetwork> {
+        let no_commits: Vec<CommitId> = Vec::new();
+        project_fork_upstreams.iter()
+            .map(|(project, upstream)| (*upstream, *project))
+            .into_group_map()
+            .into_iter()
+            .map(|(upstream, members)| {
+                let upstream_commits: BTreeSet<CommitId> = project_commits.get(&upstream)
+                    .unwrap_or(&no_commits).iter().map(|commit| *commit).collect();
+
+                // Commits that the upstream merged in from elsewhere, eg. from one of its forks, are
+                // not on its own first-parent history.
+                let upstream_mainline = Self::mainline(&upstream_commits, commits);
+
+                let forks = members.into_iter()
+                    .filter(|project| *project != upstream)
+                    .sorted()
+                    .map(|project| {
+                        let fork_commits = project_commits.get(&project).unwrap_or(&no_commits);
+                        let unique = fork_commits.iter().filter(|commit| !upstream_commits.contains(commit)).count();
+                        // The fork's own commits are the ones the upstream does not have, and the ones
+                        // the upstream merged in.
+                        let (shared, own): (Vec<CommitId>, Vec<CommitId>) = fork_commits.iter()
+                            .map(|commit| *commit)
+                            .partition(|commit| upstream_mainline.contains(commit));
+
+                        // The fork branches off at the oldest shared commit that is a parent of one of
+                        // its own commits. A fork without commits of its own is just a stale copy of
+                        // the upstream, so it diverges at its newest shared commit.
+                        let branch_points: BTreeSet<CommitId> = own.iter()
+                            .flat_map(|commit| commits.get(commit))
+                            .flat_map(|commit| commit.parents.iter())
+                            .filter(|parent| upstream_mainline.contains(*parent))
+                            .map(|parent| *parent)
+                            .collect();
+                        let divergence = if branch_points.is_empty() {
+                            shared.iter().max_by_key(|commit| (committer_timestamps.get(*commit), **commit)).map(|commit| *commit)
+                        } else {
+                            branch_points.iter().min_by_key(|commit| (committer_timestamps.get(*commit), **commit)).map(|commit| *commit)
+                        };
+
+                        // Heuristic: the fork contributed back if the upstream contains a commit made
+                        // after the divergence by someone who authored the fork's own commits. This
+                        // includes the fork's commits merged upstream, as well as commits squashed or
+                        // rebased onto the upstream.
+                        let diverged = divergence.and_then(|commit| committer_timestamps.get(&commit));
+                        let fork_authors: BTreeSet<UserId> = own.iter()
+                            .filter(|commit| committer_timestamps.get(*commit) > diverged)
+                            .flat_map(|commit| commits.get(commit))
+                            .map(|commit| commit.author)
+                            .collect();
+                        let contributed_back = diverged.map_or(false, |diverged| {
+                            upstream_commits.iter()
+                                .filter(|commit| committer_timestamps.get(*commit).map_or(false, |time| time > diverged))
+                                .flat_map(|commit| commits.get(commit))
+                                .any(|commit| fork_authors.contains(&commit.author))
+                        });
+
+                        Fork::new(project, divergence, unique, contributed_back)
+                    })
+                    .collect();
+
+                (upstream, ForkNetwork::new(upstream, forks))
+            })
+            .collect()
+    }
+
+    // The commits on the first-parent history of any of the project's heads.
+    fn mainline(project_commits: &BTreeSet<CommitId>, commits: &BTreeMap<CommitId, Commit>) -> BTreeSet<CommitId> {
+        let parents: BTreeSet<CommitId> = project_commits.iter()
+            .flat_map(|commit| commits.get(commit))
+            .flat_map(|commit| commit.parents.iter().map(|parent| *parent))
+            .collect();
+        let mut mainline: BTreeSet<CommitId> = BTreeSet::new();
+        for head in project_commits.iter().filter(|commit| !parents.contains(*commit)) {
+            let mut current = Some(*head);
+            while let Some(commit) = current {
+                if !project_commits.contains(&commit) || !mainline.insert(commit) {
+                    break
+                }
+                current = commits.get(&commit).and_then(|commit| commit.parents.first().map(|parent| *parent));
+            }
+        }
+        mainline
+    }
+}
+
 pub(crate) struct ProjectHeadTreesExtractor {}
 
 impl MapExtractor for ProjectHeadTreesExtractor {
@@ -1804,4 +2746,184 @@ impl DoubleItemExtractor for CommitTreeExtractor {
     //         (*pid, heads)
     //     }).collect()
     // }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use crate::objects::*;
+    use std::collections::BTreeSet;
+    use super::{ProjectForkUpstreamExtractor, ForkNetworkExtractor, UserPageRankExtractor, ProjectTeamStructureExtractor};
+
+    fn project(id: usize) -> ProjectId { ProjectId::from(id) }
+    fn commit(id: u64) -> CommitId { CommitId::from(id) }
+    fn user(id: u64) -> UserId { UserId::from(id) }
+
+    fn graph(edges: &[(u64, u64)], isolated: &[u64]) -> BTreeMap<UserId, BTreeSet<UserId>> {
+        let mut graph: BTreeMap<UserId, BTreeSet<UserId>> =
+            isolated.iter().map(|id| (user(*id), BTreeSet::new())).collect();
+        for (a, b) in edges {
+            graph.entry(user(*a)).or_insert_with(BTreeSet::new).insert(user(*b));
+            graph.entry(user(*b)).or_insert_with(BTreeSet::new).insert(user(*a));
+        }
+        graph
+    }
+
+    #[test]
+    fn page_rank_converges_on_star() {
+        // Solving the PageRank equations by hand for a star with 3 leaves and damping 0.85 gives
+        // c = 0.0375 + 0.85 * 3l, l = 0.0375 + 0.85 * c/3.
+        let star = graph(&[(1, 2), (1, 3), (1, 4)], &[]);
+        let collaborators = star.into_iter().map(|(user, others)| (user, others.into_iter().collect())).collect();
+        let rank = UserPageRankExtractor::page_rank(&collaborators);
+
+        assert!((rank[&user(1)] - 0.133125 / 0.2775).abs() < 1e-6);
+        for leaf in 2..=4 {
+            assert!((rank[&user(leaf)] - (0.0375 + 0.85 * rank[&user(1)] / 3f64)).abs() < 1e-6);
+        }
+        assert!((rank.values().sum::<f64>() - 1f64).abs() < 1e-6);
+    }
+
+    #[test]
+    fn page_rank_spreads_dangling_users() {
+        let collaborators = graph(&[(1, 2)], &[3]).into_iter()
+            .map(|(user, others)| (user, others.into_iter().collect()))
+            .collect();
+        let rank = UserPageRankExtractor::page_rank(&collaborators);
+
+        assert!((rank[&user(1)] - rank[&user(2)]).abs() < 1e-9);
+        assert!(rank[&user(3)] < rank[&user(1)]);
+        assert!((rank.values().sum::<f64>() - 1f64).abs() < 1e-6);
+        assert!(UserPageRankExtractor::page_rank(&BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn team_structure_of_triangle_with_tail() {
+        // Triangle 1-2-3 with 4 hanging off 3: coefficients are 1, 1, 1/3 and 0.
+        let team = ProjectTeamStructureExtractor::team_structure(&graph(&[(1, 2), (2, 3), (1, 3), (3, 4)], &[]));
+        assert_eq!(team.size(), 4);
+        assert_eq!(team.collaboration_count(), 4);
+        assert!((team.density() - 4f64 / 6f64).abs() < 1e-9);
+        assert!((team.clustering() - (1f64 + 1f64 + 1f64 / 3f64) / 4f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn team_structure_of_lone_author() {
+        let team = ProjectTeamStructureExtractor::team_structure(&graph(&[], &[1]));
+        assert_eq!(team.size(), 1);
+        assert_eq!(team.collaboration_count(), 0);
+        assert_eq!(team.density(), 0f64);
+        assert_eq!(team.clustering(), 0f64);
+    }
+
+    #[test]
+    fn fork_upstreams_are_oldest_projects_in_network() {
+        // 1 and 2 share commit 10, 2 and 3 share commit 11, 4 shares nothing.
+        let commit_projects: BTreeMap<CommitId, Vec<ProjectId>> = vec![
+            (commit(10), vec![project(1), project(2)]),
+            (commit(11), vec![project(2), project(3)]),
+            (commit(12), vec![project(4)]),
+        ].into_iter().collect();
+        let created: BTreeMap<ProjectId, i64> = vec![
+            (project(1), 300), (project(2), 100), (project(4), 0),
+        ].into_iter().collect();
+
+        let upstreams = ProjectForkUpstreamExtractor::upstreams(&commit_projects, &created);
+        assert_eq!(upstreams.get(&project(1)), Some(&project(2)));
+        assert_eq!(upstreams.get(&project(2)), Some(&project(2)));
+        assert_eq!(upstreams.get(&project(3)), Some(&project(2)));
+        assert_eq!(upstreams.get(&project(4)), None);
+    }
+
+    #[test]
+    fn fork_diverges_at_oldest_branch_point_after_merging_upstream() {
+        // Upstream: 1 <- 2 <- 3. The fork branches off at 2 with commit 4, and then merges the
+        // upstream's 3 in commit 5, so both 2 and 3 are parents of fork-only commits.
+        let commits: BTreeMap<CommitId, Commit> = vec![
+            Commit::new(commit(1), user(1), user(1), vec![]),
+            Commit::new(commit(2), user(1), user(1), vec![commit(1)]),
+            Commit::new(commit(3), user(1), user(1), vec![commit(2)]),
+            Commit::new(commit(4), user(2), user(2), vec![commit(2)]),
+            Commit::new(commit(5), user(2), user(2), vec![commit(4), commit(3)]),
+        ].into_iter().map(|c| (c.id, c)).collect();
+        let timestamps: BTreeMap<CommitId, i64> = (1..=5).map(|id| (commit(id), id as i64 * 10)).collect();
+        let project_commits: BTreeMap<ProjectId, Vec<CommitId>> = vec![
+            (project(1), vec![commit(1), commit(2), commit(3)]),
+            (project(2), vec![commit(1), commit(2), commit(3), commit(4), commit(5)]),
+            (project(3), vec![commit(1), commit(2)]),
+        ].into_iter().collect();
+        let upstreams: BTreeMap<ProjectId, ProjectId> =
+            (1..=3).map(|id| (project(id), project(1))).collect();
+
+        let networks = ForkNetworkExtractor::networks(&upstreams, &project_commits, &commits, &timestamps);
+        let network = networks.get(&project(1)).unwrap();
+        assert_eq!(network.fork_ids(), vec![project(2), project(3)]);
+
+        let fork = network.fork(&project(2)).unwrap();
+        assert_eq!(fork.divergence_commit_id(), Some(commit(2)));
+        assert_eq!(fork.unique_commit_count(), 2);
+        assert!(!fork.contributed_back());
+
+        // A fork without commits of its own diverges at its newest shared commit.
+        let stale = network.fork(&project(3)).unwrap();
+        assert_eq!(stale.divergence_commit_id(), Some(commit(2)));
+        assert_eq!(stale.unique_commit_count(), 0);
+    }
+
+    #[test]
+    fn fork_contributed_back_when_merged_upstream() {
+        // The fork's only commit 4 branches off at 2, and the upstream merges it in commit 5. The
+        // stale fork copies the upstream up to 3.
+        let commits: BTreeMap<CommitId, Commit> = vec![
+            Commit::new(commit(1), user(1), user(1), vec![]),
+            Commit::new(commit(2), user(1), user(1), vec![commit(1)]),
+            Commit::new(commit(3), user(1), user(1), vec![commit(2)]),
+            Commit::new(commit(4), user(2), user(2), vec![commit(2)]),
+            Commit::new(commit(5), user(1), user(1), vec![commit(3), commit(4)]),
+        ].into_iter().map(|c| (c.id, c)).collect();
+        let timestamps: BTreeMap<CommitId, i64> = (1..=5).map(|id| (commit(id), id as i64 * 10)).collect();
+        let project_commits: BTreeMap<ProjectId, Vec<CommitId>> = vec![
+            (project(1), (1..=5).map(commit).collect()),
+            (project(2), vec![commit(1), commit(2), commit(4)]),
+            (project(3), vec![commit(1), commit(2), commit(3)]),
+        ].into_iter().collect();
+        let upstreams: BTreeMap<ProjectId, ProjectId> =
+            (1..=3).map(|id| (project(id), project(1))).collect();
+
+        let networks = ForkNetworkExtractor::networks(&upstreams, &project_commits, &commits, &timestamps);
+        let network = networks.get(&project(1)).unwrap();
+
+        let fork = network.fork(&project(2)).unwrap();
+        assert_eq!(fork.divergence_commit_id(), Some(commit(2)));
+        assert_eq!(fork.unique_commit_count(), 0);
+        assert!(fork.contributed_back());
+
+        let stale = network.fork(&project(3)).unwrap();
+        assert_eq!(stale.divergence_commit_id(), Some(commit(3)));
+        assert!(!stale.contributed_back());
+    }
+
+    #[test]
+    fn fork_contributed_back_when_squashed_upstream() {
+        // The fork's commit 4 lands in the upstream squashed into commit 6 by the same author.
+        let commits: BTreeMap<CommitId, Commit> = vec![
+            Commit::new(commit(1), user(1), user(1), vec![]),
+            Commit::new(commit(2), user(1), user(1), vec![commit(1)]),
+            Commit::new(commit(4), user(2), user(2), vec![commit(2)]),
+            Commit::new(commit(6), user(1), user(2), vec![commit(2)]),
+        ].into_iter().map(|c| (c.id, c)).collect();
+        let timestamps: BTreeMap<CommitId, i64> = vec![1, 2, 4, 6].into_iter().map(|id| (commit(id), id as i64 * 10)).collect();
+        let project_commits: BTreeMap<ProjectId, Vec<CommitId>> = vec![
+            (project(1), vec![commit(1), commit(2), commit(6)]),
+            (project(2), vec![commit(1), commit(2), commit(4)]),
+        ].into_iter().collect();
+        let upstreams: BTreeMap<ProjectId, ProjectId> =
+            (1..=2).map(|id| (project(id), project(1))).collect();
+
+        let networks = ForkNetworkExtractor::networks(&upstreams, &project_commits, &commits, &timestamps);
+        let fork = networks.get(&project(1)).unwrap().fork(&project(2)).unwrap();
+        assert_eq!(fork.divergence_commit_id(), Some(commit(2)));
+        assert_eq!(fork.unique_commit_count(), 1);
+        assert!(fork.contributed_back());
+    }
+}
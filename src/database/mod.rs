@@ -6,33 +6,42 @@ pub mod data;
 pub mod extractors;
 pub mod lazy;
 
+use std::any::Any;
 use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use delegate::delegate;
 
 use crate::objects::*;
 use crate::iterators::*;
 use crate::log::*;
 use crate::time::Duration;
+use crate::blame::{self, PathHistory};
+use crate::diff;
 use crate::{CacheDir, Store, Percentage, Timestamp};
 
 use source::Source;
+use persistent::{Persistent, PersistentCache};
+use cache::CACHE_FILE_CUSTOM_PREFIX;
 
 use data::Data;
 
 // Internally Mutable Data
 pub struct Database {
     data: RefCell<Data>,
+    custom: RefCell<BTreeMap<String, Box<dyn Any>>>,
     source: Source,
+    cache_dir: String,
     log: Log,
 }
 
 // Constructors
 impl Database {
     pub fn new(source: Source, cache_dir: CacheDir, log: Log) -> Self {
+        let dir = cache_dir.as_string();
         let data = RefCell::new(Data::new(cache_dir, log.clone()));
-        Database { data, source, log }
+        Database { data, custom: RefCell::new(BTreeMap::new()), source, cache_dir: dir, log }
     }
 }
 
@@ -83,6 +92,20 @@ impl Database {
         self.snapshots().attach_data_to_each(self)
     }
 
+    pub fn fork_networks<'a>(&'a self) -> impl Iterator<Item=ItemWithData<'a, ForkNetwork>> + 'a {
+        self.all_fork_networks().into_iter().attach_data_to_each(self)
+    }
+
+    /* 
+     * Projects whose default branch declares a dependency on a package with the given name, in any
+     * ecosystem.
+     */
+    pub fn dependents_of<'a>(&'a self, name: impl Into<String>) -> impl Iterator<Item=ItemWithData<'a, Project>> + 'a {
+        self.dependent_project_ids(&name.into()).into_iter()
+            .flat_map(move |id| self.project(&id))
+            .attach_data_to_each(self)
+    }
+
     pub fn source(&self) -> &Source {
         &self.source
     }
@@ -218,6 +241,20 @@ impl Database {
             #[append_args(&self.source)] pub fn project_major_language_changes(&self, id: &ProjectId) -> Option<usize>;
             #[append_args(&self.source)] pub fn project_all_forks(&self, id: &ProjectId) -> Option<Vec<ProjectId>>;
             #[append_args(&self.source)] pub fn project_all_forks_count(&self, id: &ProjectId) -> Option<usize>;
+            #[append_args(&self.source)] pub fn project_team_structure(&self, id: &ProjectId) -> Option<TeamStructure>;
+            #[append_args(&self.source)] pub fn project_dependencies(&self, id: &ProjectId) -> Option<Vec<Dependency>>;
+            #[append_args(&self.source)] pub fn project_dependency_count(&self, id: &ProjectId) -> Option<usize>;
+            #[append_args(&self.source)] pub fn project_detected_license(&self, id: &ProjectId) -> Option<DetectedLicense>;
+            #[append_args(&self.source)] pub fn project_header_licenses(&self, id: &ProjectId) -> Option<Vec<(String, usize)>>;
+            #[append_args(&self.source)] pub fn project_tooling(&self, id: &ProjectId) -> Option<Vec<Tool>>;
+            #[append_args(&self.source)] pub fn project_bug_fix_ratio(&self, id: &ProjectId) -> Option<f64>;
+            #[append_args(&self.source)] pub fn project_line_author_ids(&self, id: &ProjectId) -> Option<Vec<(UserId, usize)>>;
+            #[append_args(&self.source)] pub fn project_authorship_shares(&self, id: &ProjectId) -> Option<Vec<(User, f64)>>;
+            #[append_args(&self.source)] pub fn project_tooling_count(&self, id: &ProjectId) -> Option<usize>;
+            #[append_args(&self.source)] pub fn project_tool_adoption(&self, id: &ProjectId) -> Option<Vec<(Tool, Timestamp)>>;
+            #[append_args(&self.source)] pub fn project_fork_upstream(&self, id: &ProjectId) -> Option<ProjectId>;
+            #[append_args(&self.source)] pub fn project_fork_network(&self, id: &ProjectId) -> Option<ForkNetwork>;
+            #[append_args(&self.source)] pub fn fork_network(&self, upstream: &ProjectId) -> Option<ForkNetwork>;
             #[append_args(&self.source)] pub fn project_head_trees(&self, id: &ProjectId) -> Option<Vec<(String, Vec<(PathId, SnapshotId)>)>>;
             #[append_args(&self.source)] pub fn project_head_trees_count(&self, id : &ProjectId) -> Option<usize>;
             #[append_args(&self.source)] pub fn project_max_commit_delta(&self, id: &ProjectId) -> Option<i64>;
@@ -233,6 +270,9 @@ impl Database {
             #[append_args(&self.source)] pub fn project_newest_commit(&self, id: &ProjectId) -> Option<Commit>;
             #[append_args(&self.source)] pub fn project_is_abandoned(&self, id: &ProjectId) -> Option<bool>;
             #[append_args(&self.source)] pub fn project_locs(&self, id: &ProjectId) -> Option<usize>;
+            #[append_args(&self.source)] pub fn project_line_counts(&self, id: &ProjectId) -> Option<LineCounts>;
+            #[append_args(&self.source)] pub fn project_near_duplication_ratio(&self, id: &ProjectId) -> Option<f64>;
+            #[append_args(&self.source)] pub fn project_test_code(&self, id: &ProjectId) -> Option<(usize, usize, usize)>;
             #[append_args(&self.source)] pub fn project_duplicated_code(&self, id: &ProjectId) -> Option<f64>;
             #[append_args(&self.source)] pub fn project_latest_update_time(&self, id : &ProjectId) -> Option<i64>;
             #[append_args(&self.source)] pub fn project_is_valid(&self, id : &ProjectId) -> Option<bool>;
@@ -254,14 +294,25 @@ impl Database {
             #[append_args(&self.source)] pub fn user_h_index2(&self, id: &UserId) -> Option<u64>;
             #[append_args(&self.source)] pub fn user_project_ids(&self, id: &UserId) -> Option<Vec<ProjectId>>;
             #[append_args(&self.source)] pub fn user_project_ids_count(&self, id: &UserId) -> Option<usize>;
+            #[append_args(&self.source)] pub fn user_collaborator_ids(&self, id: &UserId) -> Option<Vec<UserId>>;
+            #[append_args(&self.source)] pub fn user_collaborators(&self, id: &UserId) -> Option<Vec<User>>;
+            #[append_args(&self.source)] pub fn user_collaborator_count(&self, id: &UserId) -> Option<usize>;
+            #[append_args(&self.source)] pub fn user_collaborator_ids_between(&self, id: &UserId, since: Timestamp, until: Timestamp) -> Option<Vec<UserId>>;
+            #[append_args(&self.source)] pub fn user_centrality(&self, id: &UserId, measure: CentralityMeasure) -> Option<f64>;
+            #[append_args(&self.source)] pub fn user_surviving_lines(&self, id: &UserId) -> Option<usize>;
 
             // File path attributes
             #[append_args(&self.source)] pub fn path(&self, id: &PathId) -> Option<Path>;
+            #[append_args(&self.source)] pub fn path_language(&self, id: &PathId) -> Option<Language>;
+            #[append_args(&self.source)] pub fn path_is_test(&self, id: &PathId) -> Option<bool>;
 
             // Commit attributes
             #[append_args(&self.source)] pub fn commit(&self, id: &CommitId) -> Option<Commit>;
             #[append_args(&self.source)] pub fn commit_hash(&self, id: &CommitId) -> Option<String>;
             #[append_args(&self.source)] pub fn commit_message(&self, id: &CommitId) -> Option<String>;
+            #[append_args(&self.source)] pub fn commit_category(&self, id: &CommitId) -> Option<CommitCategory>;
+            #[append_args(&self.source)] pub fn commit_issue_refs(&self, id: &CommitId) -> Option<Vec<String>>;
+            #[append_args(&self.source)] pub fn commit_introduced_bug_fix_ids(&self, id: &CommitId) -> Vec<CommitId>;
             #[append_args(&self.source)] pub fn commit_author_timestamp(&self, id: &CommitId) -> Option<Timestamp>;
             #[append_args(&self.source)] pub fn commit_committer_timestamp(&self, id: &CommitId) -> Option<Timestamp>;
             #[append_args(&self.source)] pub fn commit_changes(&self, id: &CommitId) -> Option<Vec<Change>>;
@@ -272,6 +323,7 @@ impl Database {
             #[append_args(&self.source)] pub fn commit_projects_count(&self, id: &CommitId) -> Option<usize>;
             #[append_args(&self.source)] pub fn commit_languages(&self, id : &CommitId) -> Option<Vec<Language>>;
             #[append_args(&self.source)] pub fn commit_languages_count(&self, id: &CommitId) -> Option<usize>;
+            #[append_args(&self.source)] pub fn commit_touches_tests(&self, id: &CommitId) -> Option<bool>;
             #[append_args(&self.source)] pub fn commit_changes_with_contents(&self, id: &CommitId) -> Option<Vec<Change>>;
             #[append_args(&self.source)] pub fn commit_change_with_contents_count(&self, id: &CommitId) -> Option<usize>;
             #[append_args(&self.source)] pub fn commit_trees(&self, id: &CommitId) -> Tree;
@@ -280,6 +332,9 @@ impl Database {
 
             // Snapshot attributes
             #[append_args(&self.source)] pub fn snapshot_locs(&self, id: &SnapshotId) -> Option<usize>;
+            #[append_args(&self.source)] pub fn snapshot_line_counts(&self, id: &SnapshotId) -> Option<LineCounts>;
+            #[append_args(&self.source)] pub fn snapshot_near_duplicate_ids(&self, id: &SnapshotId) -> Vec<SnapshotId>;
+            #[append_args(&self.source)] pub fn snapshot_clone_class(&self, id: &SnapshotId) -> Option<SnapshotId>;
             #[append_args(&self.source)] pub fn snapshot_unique_projects(&self, id: &SnapshotId) -> usize;
             #[append_args(&self.source)] pub fn snapshot_original_project(&self, id: &SnapshotId) -> ProjectId;
             #[append_args(&self.source)] pub fn snapshot_has_contents(&self, id: &SnapshotId) -> bool;
@@ -289,6 +344,8 @@ impl Database {
             #[append_args(&self.source)] pub fn all_user_ids(&self)    -> Vec<UserId>;
             #[append_args(&self.source)] pub fn all_path_ids(&self)    -> Vec<PathId>;
             #[append_args(&self.source)] pub fn all_commit_ids(&self)  -> Vec<CommitId>;
+            #[append_args(&self.source)] pub fn all_fork_networks(&self) -> Vec<ForkNetwork>;
+            #[append_args(&self.source)] pub fn dependent_project_ids(&self, name: &String) -> Vec<ProjectId>;
 
             // Misc.
             #[append_args(&self.source)] pub fn export_to_csv<S>(&self, dir: S) -> Result<(), std::io::Error> where S: Into<String>;
@@ -307,3 +364,108 @@ impl Database {
         })
     }
 }
+
+impl Database {
+    /*
+     * Line-level authorship of a file: each line of the path as of the given commit of the project,
+     * together with the commit that last changed it and that commit's author. The history of the
+     * path is followed through first parents, diffing each version against the one before it.
+     *
+     * Returns `None` if the commit does not belong to the project or if the path does not exist at
+     * that commit.
+     */
+    pub fn blame(&self, project: &ProjectId, commit: &CommitId, path: &PathId) -> Option<Vec<BlamedLine>> {
+        if !self.project_commit_ids(project)?.contains(commit) {
+            return None
+        }
+        let lines = blame::blame_file(self, commit, path)?;
+        let authors: BTreeMap<CommitId, UserId> = lines.iter()
+            .map(|(commit_id, _)| *commit_id)
+            .collect::<BTreeSet<CommitId>>().into_iter()
+            .flat_map(|commit_id| self.commit(&commit_id).map(|commit| (commit_id, commit.author_id())))
+            .collect();
+        Some(lines.into_iter().enumerate().flat_map(|(line, (commit_id, contents))| {
+            authors.get(&commit_id).map(|author_id| BlamedLine::new(line, commit_id, *author_id, contents))
+        }).collect())
+    }
+}
+
+impl Database {
+    /*
+     * Line-based unified diff between two snapshots, with hunks, the numbers of added and removed
+     * lines, and a similarity ratio (see `objects::Diff`). Returns `None` if the contents of either
+     * snapshot are not available.
+     */
+    pub fn diff(&self, a: SnapshotId, b: SnapshotId) -> Option<Diff> {
+        let old = self.lines(&a)?;
+        let new = self.lines(&b)?;
+        Some(diff::unified_diff(&old, &new, diff::CONTEXT_LINES))
+    }
+
+    /*
+     * Diff of a change made by a commit to a path: from the version of the path in the commit's
+     * first parent to the snapshot the commit left there. Missing versions (the path is created or
+     * deleted) are treated as empty files.
+     */
+    pub fn change_diff(&self, commit: &CommitId, path: &PathId, snapshot: Option<SnapshotId>) -> Option<Diff> {
+        let old = match self.previous_version(commit, path) {
+            Some((_, previous)) => self.lines(&previous)?,
+            None => Vec::new(),
+        };
+        let new = match snapshot {
+            Some(snapshot) => self.lines(&snapshot)?,
+            None => Vec::new(),
+        };
+        Some(diff::unified_diff(&old, &new, diff::CONTEXT_LINES))
+    }
+}
+
+impl Database {
+    /*
+     * Values of user-defined attributes (see `Cached`), kept per object id in a cache named after
+     * the attribute. Values missing from the cache are computed and stored into it, and the cache
+     * is written into the cache directory when the database is dropped. Fails if the cache was
+     * already used with different key or value types.
+     */
+    pub fn cached_value<K, V, F>(&self, name: &str, key: K, compute: F) -> Result<Option<V>>
+        where K: Ord + Persistent + 'static, V: Clone + Persistent + 'static, F: FnOnce() -> Option<V> {
+
+        {
+            let mut custom = self.custom.borrow_mut();
+            let cache = custom.entry(name.to_owned()).or_insert_with(|| {
+                let file = format!("{}{}", CACHE_FILE_CUSTOM_PREFIX, name);
+                Box::new(PersistentCache::<K, Option<V>>::new(file, self.log.clone(), self.cache_dir.clone())) as Box<dyn Any>
+            });
+            let cache = cache.downcast_ref::<PersistentCache<K, Option<V>>>()
+                .ok_or_else(|| anyhow!("cached attribute {} was already used with different types than {} and {}",
+                                       name, std::any::type_name::<K>(), std::any::type_name::<V>()))?;
+            if let Some(value) = cache.get(&key) {
+                return Ok(value.clone())
+            }
+        }
+        // The cache is not borrowed while computing, so that the value can depend on other cached attributes.
+        let value = compute();
+        if let Some(cache) = self.custom.borrow_mut().get_mut(name) {
+            if let Some(cache) = cache.downcast_mut::<PersistentCache<K, Option<V>>>() {
+                cache.insert(key, value.clone());
+            }
+        }
+        Ok(value)
+    }
+}
+
+impl PathHistory for Database {
+    fn parents(&self, commit: &CommitId) -> Vec<CommitId> {
+        self.commit(commit).map_or(Vec::new(), |commit| commit.parent_ids().clone())
+    }
+    fn change(&self, commit: &CommitId, path: &PathId) -> Option<Option<SnapshotId>> {
+        self.commit_changes(commit)?.into_iter()
+            .find(|change| change.path_id() == *path)
+            .map(|change| change.snapshot_id())
+    }
+    fn lines(&self, snapshot: &SnapshotId) -> Option<Vec<String>> {
+        self.snapshot(snapshot).map(|snapshot| {
+            snapshot.contents_owned().lines().map(|line| line.to_string()).collect()
+        })
+    }
+}
@@ -284,6 +284,67 @@ impl<E,A,B,C, D> PersistentMap<E> where E: QuadrupleMapExtractor<A=A,B=B,C=C,D=D
     }
 }
 
+/*
+ * A map of values that are computed on demand instead of extracted from the source up front,
+ * such as the values of user-defined attributes. The map is read from the cache directory when it
+ * is created and written back when it is dropped, if any values were added in the meantime.
+ */
+pub struct PersistentCache<K, V> where K: Ord + Persistent, V: Persistent {
+    log: Log,
+    name: String,
+    cache_path: PathBuf,
+    cache_dir: PathBuf,
+    map: BTreeMap<K, V>,
+    modified: bool,
+}
+
+impl<K, V> PersistentCache<K, V> where K: Ord + Persistent, V: Persistent {
+    pub fn new<Sa, Sb>(name: Sa, log: Log, dir: Sb) -> Self where Sa: Into<String>, Sb: Into<String> {
+        let name = name.into();
+        let cache_dir = PathBuf::from(dir.into());
+        let mut cache_path = cache_dir.join(&name);
+        cache_path.set_extension(PERSISTENT_EXTENSION);
+        let map = if cache_path.is_file() {
+            let mut event = log.start(Verbosity::Log, format!("loading {} from cache {}", name, cache_path.to_str().unwrap()));
+            let map: BTreeMap<K, V> = File::open(&cache_path).ok()
+                .and_then(|reader| serde_cbor::from_reader(reader).ok())
+                .unwrap_or_else(BTreeMap::new);
+            event.counted(map.len());
+            log.end(event);
+            map
+        } else {
+            BTreeMap::new()
+        };
+        PersistentCache { log, name, cache_path, cache_dir, map, modified: false }
+    }
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+    pub fn insert(&mut self, key: K, value: V) {
+        self.map.insert(key, value);
+        self.modified = true;
+    }
+    pub fn store_to_cache(&mut self) -> Result<(), Box<dyn Error>> {
+        create_dir_all(&self.cache_dir)?;
+        let writer = File::create(&self.cache_path)?;
+        serde_cbor::to_writer(writer, &self.map)?;
+        self.modified = false;
+        Ok(())
+    }
+}
+
+impl<K, V> Drop for PersistentCache<K, V> where K: Ord + Persistent, V: Persistent {
+    fn drop(&mut self) {
+        if self.modified {
+            let event = self.log.start(Verbosity::Log, format!("storing {} into cache at {}", self.name, self.cache_path.to_str().unwrap()));
+            if let Err(error) = self.store_to_cache() {
+                eprintln!("Could not store {} into cache at {}: {}", self.name, self.cache_path.to_str().unwrap(), error);
+            }
+            self.log.end(event);
+        }
+    }
+}
+
 // impl<E> IntoIterator for PersistentMap<E> where E: MapExtractor {
 //     type Item = (E::Key, E::Value);
 //     type IntoIter = ();
@@ -301,3 +362,32 @@ impl<E,A,B,C, D> PersistentMap<E> where E: QuadrupleMapExtractor<A=A,B=B,C=C,D=D
 
 // pub struct Created
 
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+    use crate::database::persistent::*;
+    use crate::log::{Log, Verbosity};
+
+    #[test] fn test_persistent_cache() {
+        let temporary = tempfile::tempdir().unwrap();
+        let dir = temporary.path().to_str().unwrap().to_owned();
+        let log = Log::new(Verbosity::Warning);
+
+        {
+            let mut cache: PersistentCache<u64, Option<String>> = PersistentCache::new("values", log.clone(), dir.clone());
+            assert_eq!(cache.get(&1), None);
+            cache.insert(1, Some("one".to_owned()));
+            cache.insert(2, None);
+            let _untouched: PersistentCache<u64, Option<String>> = PersistentCache::new("untouched", log.clone(), dir.clone());
+        }
+
+        let cache: PersistentCache<u64, Option<String>> = PersistentCache::new("values", log.clone(), dir.clone());
+        assert_eq!(cache.get(&1), Some(&Some("one".to_owned())));
+        assert_eq!(cache.get(&2), Some(&None));
+        assert_eq!(cache.get(&3), None);
+
+        // Caches without new values are not written.
+        assert!(!Path::new(&dir).join("untouched").with_extension(PERSISTENT_EXTENSION).exists());
+    }
+}
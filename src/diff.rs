@@ -0,0 +1,267 @@
+use crate::objects::{Diff, DiffLine, Hunk};
+
+/** Number of unchanged lines shown around each hunk, as in `diff -u`. */
+pub const CONTEXT_LINES: usize = 3;
+
+/**
+ * Line-based differencing using Myers' O((N+M)D) algorithm, in its linear space variant that
+ * recursively splits the problem at the middle of an optimal edit path.
+ *
+ * The result is an edit script: a sequence of operations that turns the old sequence of lines into
+ * the new one, in order. Common prefixes and suffixes are stripped before running the algorithm,
+ * which makes the typical case of a small change in a large file cheap.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiffOp {
+    /** Line `old` of the old file is the same as line `new` of the new file. */
+    Equal { old: usize, new: usize },
+    /** Line `old` of the old file was removed. */
+    Delete { old: usize },
+    /** Line `new` of the new file was added. */
+    Insert { new: usize },
+}
+
+pub fn diff_lines<T: PartialEq>(old: &[T], new: &[T]) -> Vec<DiffOp> {
+    let mut ops = Vec::with_capacity(old.len() + new.len());
+    diff_range(old, new, 0, old.len(), 0, new.len(), &mut ops);
+    ops
+}
+
+/** For each line of the new file, the line of the old file it is unchanged from, if any. */
+pub fn new_to_old(ops: &[DiffOp], new_len: usize) -> Vec<Option<usize>> {
+    let mut mapping = vec![None; new_len];
+    for op in ops {
+        if let DiffOp::Equal { old, new } = op { mapping[*new] = Some(*old) }
+    }
+    mapping
+}
+
+/** Lines of the old file that were deleted or changed. */
+pub fn deleted_lines(ops: &[DiffOp]) -> Vec<usize> {
+    ops.iter().filter_map(|op| match op { DiffOp::Delete { old } => Some(*old), _ => None }).collect()
+}
+
+/**
+ * A unified diff of two versions of a file, with `context` unchanged lines around each hunk. Hunks
+ * whose context would overlap are merged.
+ */
+pub fn unified_diff<T: AsRef<str> + PartialEq>(old: &[T], new: &[T], context: usize) -> Diff {
+    let ops = diff_lines(old, new);
+
+    // Line numbers in the old and new files just before each operation.
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut old_position, mut new_position) = (0, 0);
+    for op in ops.iter() {
+        positions.push((old_position, new_position));
+        match op {
+            DiffOp::Equal { .. } => { old_position += 1; new_position += 1; }
+            DiffOp::Delete { .. } => old_position += 1,
+            DiffOp::Insert { .. } => new_position += 1,
+        }
+    }
+    positions.push((old_position, new_position));
+
+    let changes: Vec<usize> = ops.iter().enumerate()
+        .filter(|(_, op)| !matches!(op, DiffOp::Equal { .. }))
+        .map(|(index, _)| index)
+        .collect();
+
+    // Ranges of operations covered by each hunk.
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for index in changes.iter() {
+        let start = index.saturating_sub(context);
+        let end = (index + context + 1).min(ops.len());
+        match ranges.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    let hunks = ranges.into_iter().map(|(start, end)| {
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        let lines = ops[start..end].iter().map(|op| match op {
+            DiffOp::Equal { old: line, .. } => DiffLine::Context(old[*line].as_ref().to_owned()),
+            DiffOp::Delete { old: line } => DiffLine::Removed(old[*line].as_ref().to_owned()),
+            DiffOp::Insert { new: line } => DiffLine::Added(new[*line].as_ref().to_owned()),
+        }).collect();
+        let (old_lines, new_lines) = (old_end - old_start, new_end - new_start);
+        Hunk {
+            old_start: if old_lines == 0 { old_start } else { old_start + 1 },
+            old_lines,
+            new_start: if new_lines == 0 { new_start } else { new_start + 1 },
+            new_lines,
+            lines,
+        }
+    }).collect();
+
+    let added = ops.iter().filter(|op| matches!(op, DiffOp::Insert { .. })).count();
+    let removed = ops.iter().filter(|op| matches!(op, DiffOp::Delete { .. })).count();
+    let unchanged = ops.len() - added - removed;
+    let similarity = match old.len() + new.len() {
+        0 => 1.0,
+        total => 2.0 * unchanged as f64 / total as f64,
+    };
+    Diff { hunks, added, removed, similarity }
+}
+
+// Diffs old[old_start..old_end] against new[new_start..new_end] by splitting both at a point on
+// an optimal edit path and recursing on the two halves, so only O(N+M) memory is needed.
+fn diff_range<T: PartialEq>(old: &[T], new: &[T], mut old_start: usize, mut old_end: usize, mut new_start: usize, mut new_end: usize, ops: &mut Vec<DiffOp>) {
+    while old_start < old_end && new_start < new_end && old[old_start] == new[new_start] {
+        ops.push(DiffOp::Equal { old: old_start, new: new_start });
+        old_start += 1;
+        new_start += 1;
+    }
+    let mut suffix = 0;
+    while old_start < old_end && new_start < new_end && old[old_end - 1] == new[new_end - 1] {
+        old_end -= 1;
+        new_end -= 1;
+        suffix += 1;
+    }
+
+    if old_start == old_end {
+        ops.extend((new_start..new_end).map(|new| DiffOp::Insert { new }));
+    } else if new_start == new_end {
+        ops.extend((old_start..old_end).map(|old| DiffOp::Delete { old }));
+    } else {
+        let (x, y) = middle_snake(&old[old_start..old_end], &new[new_start..new_end]);
+        diff_range(old, new, old_start, old_start + x, new_start, new_start + y, ops);
+        diff_range(old, new, old_start + x, old_end, new_start + y, new_end, ops);
+    }
+
+    ops.extend((0..suffix).map(|i| DiffOp::Equal { old: old_end + i, new: new_end + i }));
+}
+
+// A point on a shortest edit path between two non-empty sequences that have no common prefix or
+// suffix, found by running the search forward from the start and backward from the end until the
+// two meet. v[k + offset] is the furthest x reached on diagonal k (counted from the end for the
+// backward search), or -1 if the diagonal was not reached yet.
+fn middle_snake<T: PartialEq>(old: &[T], new: &[T]) -> (usize, usize) {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d;
+    let length = 2 * max_d + 2;
+    let mut forward = vec![-1isize; length as usize];
+    let mut backward = vec![-1isize; length as usize];
+    forward[(offset + 1) as usize] = 0;
+    backward[(offset + 1) as usize] = 0;
+
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    // Diagonals that ran off the edge of the grid are skipped in later steps.
+    let (mut forward_start, mut forward_end, mut backward_start, mut backward_end) = (0, 0, 0, 0);
+
+    for d in 0..max_d {
+        let mut k = -d + forward_start;
+        while k <= d - forward_end {
+            let index = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && forward[index - 1] < forward[index + 1]) {
+                forward[index + 1]
+            } else {
+                forward[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index] = x;
+            if x > n {
+                forward_end += 2;
+            } else if y > m {
+                forward_start += 2;
+            } else if odd {
+                let other = offset + delta - k;
+                if other >= 0 && other < length && backward[other as usize] != -1 && x >= n - backward[other as usize] {
+                    return (x as usize, y as usize)
+                }
+            }
+            k += 2;
+        }
+
+        let mut k = -d + backward_start;
+        while k <= d - backward_end {
+            let index = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && backward[index - 1] < backward[index + 1]) {
+                backward[index + 1]
+            } else {
+                backward[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index] = x;
+            if x > n {
+                backward_end += 2;
+            } else if y > m {
+                backward_start += 2;
+            } else if !odd {
+                let other = offset + delta - k;
+                if other >= 0 && other < length && forward[other as usize] != -1 {
+                    let forward_x = forward[other as usize];
+                    if forward_x >= n - x {
+                        return (forward_x as usize, (forward_x - (other - offset)) as usize)
+                    }
+                }
+            }
+            k += 2;
+        }
+    }
+    // The searches only fail to meet when the shortest edit path takes all N+M steps, that is when
+    // the sequences have nothing in common: delete everything, then insert everything.
+    (old.len(), 0)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::diff::*;
+
+    fn apply(old: &[&str], new: &[&str], ops: &[DiffOp]) -> Vec<String> {
+        ops.iter().filter_map(|op| match op {
+            DiffOp::Equal { old: i, .. } => Some(old[*i].to_string()),
+            DiffOp::Insert { new: j } => Some(new[*j].to_string()),
+            DiffOp::Delete { .. } => None,
+        }).collect()
+    }
+
+    #[test] fn test_diff_lines() {
+        let old = vec!["a", "b", "c", "a", "b", "b", "a"];
+        let new = vec!["c", "b", "a", "b", "a", "c"];
+        let ops = diff_lines(&old, &new);
+        assert_eq!(apply(&old, &new, &ops), new);
+        assert_eq!(ops.iter().filter(|op| !matches!(op, DiffOp::Equal { .. })).count(), 5);
+    }
+
+    #[test] fn test_diff_edge_cases() {
+        let empty: Vec<&str> = vec![];
+        assert_eq!(diff_lines(&empty, &empty), vec![]);
+        assert_eq!(diff_lines(&empty, &["x"]), vec![DiffOp::Insert { new: 0 }]);
+        assert_eq!(diff_lines(&["x"], &empty), vec![DiffOp::Delete { old: 0 }]);
+        let ops = diff_lines(&["a", "b", "c"], &["a", "x", "c"]);
+        assert_eq!(deleted_lines(&ops), vec![1]);
+        assert_eq!(new_to_old(&ops, 3), vec![Some(0), None, Some(2)]);
+    }
+
+    #[test] fn test_diff_rewrite() {
+        let old: Vec<String> = (0..1000).map(|i| format!("old {}", i)).collect();
+        let new: Vec<String> = (0..1000).map(|i| format!("new {}", i)).collect();
+        let ops = diff_lines(&old, &new);
+        assert_eq!(ops.len(), 2000);
+        assert_eq!(deleted_lines(&ops), (0..1000).collect::<Vec<usize>>());
+        assert_eq!(ops[1000], DiffOp::Insert { new: 0 });
+    }
+
+    #[test] fn test_unified_diff() {
+        let old = vec!["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"];
+        let new = vec!["a", "B", "c", "d", "e", "f", "g", "h", "i", "j", "k"];
+        let diff = unified_diff(&old, &new, 1);
+        assert_eq!((diff.added(), diff.removed(), diff.hunk_count()), (2, 1, 2));
+        assert_eq!(diff.similarity(), 18.0 / 21.0);
+        assert_eq!(diff.to_string(), "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -10,1 +10,2 @@\n j\n+k\n");
+        assert_eq!(unified_diff(&old, &old, CONTEXT_LINES).hunk_count(), 0);
+        assert_eq!(unified_diff(&old, &old, CONTEXT_LINES).similarity(), 1.0);
+    }
+}
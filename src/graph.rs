@@ -0,0 +1,466 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+use crate::objects::*;
+use crate::attrib::*;
+use crate::csv::*;
+
+/**
+ * Graph export.
+ *
+ * Relationships between objects in the dataset (developers and the projects they contribute to,
+ * commits and their parents, forks and their upstreams) can be turned into a `Graph` and then
+ * written out as GraphML, DOT, or as a CSV edge list, for analysis in external graph tools.
+ *
+ * Node attributes are selected using the same attributes that are used in queries, eg.:
+ * ```ignore
+ * database.projects()
+ *     .filter_by(AtLeast(project::Stars, 100))
+ *     .into_developer_project_graph_with(Select!(project::URL, project::Stars), Select!(user::Email))
+ *     .into_graphml("developers.graphml")
+ * ```
+ * Attribute values are converted to strings via their `CSVItem` implementation, so anything that
+ * can be written into a CSV file can be used as a node attribute. Values that span multiple rows
+ * (eg. trees) cannot.
+ */
+#[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Debug)]
+pub enum GraphFormat { GraphML, DOT, EdgeList }
+
+impl GraphFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::GraphML  => "graphml",
+            GraphFormat::DOT      => "dot",
+            GraphFormat::EdgeList => "csv",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub(crate) id: String,
+    pub(crate) kind: &'static str,
+    pub(crate) attributes: Vec<(String, String)>,
+}
+
+impl Node {
+    pub fn new(id: impl Into<String>, kind: &'static str) -> Self {
+        Node { id: id.into(), kind, attributes: Vec::new() }
+    }
+    pub fn with_attributes(mut self, attributes: Vec<(String, String)>) -> Self {
+        self.attributes.extend(attributes.into_iter());
+        self
+    }
+    pub fn id(&self) -> &str { &self.id }
+    pub fn kind(&self) -> &'static str { self.kind }
+    pub fn attributes(&self) -> &Vec<(String, String)> { &self.attributes }
+}
+
+#[derive(Clone, Debug)]
+pub struct Edge {
+    pub(crate) source: String,
+    pub(crate) target: String,
+    pub(crate) attributes: Vec<(String, String)>,
+}
+
+impl Edge {
+    pub fn new(source: impl Into<String>, target: impl Into<String>) -> Self {
+        Edge { source: source.into(), target: target.into(), attributes: Vec::new() }
+    }
+    pub fn with_attribute(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.attributes.push((name.into(), value.to_string()));
+        self
+    }
+    pub fn source(&self) -> &str { &self.source }
+    pub fn target(&self) -> &str { &self.target }
+    pub fn attributes(&self) -> &Vec<(String, String)> { &self.attributes }
+}
+
+/**
+ * A graph ready to be written out. Nodes are kept unique by id: adding a node with an id that is
+ * already present merges its attributes into the existing node.
+ */
+#[derive(Clone, Debug)]
+pub struct Graph {
+    pub(crate) directed: bool,
+    pub(crate) nodes: BTreeMap<String, Node>,
+    pub(crate) edges: Vec<Edge>,
+}
+
+impl Graph {
+    pub fn directed() -> Self { Graph { directed: true, nodes: BTreeMap::new(), edges: Vec::new() } }
+    pub fn undirected() -> Self { Graph { directed: false, nodes: BTreeMap::new(), edges: Vec::new() } }
+
+    pub fn is_directed(&self) -> bool { self.directed }
+    pub fn nodes(&self) -> impl Iterator<Item=&Node> { self.nodes.values() }
+    pub fn edges(&self) -> impl Iterator<Item=&Edge> { self.edges.iter() }
+    pub fn node_count(&self) -> usize { self.nodes.len() }
+    pub fn edge_count(&self) -> usize { self.edges.len() }
+
+    pub fn add_node(&mut self, node: Node) {
+        match self.nodes.get_mut(&node.id) {
+            Some(existing) => {
+                for (name, value) in node.attributes {
+                    if !existing.attributes.iter().any(|(existing_name, _)| *existing_name == name) {
+                        existing.attributes.push((name, value));
+                    }
+                }
+            }
+            None => { self.nodes.insert(node.id.clone(), node); }
+        }
+    }
+    pub fn add_edge(&mut self, edge: Edge) {
+        self.edges.push(edge)
+    }
+
+    fn node_attribute_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = vec!["kind".to_owned()];
+        for node in self.nodes.values() {
+            for (name, _) in node.attributes.iter() {
+                if !names.contains(name) { names.push(name.clone()) }
+            }
+        }
+        names
+    }
+    fn edge_attribute_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for edge in self.edges.iter() {
+            for (name, _) in edge.attributes.iter() {
+                if !names.contains(name) { names.push(name.clone()) }
+            }
+        }
+        names
+    }
+
+    pub fn into_file(&self, format: GraphFormat, location: impl Into<String>) -> Result<(), std::io::Error> {
+        match format {
+            GraphFormat::GraphML  => self.into_graphml(location),
+            GraphFormat::DOT      => self.into_dot(location),
+            GraphFormat::EdgeList => self.into_edge_list_csv(location),
+        }
+    }
+    pub fn into_file_in_dir(&self, format: GraphFormat, dir: &std::path::Path, file: impl Into<String>) -> Result<(), std::io::Error> {
+        let mut location = dir.join(std::path::PathBuf::from(file.into()));
+        location.set_extension(format.extension());
+        self.into_file(format, location.into_os_string().to_str().unwrap())
+    }
+
+    pub fn into_graphml(&self, location: impl Into<String>) -> Result<(), std::io::Error> {
+        let location = location.into();
+        eprintln!("Writing graph to GraphML file at {}", location);
+        let mut file = create_file!(location)?;
+        self.write_graphml_to(&mut file)?;
+        eprintln!("Done writing graph to GraphML file at {}", location);
+        Ok(())
+    }
+    pub fn write_graphml_to<F>(&self, file: &mut F) -> Result<(), std::io::Error> where F: Write {
+        let node_attributes = self.node_attribute_names();
+        let edge_attributes = self.edge_attribute_names();
+
+        writeln!(file, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(file, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+        for (index, name) in node_attributes.iter().enumerate() {
+            writeln!(file, r#"  <key id="n{}" for="node" attr.name="{}" attr.type="string"/>"#, index, name.escape_xml())?;
+        }
+        for (index, name) in edge_attributes.iter().enumerate() {
+            writeln!(file, r#"  <key id="e{}" for="edge" attr.name="{}" attr.type="string"/>"#, index, name.escape_xml())?;
+        }
+        writeln!(file, r#"  <graph edgedefault="{}">"#, if self.directed { "directed" } else { "undirected" })?;
+        for node in self.nodes.values() {
+            writeln!(file, r#"    <node id="{}">"#, node.id.escape_xml())?;
+            writeln!(file, r#"      <data key="n0">{}</data>"#, node.kind)?;
+            for (name, value) in node.attributes.iter() {
+                let index = node_attributes.iter().position(|e| e == name).unwrap();
+                writeln!(file, r#"      <data key="n{}">{}</data>"#, index, value.escape_xml())?;
+            }
+            writeln!(file, r#"    </node>"#)?;
+        }
+        for edge in self.edges.iter() {
+            writeln!(file, r#"    <edge source="{}" target="{}">"#, edge.source.escape_xml(), edge.target.escape_xml())?;
+            for (name, value) in edge.attributes.iter() {
+                let index = edge_attributes.iter().position(|e| e == name).unwrap();
+                writeln!(file, r#"      <data key="e{}">{}</data>"#, index, value.escape_xml())?;
+            }
+            writeln!(file, r#"    </edge>"#)?;
+        }
+        writeln!(file, r#"  </graph>"#)?;
+        writeln!(file, r#"</graphml>"#)
+    }
+
+    pub fn into_dot(&self, location: impl Into<String>) -> Result<(), std::io::Error> {
+        let location = location.into();
+        eprintln!("Writing graph to DOT file at {}", location);
+        let mut file = create_file!(location)?;
+        self.write_dot_to(&mut file)?;
+        eprintln!("Done writing graph to DOT file at {}", location);
+        Ok(())
+    }
+    pub fn write_dot_to<F>(&self, file: &mut F) -> Result<(), std::io::Error> where F: Write {
+        let (keyword, connector) = if self.directed { ("digraph", "->") } else { ("graph", "--") };
+        writeln!(file, "{} {{", keyword)?;
+        for node in self.nodes.values() {
+            let attributes = std::iter::once(("kind".to_owned(), node.kind.to_owned()))
+                .chain(node.attributes.iter().cloned())
+                .map(|(name, value)| format!("{}={}", name.escape_dot(), value.escape_dot()))
+                .collect::<Vec<String>>()
+                .join(", ");
+            writeln!(file, "  {} [{}];", node.id.escape_dot(), attributes)?;
+        }
+        for edge in self.edges.iter() {
+            if edge.attributes.is_empty() {
+                writeln!(file, "  {} {} {};", edge.source.escape_dot(), connector, edge.target.escape_dot())?;
+            } else {
+                let attributes = edge.attributes.iter()
+                    .map(|(name, value)| format!("{}={}", name.escape_dot(), value.escape_dot()))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                writeln!(file, "  {} {} {} [{}];", edge.source.escape_dot(), connector, edge.target.escape_dot(), attributes)?;
+            }
+        }
+        writeln!(file, "}}")
+    }
+
+    pub fn into_edge_list_csv(&self, location: impl Into<String>) -> Result<(), std::io::Error> {
+        let location = location.into();
+        eprintln!("Writing graph to CSV edge list at {}", location);
+        let mut file = create_file!(location)?;
+        self.write_edge_list_csv_to(&mut file)?;
+        eprintln!("Done writing graph to CSV edge list at {}", location);
+        Ok(())
+    }
+    pub fn write_edge_list_csv_to<F>(&self, file: &mut F) -> Result<(), std::io::Error> where F: Write {
+        let edge_attributes = self.edge_attribute_names();
+        let mut header = vec!["source".to_owned(), "target".to_owned()];
+        header.extend(edge_attributes.iter().cloned());
+        writeln!(file, "{}", header.to_comma_separated_string())?;
+        for edge in self.edges.iter() {
+            let mut row = vec![edge.source.clone(), edge.target.clone()];
+            row.extend(edge_attributes.iter().map(|name| {
+                edge.attributes.iter()
+                    .find(|(edge_name, _)| edge_name == name)
+                    .map_or_else(String::new, |(_, value)| value.clone())
+            }));
+            writeln!(file, "{}", row.to_comma_separated_string())?;
+        }
+        Ok(())
+    }
+}
+
+trait GraphEscape {
+    fn escape_xml(&self) -> String;
+    fn escape_dot(&self) -> String;
+}
+
+impl GraphEscape for String {
+    fn escape_xml(&self) -> String {
+        self.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    }
+    fn escape_dot(&self) -> String {
+        format!("\"{}\"", self.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/**
+ * Converts the value of an attribute into a list of named node attributes using the column
+ * headers and the row of its `CSVItem` implementation. Headers are generic (eg. `Select!` of two
+ * counts gives two `n` columns), so repeated ones get a numeric suffix: `n`, `n_2`, `n_3`.
+ */
+fn node_attributes<T>(value: &T) -> Vec<(String, String)> where T: CSVItem {
+    unique_names(T::column_headers()).into_iter()
+        .zip(value.row().into_iter().map(|cell| unquote(cell)))
+        .collect()
+}
+
+fn unique_names(headers: Vec<&str>) -> Vec<String> {
+    let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+    headers.into_iter().map(|header| {
+        let count = seen.entry(header).or_insert(0);
+        *count += 1;
+        if *count == 1 { header.to_owned() } else { format!("{}_{}", header, count) }
+    }).collect()
+}
+
+fn unquote(cell: String) -> String {
+    if cell.len() >= 2 && cell.starts_with('"') && cell.ends_with('"') {
+        cell[1..cell.len() - 1].replace("\"\"", "\"")
+    } else {
+        cell
+    }
+}
+
+fn project_node_id(id: &ProjectId) -> String { format!("project:{}", id) }
+fn user_node_id(id: &UserId) -> String { format!("user:{}", id) }
+fn commit_node_id(id: &CommitId) -> String { format!("commit:{}", id) }
+
+/**
+ * Selects no node attributes. Useful when node attributes are needed for one kind of node in a
+ * graph, but not the other.
+ */
+pub struct NoAttributes<T>(std::marker::PhantomData<T>);
+impl<T> NoAttributes<T> { pub fn new() -> Self { NoAttributes(std::marker::PhantomData) } }
+impl<T> Attribute for NoAttributes<T> { type Object = T; }
+impl<'a, T> Getter<'a> for NoAttributes<T> {
+    type IntoItem = ();
+    fn get(&self, _: &ItemWithData<'a, Self::Object>) -> Self::IntoItem { () }
+}
+
+/**
+ * Builds graphs out of a stream of projects.
+ */
+pub trait ProjectGraphs<'a>: Iterator<Item=ItemWithData<'a, Project>> + Sized {
+    /**
+     * Bipartite graph connecting each project to the developers who authored commits in it.
+     * Edges carry the number of commits the developer authored in the project.
+     */
+    fn into_developer_project_graph(self) -> Graph {
+        self.into_developer_project_graph_with(NoAttributes::<Project>::new(), NoAttributes::<User>::new())
+    }
+    fn into_developer_project_graph_with<Ap, Au, Vp, Vu>(self, project_attributes: Ap, user_attributes: Au) -> Graph
+        where Ap: Attribute<Object=Project> + Getter<'a, IntoItem=Vp>, Vp: CSVItem,
+              Au: Attribute<Object=User> + Getter<'a, IntoItem=Vu>, Vu: CSVItem {
+
+        let mut graph = Graph::undirected();
+        for project in self {
+            let project_id = project_node_id(&project.id());
+            graph.add_node(Node::new(project_id.clone(), "project")
+                .with_attributes(node_attributes(&project_attributes.get(&project))));
+
+            for (user_id, commits) in project.commit_contribution_ids().unwrap_or_default() {
+                if let Some(user) = project.data.user(&user_id) {
+                    let user = ItemWithData::new(project.data, user);
+                    graph.add_node(Node::new(user_node_id(&user_id), "user")
+                        .with_attributes(node_attributes(&user_attributes.get(&user))));
+                    graph.add_edge(Edge::new(user_node_id(&user_id), project_id.clone())
+                        .with_attribute("commits", commits));
+                }
+            }
+        }
+        graph
+    }
+
+    /**
+     * Directed acyclic graph of the commits of each project, with edges pointing from each commit
+     * to its parents. Commits shared between projects appear in the graph once.
+     */
+    fn into_commit_graph(self) -> Graph {
+        self.into_commit_graph_with(NoAttributes::<Commit>::new())
+    }
+    fn into_commit_graph_with<Ac, Vc>(self, commit_attributes: Ac) -> Graph
+        where Ac: Attribute<Object=Commit> + Getter<'a, IntoItem=Vc>, Vc: CSVItem {
+
+        let mut graph = Graph::directed();
+        let mut visited: BTreeSet<CommitId> = BTreeSet::new();
+        for project in self {
+            for commit in project.commits_with_data().unwrap_or_default() {
+                if !visited.insert(commit.id()) {
+                    continue;
+                }
+                graph.add_node(Node::new(commit_node_id(&commit.id()), "commit")
+                    .with_attributes(node_attributes(&commit_attributes.get(&commit))));
+                for parent_id in commit.parent_ids() {
+                    graph.add_edge(Edge::new(commit_node_id(&commit.id()), commit_node_id(&parent_id)));
+                }
+            }
+        }
+        graph
+    }
+
+    /**
+     * Graph of the fork networks the projects belong to. Edges point from the upstream to each of
+     * its forks and describe the fork: divergence commit, number of unique commits, and whether the
+     * fork contributed back. All members of each network are included, not just the projects in
+     * the stream.
+     */
+    fn into_fork_network_graph(self) -> Graph {
+        self.into_fork_network_graph_with(NoAttributes::<Project>::new())
+    }
+    fn into_fork_network_graph_with<Ap, Vp>(self, project_attributes: Ap) -> Graph
+        where Ap: Attribute<Object=Project> + Getter<'a, IntoItem=Vp>, Vp: CSVItem {
+
+        let mut graph = Graph::directed();
+        let mut visited: BTreeSet<ProjectId> = BTreeSet::new();
+        for project in self {
+            let network = match project.fork_network_with_data() {
+                Some(network) => network,
+                None => continue,
+            };
+            if !visited.insert(network.upstream_id()) {
+                continue;
+            }
+
+            let upstream_id = project_node_id(&network.upstream_id());
+            for member in network.upstream_with_data().into_iter().chain(network.fork_projects_with_data().into_iter()) {
+                graph.add_node(Node::new(project_node_id(&member.id()), "project")
+                    .with_attributes(node_attributes(&project_attributes.get(&member))));
+            }
+            for fork in network.forks() {
+                graph.add_edge(Edge::new(upstream_id.clone(), project_node_id(&fork.project_id()))
+                    .with_attribute("divergence_commit_id", fork.divergence_commit_id().map_or_else(String::new, |id| id.to_string()))
+                    .with_attribute("unique_commits", fork.unique_commit_count())
+                    .with_attribute("contributed_back", fork.contributed_back()));
+            }
+        }
+        graph
+    }
+}
+
+impl<'a, I> ProjectGraphs<'a> for I where I: Iterator<Item=ItemWithData<'a, Project>> {}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::*;
+
+    fn example() -> Graph {
+        let mut graph = Graph::directed();
+        graph.add_node(Node::new("project:1", "project").with_attributes(vec![("url".to_owned(), "a \"b\" <c>".to_owned())]));
+        graph.add_node(Node::new("project:2", "project"));
+        graph.add_edge(Edge::new("project:1", "project:2").with_attribute("unique_commits", 3));
+        graph
+    }
+
+    #[test]
+    fn test_dot() {
+        let mut output: Vec<u8> = Vec::new();
+        example().write_dot_to(&mut output).unwrap();
+
+        let expected_result = "digraph {\n\
+                               \x20 \"project:1\" [\"kind\"=\"project\", \"url\"=\"a \\\"b\\\" <c>\"];\n\
+                               \x20 \"project:2\" [\"kind\"=\"project\"];\n\
+                               \x20 \"project:1\" -> \"project:2\" [\"unique_commits\"=\"3\"];\n\
+                               }\n";
+
+        assert_eq!(expected_result, String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_edge_list() {
+        let mut output: Vec<u8> = Vec::new();
+        example().write_edge_list_csv_to(&mut output).unwrap();
+
+        let expected_result = "source,target,unique_commits\nproject:1,project:2,3\n";
+
+        assert_eq!(expected_result, String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_graphml_escapes() {
+        let mut output: Vec<u8> = Vec::new();
+        example().write_graphml_to(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains(r#"<data key="n1">a &quot;b&quot; &lt;c&gt;</data>"#));
+        assert!(output.contains(r#"<graph edgedefault="directed">"#));
+    }
+
+    #[test]
+    fn test_repeated_headers() {
+        let attributes = node_attributes(&(3usize, 5usize, 8usize));
+        let expected_result = vec![
+            ("n".to_owned(), "3".to_owned()),
+            ("n_2".to_owned(), "5".to_owned()),
+            ("n_3".to_owned(), "8".to_owned()),
+        ];
+
+        assert_eq!(expected_result, attributes);
+    }
+}
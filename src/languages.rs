@@ -0,0 +1,314 @@
+mod table;
+
+pub use table::{Language, LanguageKind, LanguageDefinition, LANGUAGES};
+
+/**
+ * Content-aware language classification.
+ *
+ * Follows the strategies of GitHub's linguist, in order: modelines (Vim and Emacs), shebang lines,
+ * file extensions, and, for extensions that are shared by several languages, heuristics that look
+ * for characteristic keywords in the contents. Reading contents is expensive, so it is only done
+ * for files whose language cannot be decided from the extension alone: files without an extension
+ * and files with an ambiguous extension (see `needs_contents`). Everything else is classified by
+ * the extension-only `Language::from_path`, which also serves as the fallback when the contents of
+ * a file are not available.
+ */
+pub fn detect_language(location: &str, contents: Option<&str>) -> Option<Language> {
+    let from_extension = Language::from_path(location);
+    if !needs_contents(location) {
+        return from_extension;
+    }
+    let contents = match contents {
+        Some(contents) => contents,
+        None => return from_extension,
+    };
+    from_modeline(contents)
+        .or_else(|| from_shebang(contents))
+        .or_else(|| extension(location).and_then(|extension| disambiguate(&extension, contents)))
+        .or(from_extension)
+}
+
+/** Extensions that are commonly used by more than one language. */
+pub static AMBIGUOUS_EXTENSIONS: [&str; 11] = ["h", "m", "pl", "pro", "inc", "fs", "cl", "d", "t", "pp", "v"];
+
+fn extension(location: &str) -> Option<String> {
+    std::path::Path::new(location).extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_owned())
+}
+
+/** True for files whose language cannot be reliably decided without looking at the contents. */
+pub fn needs_contents(location: &str) -> bool {
+    match extension(location) {
+        None => Language::from_path(location).is_none(),
+        Some(extension) => AMBIGUOUS_EXTENSIONS.contains(&extension.as_str()),
+    }
+}
+
+/** Interprets a language or mode name as used by editors and interpreters. */
+pub fn from_name(name: &str) -> Option<Language> {
+    let name = name.to_lowercase();
+    match name.as_str() {
+        "cpp" | "cxx" | "c++"                           => Some(Language::Cpp),
+        "objc" | "objcpp"                               => Some(Language::ObjectiveC),
+        "cs" | "csharp"                                 => Some(Language::CSharp),
+        "js" | "javascript" | "js2" | "node" | "nodejs" => Some(Language::JavaScript),
+        "ts" | "typescript" | "deno" | "ts-node"        => Some(Language::TypeScript),
+        "py" | "python" | "pypy"                        => Some(Language::Python),
+        "perl" | "cperl"                                => Some(Language::Perl),
+        "rb" | "ruby" | "jruby"                         => Some(Language::Ruby),
+        "sh" | "bash" | "zsh" | "ksh" | "dash" | "ash"  => Some(Language::Shell),
+        "rscript"                                       => Some(Language::R),
+        "escript"                                       => Some(Language::Erlang),
+        "guile" | "csi" | "gsi" | "chicken"             => Some(Language::Scheme),
+        "sbcl" | "clisp" | "ecl"                        => Some(Language::Lisp),
+        "runhaskell" | "runghc"                         => Some(Language::Haskell),
+        "swipl" | "gprolog"                             => Some(Language::Prolog),
+        "rdmd"                                          => Some(Language::D),
+        "coffee"                                        => Some(Language::CoffeeScript),
+        "luajit"                                        => Some(Language::Lua),
+        "bb"                                            => Some(Language::Clojure),
+        name                                            => Language::from_str(name),
+    }
+}
+
+/**
+ * Reads the interpreter from a shebang line (eg. `#!/usr/bin/env python3` or `#!/bin/sh`).
+ * Version suffixes of interpreters are ignored (`python3.8` is `python`).
+ */
+pub fn from_shebang(contents: &str) -> Option<Language> {
+    let line = contents.lines().next()?;
+    if !line.starts_with("#!") {
+        return None;
+    }
+    let mut tokens = line[2..].split_whitespace();
+    let mut interpreter = tokens.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = tokens.filter(|token| !token.starts_with('-') && !token.contains('=')).next()?;
+    }
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.' || c == '-');
+    from_name(interpreter)
+}
+
+/**
+ * Reads the language from a Vim modeline (`vim: set ft=python:`) in the first or last five lines
+ * of the file, or from an Emacs modeline (`-*- mode: python -*-`) in its first two lines.
+ */
+pub fn from_modeline(contents: &str) -> Option<Language> {
+    let lines: Vec<&str> = contents.lines().collect();
+    let head = lines.iter().take(5);
+    let tail = lines.iter().skip(lines.len().saturating_sub(5));
+    let vim = head.chain(tail).filter_map(|line| from_vim_modeline(line)).next();
+    vim.or_else(|| lines.iter().take(2).filter_map(|line| from_emacs_modeline(line)).next())
+}
+
+fn from_vim_modeline(line: &str) -> Option<Language> {
+    let start = ["vim:", "vi:", "ex:"].iter().filter_map(|marker| line.find(marker)).min()?;
+    let settings = &line[start..];
+    ["filetype=", "ft=", "syntax="].iter()
+        .filter_map(|key| settings.find(key).map(|position| &settings[position + key.len()..]))
+        .map(|value| value.chars().take_while(|c| c.is_alphanumeric() || *c == '+' || *c == '#').collect::<String>())
+        .filter(|value| !value.is_empty())
+        .filter_map(|value| from_name(&value))
+        .next()
+}
+
+fn from_emacs_modeline(line: &str) -> Option<Language> {
+    let start = line.find("-*-")? + 3;
+    let end = line[start..].find("-*-")? + start;
+    let settings = line[start..end].trim();
+    if !settings.contains(':') {
+        return from_name(settings.trim_end_matches("-mode"));
+    }
+    settings.split(';')
+        .filter_map(|setting| {
+            let mut pair = setting.splitn(2, ':');
+            let key = pair.next()?.trim();
+            let value = pair.next()?.trim();
+            if key.eq_ignore_ascii_case("mode") { Some(value.trim_end_matches("-mode").to_owned()) } else { None }
+        })
+        .filter_map(|value| from_name(&value))
+        .next()
+}
+
+fn any_line_starts_with(contents: &str, prefixes: &[&str]) -> bool {
+    contents.lines().any(|line| {
+        let line = line.trim_start();
+        prefixes.iter().any(|prefix| line.starts_with(prefix))
+    })
+}
+
+fn contains_any(contents: &str, needles: &[&str]) -> bool {
+    needles.iter().any(|needle| contents.contains(needle))
+}
+
+/**
+ * Keyword heuristics for ambiguous extensions. Returns None if the heuristics are inconclusive, in
+ * which case the caller falls back to the extension table. Languages we do not have a variant for
+ * (eg. OpenCL) are classified as `Language::Other`.
+ */
+pub fn disambiguate(extension: &str, contents: &str) -> Option<Language> {
+    match extension {
+        "h" => {
+            if contains_any(contents, &["@interface", "@end", "@property", "@protocol"])
+                || any_line_starts_with(contents, &["#import"]) {
+                Some(Language::ObjectiveC)
+            } else if contains_any(contents, &["std::", "namespace ", "template<", "template <", "public:", "private:", "protected:", "virtual "])
+                || any_line_starts_with(contents, &["class ", "#include <iostream>", "#include <string>", "#include <vector>", "#include <map>", "#include <memory>"]) {
+                Some(Language::Cpp)
+            } else {
+                Some(Language::C)
+            }
+        }
+        "m" => {
+            if contains_any(contents, &["@interface", "@implementation", "@end", "@property", "@synthesize", "NSString", "NSObject"])
+                || any_line_starts_with(contents, &["#import", "#include"]) {
+                Some(Language::ObjectiveC)
+            } else if any_line_starts_with(contents, &[":- module", ":- interface", ":- implementation"]) {
+                Some(Language::Mercury)
+            } else if any_line_starts_with(contents, &["function ", "%", "end", "disp(", "fprintf("]) {
+                Some(Language::MATLAB)
+            } else {
+                None
+            }
+        }
+        "pl" | "pro" => {
+            if any_line_starts_with(contents, &[":-"])
+                || contents.lines().any(|line| line.contains(") :-") || line.trim_end().ends_with(":-")) {
+                Some(Language::Prolog)
+            } else if any_line_starts_with(contents, &["use v6", "unit module", "unit class"]) {
+                Some(Language::Raku)
+            } else if contains_any(contents, &["use strict", "use warnings", "my $", "my @", "my %", "$_", "print "])
+                || any_line_starts_with(contents, &["sub ", "package "]) {
+                Some(Language::Perl)
+            } else if extension == "pro" && contains_any(contents, &["SOURCES", "HEADERS", "TEMPLATE", "QT +="]) {
+                Some(Language::QMake)
+            } else if extension == "pro" && any_line_starts_with(&contents.to_lowercase(), &["pro ", "function ", "end"]) {
+                Some(Language::IDL)
+            } else {
+                None
+            }
+        }
+        "inc" => {
+            if contents.contains("<?php") || contents.contains("<?=") {
+                Some(Language::PHP)
+            } else if any_line_starts_with(contents, &["section ", "mov ", "db ", "dw ", "dd ", "%macro", "%define"]) {
+                Some(Language::ASM)
+            } else if any_line_starts_with(contents, &["#include", "#define", "#ifndef", "#if "]) {
+                Some(Language::C)
+            } else {
+                None
+            }
+        }
+        "fs" => {
+            if contains_any(contents, &["gl_FragColor", "gl_Position", "uniform ", "varying "])
+                || any_line_starts_with(contents, &["#version", "precision "]) {
+                Some(Language::GLSL)
+            } else if any_line_starts_with(contents, &["let ", "open ", "module ", "type ", "namespace ", "[<"]) {
+                Some(Language::FSharp)
+            } else if any_line_starts_with(contents, &[": ", "\\ "]) {
+                Some(Language::Forth)
+            } else {
+                None
+            }
+        }
+        "cl" => {
+            if contains_any(contents, &["__kernel", "kernel void", "get_global_id"]) {
+                Some(Language::Other) // OpenCL
+            } else if any_line_starts_with(contents, &["(def", "(in-package", "(require", ";;"]) {
+                Some(Language::Lisp)
+            } else {
+                None
+            }
+        }
+        "d" => {
+            if any_line_starts_with(contents, &["module ", "import ", "void main", "int main"]) {
+                Some(Language::D)
+            } else if contents.lines().any(|line| line.contains(".o:") || line.contains(".o :")) {
+                Some(Language::Makefile) // dependencies generated by compilers
+            } else {
+                None
+            }
+        }
+        "t" => {
+            if any_line_starts_with(contents, &["use v6"]) {
+                Some(Language::Raku)
+            } else if contains_any(contents, &["use strict", "use warnings", "use Test", "done_testing", "my $"]) {
+                Some(Language::Perl)
+            } else if contains_any(contents, &["end if", "end loop", "put "]) {
+                Some(Language::Turing)
+            } else {
+                None
+            }
+        }
+        "v" => {
+            if contains_any(contents, &["Theorem ", "Lemma ", "Proof.", "Qed.", "Require Import", "Inductive "]) {
+                Some(Language::Coq)
+            } else if contains_any(contents, &["endmodule", "always @", "posedge", "negedge"]) {
+                Some(Language::Verilog)
+            } else if contains_any(contents, &["fn main()", "module main"]) || any_line_starts_with(contents, &["fn ", "pub fn "]) {
+                Some(Language::V)
+            } else {
+                None
+            }
+        }
+        "pp" => {
+            let lowercase = contents.to_lowercase();
+            if any_line_starts_with(&lowercase, &["program ", "unit ", "uses ", "procedure ", "function ", "begin"]) {
+                Some(Language::Pascal)
+            } else if any_line_starts_with(contents, &["class ", "define ", "node ", "include "]) || contents.contains("=>") {
+                Some(Language::Puppet)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::languages::*;
+    use crate::objects::Language;
+
+    #[test] fn test_shebang_and_modelines() {
+        assert_eq!(from_shebang("#!/usr/bin/env python3\nprint(1)\n"), Some(Language::Python));
+        assert_eq!(from_shebang("#!/bin/bash\necho 1\n"), Some(Language::Shell));
+        assert_eq!(from_shebang("#!/usr/bin/env -S node --harmony\n"), Some(Language::JavaScript));
+        assert_eq!(from_modeline("# -*- mode: ruby -*-\nputs 1\n"), Some(Language::Ruby));
+        assert_eq!(from_modeline("x = 1\n# vim: set ft=python ts=4:\n"), Some(Language::Python));
+        assert_eq!(detect_language("bin/run", Some("#!/usr/bin/perl\nprint 1;\n")), Some(Language::Perl));
+        assert_eq!(detect_language("bin/run", None), None);
+    }
+
+    #[test] fn test_ambiguous_extensions() {
+        assert_eq!(detect_language("a.h", Some("#include <vector>\nclass A {};\n")), Some(Language::Cpp));
+        assert_eq!(detect_language("a.h", Some("@interface A : NSObject\n@end\n")), Some(Language::ObjectiveC));
+        assert_eq!(detect_language("a.h", Some("int f(void);\n")), Some(Language::C));
+        assert_eq!(detect_language("a.pl", Some("parent(tom, bob).\nancestor(X, Y) :- parent(X, Y).\n")), Some(Language::Prolog));
+        assert_eq!(detect_language("a.pl", Some("use strict;\nmy $x = 1;\n")), Some(Language::Perl));
+        assert_eq!(detect_language("a.m", Some("function y = f(x)\n  y = x;\nend\n")), Some(Language::MATLAB));
+        assert_eq!(detect_language("a.h", None), Some(Language::C));
+    }
+
+    #[test] fn test_encountered_languages_are_known() {
+        for line in include_str!("../encountered_languages.notes").lines() {
+            let name = line.trim().split('"').nth(1).unwrap();
+            assert!(Language::from_str(name).is_some(), "unknown language: {}", name);
+        }
+    }
+
+    #[test] fn test_language_table() {
+        assert_eq!(Language::from_str("Common Lisp"), Some(Language::Lisp));
+        assert_eq!(Language::from_str("sqlpl"), Some(Language::SQLPL));
+        assert_eq!(Language::from_path("src/Makefile"), Some(Language::Makefile));
+        assert_eq!(Language::from_path("config.yml"), Some(Language::YAML));
+        assert_eq!(Language::YAML.kind(), Some(LanguageKind::Data));
+        assert_eq!(Language::Cpp.to_string(), "C++");
+        assert_eq!(Language::Other.kind(), None);
+    }
+
+    #[test] fn test_unambiguous_extensions_ignore_contents() {
+        assert_eq!(detect_language("a.rs", Some("#!/usr/bin/env python\n")), Some(Language::Rust));
+    }
+}
@@ -0,0 +1,438 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Serialize, Deserialize};
+
+/** The kind of a language, following GitHub's linguist. */
+#[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub enum LanguageKind { Programming, Markup, Data, Prose }
+
+impl Display for LanguageKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let string = match self {
+            LanguageKind::Programming => "programming",
+            LanguageKind::Markup => "markup",
+            LanguageKind::Data => "data",
+            LanguageKind::Prose => "prose",
+        };
+        f.write_str(string)
+    }
+}
+
+/** A row of the language table. */
+#[derive(Debug)]
+pub struct LanguageDefinition {
+    pub language: Language,
+    pub name: &'static str,
+    pub kind: LanguageKind,
+    pub aliases: &'static [&'static str],
+    pub extensions: &'static [&'static str],
+    pub filenames: &'static [&'static str],
+}
+
+/**
+ * Generates the `Language` enum and the `LANGUAGES` table from a single list of definitions.
+ *
+ * Each row consists of the variant, the display name, the kind, lowercase aliases (the lowercase
+ * display name is always accepted, so it need not be listed), extensions (case-sensitive, without
+ * the dot), and optionally exact file names. When several languages claim the same extension, the
+ * row that comes first wins.
+ */
+macro_rules! language_table {
+    ($($variant:ident => $name:expr, $kind:ident, [$($alias:expr),*], [$($extension:expr),*] $(, files [$($filename:expr),*])?;)*) => {
+        #[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+        pub enum Language {
+            $($variant,)*
+            // special category for languages we do not yet recognize and anything we do not know
+            Other
+        }
+
+        pub static LANGUAGES: &[LanguageDefinition] = &[
+            $(LanguageDefinition {
+                language: Language::$variant,
+                name: $name,
+                kind: LanguageKind::$kind,
+                aliases: &[$($alias),*],
+                extensions: &[$($extension),*],
+                filenames: &[$($($filename),*)?],
+            },)*
+        ];
+    }
+}
+
+// Languages that were classified by djanco before the table existed come first, so that they keep
+// their extensions when other languages claim the same ones.
+language_table! {
+    ASM                     => "Assembly",          Programming, ["asm", "assembly", "nasm"],          ["asm", "s", "nasm"];
+    ASP                     => "ASP",               Programming, ["classic asp", "asp.net"],           ["asp", "asax", "ascx", "ashx", "asmx", "aspx"];
+    ActionScript            => "ActionScript",      Programming, ["actionscript 3", "as3"],            ["as", "swf"];
+    C                       => "C",                 Programming, [],                                   ["c", "h"];
+    Cpp                     => "C++",               Programming, ["cpp"],                              ["C", "cc", "cpp", "cxx", "c++", "hpp", "hh", "hxx", "h++"];
+    CSharp                  => "C#",                Programming, ["csharp"],                           ["cs", "csx"];
+    CoffeeScript            => "CoffeeScript",      Programming, ["coffee"],                           ["coffee", "litcoffee", "cake"];
+    Lisp                    => "LISP",              Programming, ["common lisp"],                      ["lisp", "lsp", "l", "cl", "fasl", "asd"];
+    Cobol                   => "Cobol",             Programming, [],                                   ["cbl", "cob", "cpy", "ccp"];
+    CSS                     => "CSS",               Markup,      [],                                   ["css"];
+    Clojure                 => "Clojure",           Programming, [],                                   ["clj", "cljs", "cljc", "edn", "boot"];
+    D                       => "D",                 Programming, [],                                   ["d", "di"];
+    Eiffel                  => "Eiffel",            Programming, [],                                   ["e"];
+    Elixir                  => "Elixir",            Programming, [],                                   ["ex", "exs"];
+    Elm                     => "Elm",               Programming, [],                                   ["elm"];
+    Erlang                  => "Erlang",            Programming, [],                                   ["erl", "hrl", "escript", "xrl", "yrl"];
+    FSharp                  => "F#",                Programming, ["fsharp"],                           ["fs", "fsi", "fsx"];
+    Fortran                 => "Fortran",           Programming, [],                                   ["f90", "for", "f", "f77", "f95", "f03", "f08", "F", "F90"];
+    Go                      => "Go",                Programming, ["golang"],                           ["go"];
+    Groovy                  => "Groovy",            Programming, [],                                   ["groovy", "gvy", "gy", "gsh"];
+    HTML                    => "HTML",              Markup,      ["xhtml"],                            ["html", "htm", "xhtml"];
+    Haskell                 => "Haskell",           Programming, [],                                   ["hs", "lhs", "hs-boot"];
+    Java                    => "Java",              Programming, [],                                   ["java"];
+    JavaScript              => "JavaScript",        Programming, ["js", "node"],                       ["js", "mjs", "cjs", "jsx"];
+    Julia                   => "Julia",             Programming, [],                                   ["jl"];
+    Kotlin                  => "Kotlin",            Programming, [],                                   ["kt", "kts"];
+    Lua                     => "Lua",               Programming, [],                                   ["lua"];
+    ObjectiveC              => "Objective-C",       Programming, ["objective c", "objectivec", "objc"], ["m", "M"];
+    OCaml                   => "OCaml",             Programming, [],                                   ["ml", "mli", "mll", "mly"];
+    PHP                     => "PHP",               Programming, [],                                   ["php", "phtml", "php3", "php4", "php5", "php7", "phps", "php-s", "pht", "phar"];
+    Pascal                  => "Pascal",            Programming, ["delphi", "objectpascal"],           ["pp", "pas", "inc", "dpr", "lpr"];
+    Python                  => "Python",            Programming, [],                                   ["py", "pyi", "pyc", "pyd", "pyo", "pyw", "pyz"];
+    Perl                    => "Perl",              Programming, ["perl 6", "perl6"],                  ["plx", "pl", "pm", "t", "pod"];
+    Prolog                  => "Prolog",            Programming, [],                                   ["pro", "prolog"];
+    R                       => "R",                 Programming, ["rscript"],                          ["r", "R", "rscript"];
+    Racket                  => "Racket",            Programming, [],                                   ["rkt", "rktl", "rktd"];
+    Ruby                    => "Ruby",              Programming, ["jruby"],                            ["rb", "rake", "gemspec", "ru", "rbw"], files ["Rakefile", "Gemfile"];
+    Rust                    => "Rust",              Programming, [],                                   ["rs"];
+    Scala                   => "Scala",             Programming, [],                                   ["scala", "sc"];
+    SQL                     => "SQL",               Data,        [],                                   ["sql", "pls", "pks"];
+    Scheme                  => "Scheme",            Programming, [],                                   ["scm", "ss", "sls", "sps", "sld"];
+    Shell                   => "Shell",             Programming, ["sh", "bash", "zsh", "shell-script"], ["sh", "bash", "zsh", "ksh", "command"];
+    Swift                   => "Swift",             Programming, [],                                   ["swift"];
+    TypeScript              => "TypeScript",        Programming, ["ts"],                               ["ts", "tsx", "mts", "cts"];
+    VisualBasic             => "Visual Basic",      Programming, ["visual basic .net", "vb.net", "vbnet"], ["vb"];
+
+    AGS                     => "AGS Script",        Programming, ["ags"],                              ["asc", "ash"];
+    AMPL                    => "AMPL",              Programming, [],                                   ["ampl"];
+    ANTLR                   => "ANTLR",             Programming, [],                                   ["g4"];
+    API                     => "API Blueprint",     Markup,      [],                                   ["apib"];
+    ASL                     => "ASL",               Programming, [],                                   ["asl", "dsl"];
+    Ada                     => "Ada",               Programming, [],                                   ["adb", "ads", "ada"];
+    Agda                    => "Agda",              Programming, [],                                   ["agda"];
+    ApacheConf              => "ApacheConf",        Data,        ["apache"],                           ["apacheconf", "vhost"], files [".htaccess", "httpd.conf"];
+    Apex                    => "Apex",              Programming, [],                                   ["apex", "trigger"];
+    AppleScript             => "AppleScript",       Programming, [],                                   ["applescript", "scpt"];
+    Arc                     => "Arc",               Programming, [],                                   ["arc"];
+    Arduino                 => "Arduino",           Programming, [],                                   ["ino"];
+    AspectJ                 => "AspectJ",           Programming, [],                                   ["aj"];
+    AutoHotkey              => "AutoHotkey",        Programming, ["ahk"],                              ["ahk", "ahkl"];
+    AutoIt                  => "AutoIt",            Programming, [],                                   ["au3"];
+    Awk                     => "Awk",               Programming, ["gawk", "mawk", "nawk"],             ["awk", "gawk", "mawk", "nawk"];
+    Batchfile               => "Batchfile",         Programming, ["batch", "bat"],                     ["bat", "cmd"];
+    BitBake                 => "BitBake",           Programming, [],                                   ["bb", "bbappend", "bbclass"];
+    Blade                   => "Blade",             Markup,      [],                                   ["blade"];
+    BlitzBasic              => "BlitzBasic",        Programming, [],                                   ["decls"];
+    BlitzMax                => "BlitzMax",          Programming, [],                                   ["bmx"];
+    Bluespec                => "Bluespec",          Programming, [],                                   ["bsv"];
+    Boo                     => "Boo",               Programming, [],                                   ["boo"];
+    Brainfuck               => "Brainfuck",         Programming, [],                                   ["bf", "b"];
+    CLIPS                   => "CLIPS",             Programming, [],                                   ["clp"];
+    CMake                   => "CMake",             Programming, [],                                   ["cmake"], files ["CMakeLists.txt"];
+    CWeb                    => "CWeb",              Programming, [],                                   ["w"];
+    CapNProto               => "Cap'n Proto",       Programming, ["capnp"],                            ["capnp"];
+    CartoCSS                => "CartoCSS",          Programming, [],                                   ["mss"];
+    ChucK                   => "ChucK",             Programming, [],                                   ["ck"];
+    Clarion                 => "Clarion",           Programming, [],                                   ["clw"];
+    CommonWorkflowLanguage  => "Common Workflow Language", Programming, ["cwl"],                       ["cwl"];
+    ComponentPascal         => "Component Pascal",  Programming, [],                                   ["cp", "cps"];
+    Cool                    => "Cool",              Programming, [],                                   [];
+    Coq                     => "Coq",               Programming, [],                                   ["coq"];
+    Crystal                 => "Crystal",           Programming, [],                                   ["cr"];
+    Csound                  => "Csound",            Programming, [],                                   ["orc", "udo"];
+    CsoundDocument          => "Csound Document",   Programming, [],                                   ["csd"];
+    CsoundScore             => "Csound Score",      Programming, [],                                   ["sco"];
+    Cuda                    => "Cuda",              Programming, [],                                   ["cu", "cuh"];
+    Cycript                 => "Cycript",           Programming, [],                                   ["cy"];
+    DIGITAL                 => "DIGITAL Command Language", Programming, ["dcl"],                       ["com"];
+    DM                      => "DM",                Programming, [],                                   ["dm"];
+    DTrace                  => "DTrace",            Programming, [],                                   [];
+    Dart                    => "Dart",              Programming, [],                                   ["dart"];
+    Dhall                   => "Dhall",             Programming, [],                                   ["dhall"];
+    Dockerfile              => "Dockerfile",        Programming, ["docker"],                           ["dockerfile"], files ["Dockerfile"];
+    E                       => "E",                 Programming, [],                                   ["E"];
+    EQ                      => "EQ",                Programming, [],                                   ["eq"];
+    Eagle                   => "Eagle",             Data,        [],                                   ["sch", "brd"];
+    EmacsLisp               => "Emacs Lisp",        Programming, ["elisp", "emacs"],                   ["el", "emacs"], files [".emacs"];
+    EmberScript             => "EmberScript",       Programming, [],                                   ["em", "emberscript"];
+    FStar                   => "F*",                Programming, ["fstar"],                            ["fst", "fsti"];
+    Fancy                   => "Fancy",             Programming, [],                                   ["fy", "fancypack"];
+    Faust                   => "Faust",             Programming, [],                                   ["dsp"];
+    Forth                   => "Forth",             Programming, [],                                   ["fth", "4th", "forth", "frt"];
+    FreeMarker              => "FreeMarker",        Programming, ["ftl"],                              ["ftl"];
+    Frege                   => "Frege",             Programming, [],                                   ["fr"];
+    Futhark                 => "Futhark",           Programming, [],                                   ["fut"];
+    GCode                   => "G-code",            Programming, ["gcode"],                            ["gcode", "gco"];
+    GAML                    => "GAML",              Programming, [],                                   ["gaml"];
+    GAMS                    => "GAMS",              Programming, [],                                   ["gms"];
+    GAP                     => "GAP",               Programming, [],                                   ["gap", "gi"];
+    GCCMachineDescription   => "GCC Machine Description", Programming, [],                             [];
+    GDB                     => "GDB",               Programming, [],                                   ["gdb", "gdbinit"], files [".gdbinit"];
+    GDScript                => "GDScript",          Programming, [],                                   ["gd"];
+    GLSL                    => "GLSL",              Programming, [],                                   ["glsl", "vert", "frag", "geom", "fsh"];
+    GameMakerLanguage       => "Game Maker Language", Programming, ["gml"],                            ["gml"];
+    Gherkin                 => "Gherkin",           Programming, ["cucumber"],                         ["feature"];
+    Gnuplot                 => "Gnuplot",           Programming, [],                                   ["gp", "gnuplot", "plt", "plot"];
+    GrammaticalFramework    => "Grammatical Framework", Programming, ["gf"],                           ["gf"];
+    HCL                     => "HCL",               Programming, ["terraform"],                        ["hcl", "tf", "tfvars"];
+    HLSL                    => "HLSL",              Programming, [],                                   ["hlsl", "fx", "fxh", "hlsli"];
+    Hack                    => "Hack",              Programming, [],                                   ["hack", "hhi"];
+    Handlebars              => "Handlebars",        Markup,      [],                                   ["handlebars", "hbs"];
+    Haxe                    => "Haxe",              Programming, [],                                   ["hx", "hxsl"];
+    HiveQL                  => "HiveQL",            Programming, [],                                   ["hql"];
+    Hy                      => "Hy",                Programming, [],                                   ["hy"];
+    HyPhy                   => "HyPhy",             Programming, [],                                   [];
+    IDL                     => "IDL",               Programming, [],                                   ["dlm"];
+    Idris                   => "Idris",             Programming, [],                                   ["idr", "lidr"];
+    Inform7                 => "Inform 7",          Programming, [],                                   ["ni", "i7x"];
+    InnoSetup               => "Inno Setup",        Programming, [],                                   ["iss", "isl"];
+    Io                      => "Io",                Programming, [],                                   ["io"];
+    JSON                    => "JSON",              Data,        [],                                   ["json", "geojson", "topojson", "webmanifest"];
+    JSONiq                  => "JSONiq",            Programming, [],                                   ["jq"];
+    Jasmin                  => "Jasmin",            Programming, [],                                   ["j"];
+    Jsonnet                 => "Jsonnet",           Programming, [],                                   ["jsonnet", "libsonnet"];
+    JupyterNotebook         => "Jupyter Notebook",  Markup,      ["ipython notebook"],                 ["ipynb"];
+    KiCad                   => "KiCad",             Data,        ["kicad layout", "kicad schematic"],  ["kicad_pcb", "kicad_mod", "kicad_sch"];
+    LLVM                    => "LLVM",              Programming, [],                                   ["ll"];
+    LOLCODE                 => "LOLCODE",           Programming, [],                                   ["lol"];
+    LSL                     => "LSL",               Programming, [],                                   ["lsl", "lslp"];
+    LabVIEW                 => "LabVIEW",           Programming, [],                                   ["lvproj", "lvclass", "lvlib"];
+    Lasso                   => "Lasso",             Programming, [],                                   ["lasso", "las", "lasso8", "lasso9"];
+    Lean                    => "Lean",              Programming, [],                                   ["lean", "hlean"];
+    Less                    => "Less",              Markup,      [],                                   ["less"];
+    Lex                     => "Lex",               Programming, ["flex"],                             ["lex"];
+    LilyPond                => "LilyPond",          Programming, [],                                   ["ly", "ily"];
+    Limbo                   => "Limbo",             Programming, [],                                   [];
+    Liquid                  => "Liquid",            Markup,      [],                                   ["liquid"];
+    LiveScript              => "LiveScript",        Programming, [],                                   ["ls"];
+    Logos                   => "Logos",             Programming, [],                                   ["xm", "x", "xi"];
+    M                       => "M",                 Programming, ["mumps"],                            ["mumps"];
+    M4                      => "M4",                Programming, [],                                   ["m4"];
+    MATLAB                  => "MATLAB",            Programming, ["octave"],                           ["matlab"];
+    MAXScript               => "MAXScript",         Programming, [],                                   ["ms", "mcr"];
+    MLIR                    => "MLIR",              Programming, [],                                   ["mlir"];
+    MQL4                    => "MQL4",              Programming, [],                                   ["mq4", "mqh"];
+    MQL5                    => "MQL5",              Programming, [],                                   ["mq5"];
+    Macaulay2               => "Macaulay2",         Programming, [],                                   ["m2"];
+    Makefile                => "Makefile",          Programming, ["make", "bsdmake"],                  ["mak", "mk", "make", "mkfile"], files ["Makefile", "makefile", "GNUmakefile"];
+    Mako                    => "Mako",              Programming, [],                                   ["mako", "mao"];
+    Markdown                => "Markdown",          Prose,       ["md"],                               ["md", "markdown", "mdown", "mkd", "mkdn"];
+    Mathematica             => "Mathematica",       Programming, ["wolfram language"],                 ["mathematica", "nb", "wl", "wls"];
+    Max                     => "Max",               Programming, [],                                   ["maxpat", "maxhelp"];
+    Mercury                 => "Mercury",           Programming, [],                                   ["moo"];
+    Meson                   => "Meson",             Programming, [],                                   [], files ["meson.build", "meson_options.txt"];
+    Metal                   => "Metal",             Programming, [],                                   ["metal"];
+    Modelica                => "Modelica",          Programming, [],                                   ["mo"];
+    Modula2                 => "Modula-2",          Programming, [],                                   ["mod"];
+    Modula3                 => "Modula-3",          Programming, [],                                   ["m3", "i3", "mg", "ig"];
+    NASL                    => "NASL",              Programming, [],                                   ["nasl"];
+    NCL                     => "NCL",               Programming, [],                                   ["ncl"];
+    NSIS                    => "NSIS",              Programming, [],                                   ["nsi", "nsh"];
+    Nearley                 => "Nearley",           Programming, [],                                   ["ne", "nearley"];
+    NetLinx                 => "NetLinx",           Programming, [],                                   ["axs", "axi"];
+    NetLogo                 => "NetLogo",           Programming, [],                                   ["nlogo"];
+    NewLisp                 => "NewLisp",           Programming, [],                                   ["nl"];
+    Nextflow                => "Nextflow",          Programming, [],                                   ["nf"];
+    Nginx                   => "Nginx",             Data,        ["nginx configuration file"],         ["nginxconf", "nginx"], files ["nginx.conf"];
+    Nim                     => "Nim",               Programming, [],                                   ["nim", "nims", "nimble"];
+    Nix                     => "Nix",               Programming, ["nixos"],                            ["nix"];
+    ObjectiveCpp            => "Objective-C++",     Programming, ["objc++"],                           ["mm"];
+    ObjectiveJ              => "Objective-J",       Programming, [],                                   ["sj"];
+    Odin                    => "Odin",              Programming, [],                                   ["odin"];
+    Opa                     => "Opa",               Programming, [],                                   ["opa"];
+    OpenPolicyAgent         => "Open Policy Agent", Programming, ["rego"],                             ["rego"];
+    OpenEdgeABL             => "OpenEdge ABL",      Programming, ["progress", "abl"],                  ["p"];
+    OpenQASM                => "OpenQASM",          Programming, [],                                   ["qasm"];
+    OpenSCAD                => "OpenSCAD",          Programming, [],                                   ["scad"];
+    Oz                      => "Oz",                Programming, [],                                   ["oz"];
+    P4                      => "P4",                Programming, [],                                   ["p4"];
+    PLSQL                   => "PLSQL",             Programming, [],                                   ["pck", "pkb", "plsql"];
+    PLpgSQL                 => "PLpgSQL",           Programming, [],                                   ["pgsql"];
+    POVRaySDL               => "POV-Ray SDL",       Programming, ["pov-ray", "povray"],                ["pov"];
+    Papyrus                 => "Papyrus",           Programming, [],                                   ["psc"];
+    Parrot                  => "Parrot",            Programming, [],                                   ["parrot", "pasm", "pir"];
+    Pawn                    => "Pawn",              Programming, [],                                   ["pwn"];
+    Pep8                    => "Pep8",              Programming, [],                                   ["pep"];
+    PicoLisp                => "PicoLisp",          Programming, [],                                   [];
+    PigLatin                => "PigLatin",          Programming, [],                                   ["pig"];
+    PostScript              => "PostScript",        Markup,      ["postscr"],                          ["ps", "eps"];
+    PowerBuilder            => "PowerBuilder",      Programming, [],                                   ["pbt", "sra", "sru", "srw"];
+    PowerShell              => "PowerShell",        Programming, ["posh", "pwsh"],                     ["ps1", "psd1", "psm1"];
+    Processing              => "Processing",        Programming, [],                                   ["pde"];
+    PropellerSpin           => "Propeller Spin",    Programming, [],                                   ["spin"];
+    ProtocolBuffer          => "Protocol Buffer",   Data,        ["protobuf", "proto"],                ["proto"];
+    Pug                     => "Pug",               Markup,      ["jade"],                             ["jade", "pug"];
+    Puppet                  => "Puppet",            Programming, [],                                   [];
+    PureData                => "Pure Data",         Data,        [],                                   ["pd"];
+    PureBasic               => "PureBasic",         Programming, [],                                   ["pb", "pbi"];
+    PureScript              => "PureScript",        Programming, [],                                   ["purs"];
+    QSharp                  => "Q#",                Programming, ["qsharp"],                           ["qs"];
+    QML                     => "QML",               Programming, [],                                   ["qml", "qbs"];
+    QMake                   => "QMake",             Programming, [],                                   ["pri"];
+    QtScript                => "Qt Script",         Programming, [],                                   [];
+    RAML                    => "RAML",              Markup,      [],                                   ["raml"];
+    REXX                    => "REXX",              Programming, [],                                   ["rexx", "rex", "pprx"];
+    RPC                     => "RPC",               Programming, ["rpcgen", "oncrpc"],                 [];
+    Ragel                   => "Ragel",             Programming, [],                                   ["rl"];
+    Raku                    => "Raku",              Programming, [],                                   ["raku", "rakumod", "p6", "pm6"];
+    Rascal                  => "Rascal",            Programming, [],                                   ["rsc"];
+    Reason                  => "Reason",            Programming, [],                                   ["re", "rei"];
+    Rebol                   => "Rebol",             Programming, [],                                   ["reb", "rebol"];
+    Red                     => "Red",               Programming, [],                                   ["red", "reds"];
+    RenPy                   => "Ren'Py",            Programming, ["renpy"],                            ["rpy"];
+    RenderScript            => "RenderScript",      Programming, [],                                   ["rsh"];
+    RichTextFormat          => "Rich Text Format",  Markup,      [],                                   ["rtf"];
+    RobotFramework          => "RobotFramework",    Programming, [],                                   ["robot"];
+    Roff                    => "Roff",              Markup,      ["groff", "nroff", "troff"],          ["roff", "man", "me", "mdoc", "1", "2", "3", "5", "7", "8"];
+    SAS                     => "SAS",               Programming, [],                                   ["sas"];
+    SCSS                    => "SCSS",              Markup,      [],                                   ["scss"];
+    SMT                     => "SMT",               Programming, [],                                   ["smt2", "smt"];
+    SQF                     => "SQF",               Programming, [],                                   ["sqf", "hqf"];
+    SQLPL                   => "SQLPL",             Programming, [],                                   ["db2"];
+    SRecodeTemplate         => "SRecode Template",  Markup,      [],                                   ["srt"];
+    SWIG                    => "SWIG",              Programming, [],                                   ["swg", "i"];
+    Sage                    => "Sage",              Programming, [],                                   ["sage", "sagews"];
+    SaltStack               => "SaltStack",         Programming, ["salt", "saltstate"],                [];
+    Sass                    => "Sass",              Markup,      [],                                   ["sass"];
+    Scilab                  => "Scilab",            Programming, [],                                   ["sci", "sce"];
+    ShaderLab               => "ShaderLab",         Programming, [],                                   ["shader"];
+    Sieve                   => "Sieve",             Programming, [],                                   ["sieve"];
+    Slash                   => "Slash",             Programming, [],                                   ["sl"];
+    Slice                   => "Slice",             Programming, [],                                   ["ice"];
+    Smali                   => "Smali",             Programming, [],                                   ["smali"];
+    Smalltalk               => "Smalltalk",         Programming, ["squeak"],                           ["st"];
+    Smarty                  => "Smarty",            Programming, [],                                   ["tpl"];
+    Solidity                => "Solidity",          Programming, [],                                   ["sol"];
+    SourcePawn              => "SourcePawn",        Programming, [],                                   ["sp", "sma"];
+    Squirrel                => "Squirrel",          Programming, [],                                   ["nut"];
+    Stan                    => "Stan",              Programming, [],                                   ["stan"];
+    StandardML              => "Standard ML",       Programming, ["sml"],                              ["sml", "sig", "fun"];
+    Starlark                => "Starlark",          Programming, ["bazel", "bzl"],                     ["bzl", "star"], files ["BUILD", "BUILD.bazel", "WORKSPACE"];
+    Stata                   => "Stata",             Programming, [],                                   ["do", "ado"];
+    SuperCollider           => "SuperCollider",     Programming, [],                                   ["scd"];
+    Svelte                  => "Svelte",            Markup,      [],                                   ["svelte"];
+    SystemVerilog           => "SystemVerilog",     Programming, [],                                   ["sv", "svh"];
+    TLA                     => "TLA",               Programming, [],                                   ["tla"];
+    TSQL                    => "TSQL",              Programming, [],                                   [];
+    Tcl                     => "Tcl",               Programming, [],                                   ["tcl", "tk", "tm"];
+    TeX                     => "TeX",               Markup,      ["latex"],                            ["tex", "sty", "cls", "ltx", "dtx"];
+    Terra                   => "Terra",             Programming, [],                                   [];
+    Thrift                  => "Thrift",            Programming, [],                                   ["thrift"];
+    Turing                  => "Turing",            Programming, [],                                   ["tu"];
+    UnrealScript            => "UnrealScript",      Programming, [],                                   ["uc"];
+    V                       => "V",                 Programming, ["vlang"],                            ["vsh"];
+    VBA                     => "VBA",               Programming, [],                                   ["vba"];
+    VBScript                => "VBScript",          Programming, [],                                   ["vbs"];
+    VCL                     => "VCL",               Programming, [],                                   ["vcl"];
+    VHDL                    => "VHDL",              Programming, [],                                   ["vhd", "vhdl"];
+    Vala                    => "Vala",              Programming, [],                                   ["vala", "vapi"];
+    Verilog                 => "Verilog",           Programming, [],                                   ["v", "veo"];
+    VimSnippet              => "Vim Snippet",       Markup,      ["snipmate"],                         ["snip"];
+    VimScript               => "Vim Script",        Programming, ["viml", "vim"],                      ["vim", "vimrc"], files [".vimrc", "_vimrc"];
+    Volt                    => "Volt",              Programming, [],                                   ["volt"];
+    Vue                     => "Vue",               Markup,      [],                                   ["vue"];
+    WebOntologyLanguage     => "Web Ontology Language", Data,    [],                                   ["owl"];
+    WebAssembly             => "WebAssembly",       Programming, ["wast", "wasm"],                     ["wat", "wast"];
+    X10                     => "X10",               Programming, [],                                   ["x10"];
+    XC                      => "XC",                Programming, [],                                   ["xc"];
+    XML                     => "XML",               Data,        ["xsd", "rss"],                       ["xml", "xsd", "xaml", "csproj", "vcxproj", "plist", "svg", "rss", "xib", "iml"];
+    XProc                   => "XProc",             Programming, [],                                   ["xpl", "xproc"];
+    XQuery                  => "XQuery",            Programming, [],                                   ["xquery", "xq", "xql", "xqm", "xqy"];
+    XS                      => "XS",                Programming, [],                                   ["xs"];
+    XSLT                    => "XSLT",              Programming, ["xsl"],                              ["xslt", "xsl"];
+    Xtend                   => "Xtend",             Programming, [],                                   ["xtend"];
+    YAML                    => "YAML",              Data,        ["yml"],                              ["yml", "yaml"];
+    YARA                    => "YARA",              Programming, [],                                   ["yar", "yara"];
+    YASnippet               => "YASnippet",         Markup,      ["snippet", "yas"],                   ["yasnippet"];
+    Yacc                    => "Yacc",              Programming, ["bison"],                            ["y", "yy", "yacc"];
+    Zeek                    => "Zeek",              Programming, ["bro"],                              ["zeek", "bro"];
+    Zig                     => "Zig",               Programming, [],                                   ["zig"];
+    Zimpl                   => "Zimpl",             Programming, [],                                   ["zimpl", "zmpl", "zpl"];
+    EC                      => "eC",                Programming, [],                                   ["ec", "eh"];
+    MIRCScript              => "mIRC Script",       Programming, [],                                   ["mrc"];
+    McFunction              => "mcfunction",        Programming, [],                                   ["mcfunction"];
+    MuPAD                   => "mupad",             Programming, [],                                   ["mu"];
+    NesC                    => "nesC",              Programming, [],                                   ["nc"];
+    Q                       => "q",                 Programming, [],                                   ["q"];
+    ReStructuredText        => "reStructuredText",  Prose,       ["rst"],                              ["rst", "rest"];
+    Sed                     => "sed",               Programming, [],                                   ["sed"];
+    WDL                     => "wdl",               Programming, [],                                   ["wdl"];
+    XBase                   => "xBase",             Programming, ["clipper", "foxpro"],                ["prg", "prw"];
+}
+
+impl Language {
+    /** All known languages, excluding `Other`. */
+    pub fn all() -> impl Iterator<Item=Language> {
+        LANGUAGES.iter().map(|definition| definition.language)
+    }
+
+    pub fn definition(&self) -> Option<&'static LanguageDefinition> {
+        LANGUAGES.iter().find(|definition| definition.language == *self)
+    }
+
+    /** Parses a language name as used by GitHub (case-insensitive), or one of its aliases. */
+    pub fn from_str(string: &str) -> Option<Self> {
+        let string = string.to_lowercase();
+        LANGUAGES.iter()
+            .find(|definition| {
+                definition.name.to_lowercase() == string || definition.aliases.contains(&string.as_str())
+            })
+            .map(|definition| definition.language)
+    }
+
+    /** Returns the language of a path from its file name or, failing that, its extension. */
+    pub(crate) fn from_path(path: &str) -> Option<Self> {
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        if let Some(definition) = LANGUAGES.iter().find(|definition| definition.filenames.contains(&file_name)) {
+            return Some(definition.language);
+        }
+        std::path::Path::new(path).extension().map(|extension| {
+            extension.to_str().map(|extension| Language::from_extension(extension))
+        }).flatten().flatten()
+    }
+
+    /** Returns the language associated with given extension.
+
+        If the extension is *not* recognized, returns Language::Other. If the extension is recognized as not belonging to a source code file (say images, etc.) should return None.
+
+        TODO implement the above?
+     */
+    fn from_extension(extension: &str) -> Option<Self> {
+        LANGUAGES.iter()
+            .find(|definition| definition.extensions.contains(&extension))
+            .map(|definition| definition.language)
+            .or(Some(Language::Other))
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.definition().map_or("Other", |definition| definition.name)
+    }
+
+    /** The kind of the language, or None for `Other`. */
+    pub fn kind(&self) -> Option<LanguageKind> {
+        self.definition().map(|definition| definition.kind)
+    }
+
+    pub fn extensions(&self) -> &'static [&'static str] {
+        self.definition().map_or(&[], |definition| definition.extensions)
+    }
+
+    pub fn aliases(&self) -> &'static [&'static str] {
+        self.definition().map_or(&[], |definition| definition.aliases)
+    }
+
+    pub fn is_programming(&self) -> bool {
+        self.kind() == Some(LanguageKind::Programming)
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -5,7 +5,20 @@
              pub mod ordf64;
              pub mod weights_and_measures;
 #[macro_use] pub mod log;
-             pub mod csv;
+#[macro_use] pub mod csv;
+             pub mod graph;
+             pub mod manifests;
+             pub mod licenses;
+             pub mod languages;
+             pub mod sloc;
+             pub mod testcode;
+             pub mod tooling;
+             pub mod classification;
+             pub mod clones;
+             pub mod diff;
+             pub mod blame;
+             pub mod attributes;
+             pub mod query;
 #[macro_use] pub mod attrib;
              pub mod iterators;
              pub mod tuples;
@@ -24,6 +37,8 @@
 
 // Re-export
 pub use commandline::*;
+pub use attrib::sort::Direction::{Ascending as Asc, Descending as Desc};
+pub use attrib::sort::Nulls;
 use rand::prelude::SliceRandom;
 
 // TODO features
@@ -43,7 +58,7 @@ use rand::prelude::SliceRandom;
 // TODO rename Users to Contributors
 
 use std::fs::{remove_file, remove_dir_all, read_dir, metadata};
-use std::iter::{Sum, FromIterator};
+use std::iter::FromIterator;
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::collections::*;
@@ -55,7 +70,7 @@ use itertools::Itertools;
 use rand_pcg::Pcg64Mcg;
 use rand::SeedableRng;
 use rand::seq::IteratorRandom;
-use chrono::{NaiveDateTime, DateTime, Utc};
+use chrono::{NaiveDateTime, DateTime, Utc, Datelike};
 use anyhow::{Result,Context};
 
 use parasite;
@@ -63,8 +78,11 @@ use parasite::{StoreKind};
 
 use crate::attrib::*;
 use crate::fraction::*;
+use crate::ordf64::OrdF64;
 use crate::database::Database;
-use crate::log::{Log, Verbosity};
+use crate::database::persistent::Persistent;
+use crate::objects::Identifiable;
+use crate::log::Verbosity;
 use crate::database::source::{Source, MERGED_SUBSTORE_DIR_NAME};
 
 pub type Timestamp = i64; // Epoch
@@ -89,56 +107,8 @@ impl AsTimestamp for Timestamp {
 }
 
 pub mod store {
-    use std::fmt::Display;
-
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Ord, PartialOrd, serde::Serialize, serde::Deserialize)]
-    pub enum Language {
-        C,
-        Cpp,
-        CSharp,
-        Clojure,
-        CoffeeScript,
-        Erlang,
-        Go,
-        Haskell,
-        HTML,
-        Java,
-        JavaScript,
-        ObjectiveC,
-        Perl,
-        PHP,
-        Python,
-        Ruby,
-        Scala,
-        Shell,
-        TypeScript,
-    }
-
-    impl Display for Language {
-        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-            match self {
-                Language::C => write!(f, "C"),
-                Language::Cpp => write!(f, "Cpp"),
-                Language::CSharp => write!(f, "CSharp"),
-                Language::Clojure => write!(f, "Clojure"),
-                Language::CoffeeScript => write!(f, "CoffeeScript"),
-                Language::Erlang => write!(f, "Erlang"),
-                Language::Go => write!(f, "Go"),
-                Language::Haskell => write!(f, "Haskell"),
-                Language::HTML => write!(f, "HTML"),
-                Language::Java => write!(f, "Java"),
-                Language::JavaScript => write!(f, "JavaScript"),
-                Language::ObjectiveC => write!(f, "ObjectiveC"),
-                Language::Perl => write!(f, "Perl"),
-                Language::PHP => write!(f, "PHP"),
-                Language::Python => write!(f, "Python"),
-                Language::Ruby => write!(f, "Ruby"),
-                Language::Scala => write!(f, "Scala"),
-                Language::Shell => write!(f, "Shell"),
-                Language::TypeScript => write!(f, "TypeScript"),
-            }
-        }
-    }
+    /* Stores are split by the same languages that are used everywhere else. */
+    pub use crate::objects::Language;
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Ord, PartialOrd, serde::Serialize, serde::Deserialize)]
@@ -152,7 +122,7 @@ impl Display for Store {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Store::Small => write!(f, "SmallProjects"),
-            Store::Large(language) => write!(f, "{}", language),
+            Store::Large(language) => write!(f, "{:?}", language),
             Store::Generic => write!(f, "Generic"),
         }
     }
@@ -191,6 +161,8 @@ impl Store {
             Store::Large(store::Language::Scala) => StoreKind::Scala,
             Store::Large(store::Language::Shell) => StoreKind::Shell,
             Store::Large(store::Language::TypeScript) => StoreKind::TypeScript,
+            // only the languages above have stores of their own
+            Store::Large(_) => StoreKind::Unspecified,
         }
     }
 }
@@ -300,11 +272,11 @@ impl CacheDir {
 
 pub struct Djanco;
 impl Djanco {
-    pub fn from_spec<Sd, Sc>(dataset_path: Sd, cache_path: Sc, savepoint: Timestamp, substores: Vec<Store>, log: Log) -> anyhow::Result<Database> where Sd: Into<String>, Sc: Into<String> {
+    pub fn from_spec<Sd, Sc>(dataset_path: Sd, cache_path: Sc, savepoint: Timestamp, substores: Vec<Store>, log: log::Log) -> anyhow::Result<Database> where Sd: Into<String>, Sc: Into<String> {
         Self::from_full_spec(dataset_path, cache_path, savepoint, substores, log, false, false)
     }
     // FIXME this still sucks
-    pub fn from_full_spec<Sd, Sc>(dataset_path: Sd, cache_path: Sc, savepoint: Timestamp, substores: Vec<Store>, log: Log, preclean: bool, preclean_merged_substores: bool) -> anyhow::Result<Database> where Sd: Into<String>, Sc: Into<String> {
+    pub fn from_full_spec<Sd, Sc>(dataset_path: Sd, cache_path: Sc, savepoint: Timestamp, substores: Vec<Store>, log: log::Log, preclean: bool, preclean_merged_substores: bool) -> anyhow::Result<Database> where Sd: Into<String>, Sc: Into<String> {
         //DatastoreView::new(&dataset_path.into(), savepoint).with_cache(cache_path)
         let cache_path = cache_path.into();
         let substores = Store::discretize_selection(substores);
@@ -348,7 +320,7 @@ impl Djanco {
             path.push(top);
             path.into_os_string().to_str().unwrap().to_owned()
         });
-        let log = Log::new(Verbosity::Log);
+        let log = log::Log::new(Verbosity::Log);
         Djanco::from_full_spec(dataset_path, cache_path, savepoint, substores, log, preclean, preclean_merged_substores)
     }
     pub fn from<Sd>(dataset_path: Sd, preclean: bool, preclean_merged_substores: bool) -> Result<Database>  where Sd: Into<String> {
@@ -374,7 +346,7 @@ impl Djanco {
     //         config.preclean_merged_substores
     //     )
     // }
-    pub fn from_config(options: &Configuration, savepoint: Timestamp, substores: Vec<Store>, log: Log) -> Result<Database> {
+    pub fn from_config(options: &Configuration, savepoint: Timestamp, substores: Vec<Store>, log: log::Log) -> Result<Database> {
         Djanco::from_full_spec(
             options.dataset_path_as_str(), 
             options.cache_path_as_str(), 
@@ -389,7 +361,7 @@ impl Djanco {
 
 macro_rules! impl_attribute_definition {
     [$object:ty, $attribute:ident ()] => {
-        #[derive(Eq, PartialEq, Copy, Clone, Hash, Debug)] pub struct $attribute;
+        #[derive(Eq, PartialEq, Copy, Clone, Hash, Debug, Default)] pub struct $attribute;
         impl Attribute for $attribute { type Object = $object; }
     };
     [$object:ty, $attribute:ident ( $($arg_type:ty),+ ) ] => {
@@ -1080,6 +1052,13 @@ pub mod project {
     */
     impl_attribute![?    objects::Project, DuplicatedCode, f64, duplicated_code];
 
+    /* Fraction of the files in the head tree of the default branch that are identical to or near
+       duplicates of a file in the head tree of another project's default branch (see
+       `snapshot::CloneClass`). Unlike `DuplicatedCode`, this also catches copies that were
+       renamed, reformatted, or lightly edited.
+     */
+    impl_attribute![?    objects::Project, NearDuplicationRatio, f64, near_duplication_ratio];
+
     /*
      * Calculates the number of changes each author added to the project. 
      * Returns a list of authors with the number of changes they did. 
@@ -1214,6 +1193,119 @@ pub mod project {
      */
     impl_attribute![?..   objects::Project, AllForks, objects::ProjectId, all_forks, all_forks_count];
 
+    /* Dependencies declared in package manifests in the head tree of the default branch.
+
+       Recognized manifests: package.json, Cargo.toml, pom.xml, build.gradle, requirements.txt, 
+       setup.py, go.mod, Gemfile, and composer.json. Each dependency consists of a package name, the 
+       version specification as written in the manifest, and the ecosystem.
+     */
+    impl_attribute![?..   objects::Project, Dependencies, objects::Dependency, dependencies, dependency_count];
+    impl_attribute![?     objects::Project, DependencyCount, usize, dependency_count];
+
+    /* License detected from the contents of LICENSE, COPYING, or README files in the head tree of
+       the default branch, as opposed to `License`, which comes from GitHub metadata.
+
+       The license is identified by an SPDX identifier and comes with a confidence score between 0
+       and 1. License files take precedence over READMEs.
+     */
+    impl_attribute![?     objects::Project, DetectedLicense, objects::DetectedLicense, detected_license];
+
+    /* Licenses declared in the headers of the files in the head tree of the default branch, each 
+       with the number of files that declare it. The count is the number of distinct licenses, so
+       a count above one indicates license inconsistency within the project.
+     */
+    impl_attribute![?..   objects::Project, HeaderLicenses, (String, usize), header_licenses, header_license_count];
+
+    /* Fraction of the project's commits (not counting merges) that fix bugs, according to
+       `commit::Category`.
+     */
+    impl_attribute![?     objects::Project, BugFixRatio, f64, bug_fix_ratio];
+
+    /* CI services, build systems, linters, and container tools configured in the head tree of the
+       default branch, recognized by their configuration files (eg. `.github/workflows/*.yml`,
+       `pom.xml`, `.eslintrc`, `Dockerfile`).
+     */
+    impl_attribute![?..   objects::Project, Tooling, objects::Tool, tooling, tooling_count];
+
+    /* Every tool the project has ever used, with the time of the earliest commit that added or
+       modified its configuration, ordered by that time. Unlike `Tooling`, this includes tools that
+       the project has since abandoned.
+     */
+    impl_attribute![?..   objects::Project, ToolAdoption, (objects::Tool, Timestamp), tool_adoption, tool_adoption_count];
+
+    /* Time at which the project first configured any CI service.
+     */
+    impl_attribute![?     objects::Project, CiAdoptedAt, Timestamp, ci_adopted_at];
+
+    /* Authors of the lines in the head tree of the default branch, each with the share of the
+       non-blank lines they wrote, largest first. A line counts for the author of the commit that last
+       changed it (see `Database::blame`). The count is the number of such authors.
+     */
+    impl_attribute![?..   objects::Project, AuthorshipShare, (objects::User, f64), authorship_shares, line_author_count];
+
+    /* Source lines of code: the number of code lines, excluding comments and blank lines, in the
+       files in the head tree of the default branch that are written in programming languages.
+     */
+    impl_attribute![?     objects::Project, Sloc, usize, sloc];
+
+    /* Fraction of non-blank lines that are comments, over the same files as `Sloc`.
+     */
+    impl_attribute![?     objects::Project, CommentDensity, f64, comment_density];
+
+    /* Number of test files in the head tree of the default branch (see `path::IsTest`).
+     */
+    impl_attribute![?     objects::Project, TestFileCount, usize, test_file_count];
+
+    /* Fraction of the lines in the head tree of the default branch that are in test files.
+     */
+    impl_attribute![?     objects::Project, TestLocRatio, f64, test_loc_ratio];
+
+    /* Density of the project's team: the fraction of pairs of authors who modified at least one 
+       common path. 
+     */
+    impl_attribute![?     objects::Project, TeamDensity, f64, team_density];
+
+    /* Average local clustering coefficient of the project's team, where two authors are connected
+       if they modified at least one common path.
+     */
+    impl_attribute![?     objects::Project, TeamClustering, f64, team_clustering];
+
+    /* Returns the fork network the project belongs to.
+
+       Projects that share at least one commit belong to the same network. The oldest project in the 
+       network is its upstream, all the others are its forks. Projects that do not share commits with 
+       any other project do not belong to any network.
+     */
+    impl_attribute![?+    objects::Project, ForkNetwork, objects::ForkNetwork, fork_network_with_data];
+
+    /* Returns the number of projects in the project's fork network, including the upstream. */
+    impl_attribute![?     objects::Project, ForkNetworkSize, usize, fork_network_size];
+
+    /* Returns the ID of the upstream project of the project's fork network. */
+    impl_attribute![?     objects::Project, UpstreamId, objects::ProjectId, upstream_id];
+
+    /* Returns the upstream project of the project's fork network. */
+    impl_attribute![?+    objects::Project, Upstream, objects::Project, upstream_with_data];
+
+    /* True if the project is the upstream of its fork network. */
+    impl_attribute![?     objects::Project, IsUpstream, bool, is_upstream];
+
+    /* For forks, returns the ID of the last commit shared with the upstream before the fork diverged. */
+    impl_attribute![?     objects::Project, DivergenceCommitId, objects::CommitId, divergence_commit_id];
+
+    /* For forks, returns the last commit shared with the upstream before the fork diverged. */
+    impl_attribute![?+    objects::Project, DivergenceCommit, objects::Commit, divergence_commit_with_data];
+
+    /* For forks, returns the number of commits that are not found in the upstream. */
+    impl_attribute![?     objects::Project, UniqueCommits, usize, unique_commit_count];
+
+    /* For forks, guesses whether the fork contributed any commits back to the upstream.
+
+       This is a heuristic: a fork contributed back if the upstream contains a commit made after the 
+       fork diverged by someone who also authored commits that are unique to the fork.
+     */
+    impl_attribute![?     objects::Project, ContributedBack, bool, contributed_back];
+
     /* For each branch returns the head tree.
      
        The tree is a mapping from path ids to snapshot ids. All paths are considered. Deleted files are not displayed.
@@ -1246,6 +1338,52 @@ pub mod commit {
     impl_attribute![?    objects::Commit, Hash, String, hash];
     impl_attribute![?    objects::Commit, Message, String, message];
     impl_attribute![?    objects::Commit, MessageLength, usize, message_length];
+
+    /* Category of the commit (bug fix, feature, refactoring, revert, merge...) as determined from
+       its message by the default classification rules (see `classification::CommitRules`). Use
+       `CategoryWith` to classify commits with a custom rule set.
+     */
+    impl_attribute![?    objects::Commit, Category, objects::CommitCategory, category];
+
+    /* References to issues and pull requests in the commit message: `#123`, `owner/repo#123`,
+       or `GH-123`.
+     */
+    impl_attribute![?..  objects::Commit, IssueRefs, String, issue_refs, issue_ref_count];
+
+    /* Whether the commit reverts another commit.
+     */
+    impl_attribute![?    objects::Commit, IsRevert, bool, is_revert];
+
+    /* Bugs introduced by the commit, as identified by the SZZ algorithm: the bug-fixing commits
+       that deleted or changed lines that were last touched by this commit. Bug fixes are 
+       identified by their messages (see `Category` and `IssueRefs`).
+     */
+    impl_attribute![!+.. objects::Commit, IntroducedBugs, objects::Commit, introduced_bug_fixes_with_data, introduced_bug_count];
+
+    /* Whether the SZZ algorithm identified the commit as having introduced at least one bug.
+     */
+    impl_attribute![!    objects::Commit, IsBugIntroducing, bool, is_bug_introducing];
+
+    /* Category of the commit as determined from its message by a custom set of rules, eg.:
+
+       `commit::CategoryWith(CommitRules::new().with_rule(CommitCategory::BugFix, r"(?i)\bhotfix\b")?)`
+
+       Unlike `Category`, the results are not cached.
+     */
+    pub struct CategoryWith(pub crate::classification::CommitRules);
+    impl Attribute for CategoryWith { type Object = objects::Commit; }
+    impl<'a> Getter<'a> for CategoryWith {
+        type IntoItem = Option<objects::CommitCategory>;
+        fn get(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Self::IntoItem {
+            object.category_with(&self.0)
+        }
+    }
+    impl<'a> OptionGetter<'a> for CategoryWith {
+        type IntoItem = objects::CommitCategory;
+        fn get_opt(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem> {
+            object.category_with(&self.0)
+        }
+    }
     impl_attribute![?    objects::Commit, AuthoredTimestamp, Timestamp, author_timestamp];
     impl_attribute![?    objects::Commit, CommittedTimestamp, Timestamp, committer_timestamp];
     /* Returns all changes made by the commit. 
@@ -1277,6 +1415,11 @@ pub mod commit {
      */
     impl_attribute![?..  objects::Commit, Languages, objects::Language, languages, languages_count];
 
+    /*
+     * Whether the commit changes any test files (see `path::IsTest`).
+     */
+    impl_attribute![?    objects::Commit, TouchesTests, bool, touches_tests];
+
     /*
      * All commit ids that are the ancestors of this commit in the commit graph. I.e. all the commits in the branch if this commit is the head of that branch.
      */ 
@@ -1301,6 +1444,35 @@ pub mod head {
     impl_attribute![!+.. objects::Head, Commits, objects::Commit, commits_with_data, commit_count];
 }
 
+pub mod fork_network {
+    use crate::objects;
+    use crate::attrib::*;
+
+    impl_attribute![!+   objects::ForkNetwork, Itself];
+    impl_attribute![!    objects::ForkNetwork, Raw];
+    impl_attribute![!    objects::ForkNetwork, UpstreamId, objects::ProjectId, upstream_id];
+    impl_attribute![?+   objects::ForkNetwork, Upstream, objects::Project, upstream_with_data];
+    impl_attribute![!..  objects::ForkNetwork, ProjectIds, objects::ProjectId, project_ids, size];
+    impl_attribute![!..  objects::ForkNetwork, ForkIds, objects::ProjectId, fork_ids, fork_count];
+    impl_attribute![!+.. objects::ForkNetwork, ForkProjects, objects::Project, fork_projects_with_data, fork_count];
+    impl_attribute![!+.. objects::ForkNetwork, Forks, objects::Fork, forks_with_data, fork_count];
+    impl_attribute![!    objects::ForkNetwork, Size, usize, size];
+}
+
+pub mod fork {
+    use crate::objects;
+    use crate::attrib::*;
+
+    impl_attribute![!+   objects::Fork, Itself];
+    impl_attribute![!    objects::Fork, Raw];
+    impl_attribute![!    objects::Fork, ProjectId, objects::ProjectId, project_id];
+    impl_attribute![?+   objects::Fork, Project, objects::Project, project_with_data];
+    impl_attribute![?    objects::Fork, DivergenceCommitId, objects::CommitId, divergence_commit_id];
+    impl_attribute![?+   objects::Fork, DivergenceCommit, objects::Commit, divergence_commit_with_data];
+    impl_attribute![!    objects::Fork, UniqueCommits, usize, unique_commit_count];
+    impl_attribute![!    objects::Fork, ContributedBack, bool, contributed_back];
+}
+
 pub mod change {
     use crate::objects;
     use crate::attrib::*;
@@ -1311,6 +1483,12 @@ pub mod change {
     impl_attribute![?   objects::Change, SnapshotId, objects::SnapshotId, snapshot_id];
     impl_attribute![?+  objects::Change, Path, objects::Path, path_with_data];
     impl_attribute![?+  objects::Change, Snapshot, objects::Snapshot, snapshot_with_data];
+
+    /* Line-based diff of the change against the version of the path in the first parent of the
+       commit that made it. Only available for changes obtained from commits, whose snapshots (and
+       previous snapshots) have contents.
+     */
+    impl_attribute![?   objects::Change, Diff, objects::Diff, diff];
 }
 
 pub mod tree {
@@ -1330,6 +1508,7 @@ pub mod user {
     use crate::objects;
     use crate::time;
     use crate::attrib::*;
+    use crate::Timestamp;
 
     impl_attribute![!+   objects::User, Itself];
     impl_attribute![!    objects::User, Raw];
@@ -1361,6 +1540,29 @@ pub mod user {
     /* Ids of all projects the user contributes to (at least one authored commit)
      */
     impl_attribute![?..  objects::User, ProjectIds, objects::ProjectId, project_ids, project_ids_count];
+
+    /* Users who authored at least one commit in a project the user also authored commits in.
+     */
+    impl_attribute![?..  objects::User, CollaboratorIds, objects::UserId, collaborator_ids, collaborator_count];
+    impl_attribute![?+.. objects::User, Collaborators, objects::User, collaborators_with_data, collaborator_count];
+    impl_attribute![?    objects::User, CollaboratorCount, usize, collaborator_count];
+
+    /* Collaborators within a time window: users who authored a commit in a project between `since`
+       (inclusive) and `until` (exclusive), where the user also authored a commit within the same window.
+     */
+    impl_attribute![?..  objects::User, CollaboratorIdsBetween(Timestamp, Timestamp), objects::UserId, collaborator_ids_between, collaborator_count_between];
+    impl_attribute![?+.. objects::User, CollaboratorsBetween(Timestamp, Timestamp), objects::User, collaborators_between_with_data, collaborator_count_between];
+    impl_attribute![?    objects::User, CollaboratorCountBetween(Timestamp, Timestamp), usize, collaborator_count_between];
+
+    /* Centrality of the user in the collaboration network, either degree centrality (the fraction 
+       of all other users the user collaborated with) or PageRank.
+     */
+    impl_attribute![?    objects::User, Centrality(objects::CentralityMeasure), f64, centrality];
+
+    /* Number of non-blank lines the user wrote that are still present in the head trees of the
+       default branches of all projects, ie. the lines blamed on commits the user authored.
+     */
+    impl_attribute![?    objects::User, SurvivingLines, usize, surviving_lines];
 }
 
 pub mod path {
@@ -1371,7 +1573,28 @@ pub mod path {
     impl_attribute![!   objects::Path, Raw];
     impl_attribute![!   objects::Path, Id, objects::PathId, id];
     impl_attribute![!   objects::Path, Location, String, location];
+
+    /* Language of the file. Files without an extension and files with extensions shared by 
+       several languages (`.h`, `.m`, `.pl`, ...) are classified by their contents: modelines, 
+       shebang lines, and keyword heuristics. Other files are classified by their extension.
+     */
     impl_attribute![?   objects::Path, Language, objects::Language, language];
+
+    /* Language of the file as determined from its extension alone. Faster than `Language`, since it
+       does not need to look at any contents.
+     */
+    impl_attribute![?   objects::Path, ExtensionLanguage, objects::Language, extension_language];
+
+    /* Kind of the file's language: programming, markup, data, or prose. Useful to exclude data and
+       markup files from size and LOC metrics.
+     */
+    impl_attribute![?   objects::Path, LanguageKind, objects::LanguageKind, language_kind];
+
+    /* Whether the file is test code: it is in a test directory (eg. `src/test/`, `__tests__`) or
+       its name follows the conventions of its language's test frameworks (eg. `*_test.go`,
+       `test_*.py`, `*.spec.ts`, `FooTest.java`).
+     */
+    impl_attribute![?   objects::Path, IsTest, bool, is_test];
 }
 
 pub mod snapshot {
@@ -1384,6 +1607,18 @@ pub mod snapshot {
     impl_attribute![!   objects::Snapshot, Bytes, Vec<u8>, raw_contents_owned];
     impl_attribute![!   objects::Snapshot, Contents, String, contents_owned];
     impl_attribute![?   objects::Snapshot, Loc, usize, snapshot_locs];
+
+    /* Numbers of code, comment, and blank lines in the snapshot. Comments are recognized using the
+       comment syntax of the language of the path at which the snapshot was first seen.
+     */
+    impl_attribute![?   objects::Snapshot, CodeLines, usize, code_lines];
+    impl_attribute![?   objects::Snapshot, CommentLines, usize, comment_lines];
+    impl_attribute![?   objects::Snapshot, BlankLines, usize, blank_lines];
+
+    /* License declared in the header of the file: an SPDX-License-Identifier tag or a license 
+       notice among the first lines of the file.
+     */
+    impl_attribute![?   objects::Snapshot, LicenseHeader, String, license_header];
     impl_attribute![!   objects::Snapshot, HasContents, bool, snapshot_has_contents];
 
     /* Number of projects in the database that contain the snapshot (or did in the past). 
@@ -1401,19 +1636,33 @@ pub mod snapshot {
        Contains indformation about *all* snapshots, not just those for which we have contents stored as well. 
      */
     impl_attribute![!   objects::Snapshot, OriginalProject, objects::ProjectId, original_project];
+
+    /* Other snapshots, anywhere in the dataset, that are near duplicates (type-2 clones) of this
+       one: their estimated token-level similarity is at least 80%, after comments and layout are
+       dropped and identifiers and literals are normalized. Only files in programming languages
+       with at least 50 tokens are compared. See `djanco::clones` for details.
+     */
+    impl_attribute![!..  objects::Snapshot, NearDuplicateIds, objects::SnapshotId, near_duplicate_ids, near_duplicate_count];
+    impl_attribute![!+.. objects::Snapshot, NearDuplicates, objects::Snapshot, near_duplicates_with_data, near_duplicate_count];
+
+    /* The clone class of the snapshot: snapshots connected by the near-duplicate relation form a
+       class, identified by its smallest snapshot id. Empty for snapshots without near duplicates.
+     */
+    impl_attribute![?   objects::Snapshot, CloneClass, objects::SnapshotId, clone_class];
 }
 
 pub trait AttributeIterator<'a, T>: Sized + Iterator<Item=objects::ItemWithData<'a, T>> {
     fn filter_by<A>(self, attribute: A)
-                    -> AttributeFilterIter<Self, A>
+                    -> AttributeFilterIter<PreparedIter<'a, Self, T>, A>
         where A: Filter<'a, Item=T> {
-        AttributeFilterIter { iterator: self, attribute }
+        let iterator = prepared(self, attribute.needs_preparation(), |objects| attribute.prepare(objects));
+        AttributeFilterIter { iterator, attribute }
     }
 
     fn map_into<A, Ta, Tb>(self, attribute: A)
                            -> AttributeMapIter<Self, A, Ta, Tb>
         where A: Select<'a, Ta, Tb> {
-        AttributeMapIter { iterator: self, attribute, function: PhantomData }
+        AttributeMapIter { iterator: self, attribute, prepared: None, function: PhantomData }
     }
 
     fn sort_by<A: 'a, I>(self, attribute: A)
@@ -1430,6 +1679,23 @@ pub trait AttributeIterator<'a, T>: Sized + Iterator<Item=objects::ItemWithData<
         vector.into_iter()
     }
 
+    /* Sort by several keys, eg. `sort_by_keys(Select!(project::Stars, project::Created), [Desc, Asc])`,
+       with one direction per key. Objects missing a key go last; equal objects keep their order.
+     */
+    fn sort_by_keys<S>(self, keys: S, directions: S::Directions)
+                       -> std::vec::IntoIter<objects::ItemWithData<'a, T>>
+        where S: SortKeys<'a, T> {
+        self.sort_by_keys_with_nulls(keys, directions, Nulls::Last)
+    }
+
+    fn sort_by_keys_with_nulls<S>(self, keys: S, directions: S::Directions, nulls: Nulls)
+                                  -> std::vec::IntoIter<objects::ItemWithData<'a, T>>
+        where S: SortKeys<'a, T> {
+        let mut vector = Vec::from_iter(self);
+        keys.sort_by_keys(directions, nulls, &mut vector);
+        vector.into_iter()
+    }
+
     fn sample<S>(self, sampler: S)
                  -> std::vec::IntoIter<objects::ItemWithData<'a, T>>
         where S: Sampler<'a, T> {
@@ -1439,12 +1705,48 @@ pub trait AttributeIterator<'a, T>: Sized + Iterator<Item=objects::ItemWithData<
     fn group_by<A, K>(self, attribute: A)
                       -> std::collections::hash_map::IntoIter<K, Vec<objects::ItemWithData<'a, T>>>
         where A: Group<'a, T, K>, K: Hash + Eq {
-        self.map(|item_with_data| {
+        let vector = Vec::from_iter(self);
+        attribute.prepare(&vector);
+        vector.into_iter().map(|item_with_data| {
             let key = attribute.select_key(&item_with_data);
             (key, item_with_data)
         }).into_group_map().into_iter()
     }
 
+    /* Replace each object by the object or objects an attribute leads to, eg.
+       `projects().expand(project::Commits)` or `commits().expand(commit::Author)`. The same
+       object can appear more than once, chain with `distinct` to drop repeats.
+     */
+    fn expand<A, U>(self, attribute: A)
+                    -> AttributeExpandIter<'a, Self, A, U>
+        where A: Attribute<Object=T> + OptionGetter<'a>, A::IntoItem: Expansion<'a, U> {
+        AttributeExpandIter { iterator: self, attribute, expanded: Vec::new().into_iter() }
+    }
+
+    /// Drop objects whose id was already seen, keeping the first occurrence.
+    fn distinct(self) -> AttributeDistinctIter<Self, ById, T::Identity>
+        where T: Identifiable {
+        AttributeDistinctIter { iterator: self, key: ById, seen: HashSet::new() }
+    }
+
+    /// Drop objects whose attribute value was already seen, keeping the first occurrence.
+    fn distinct_by<A, K>(self, attribute: A)
+                         -> AttributeDistinctIter<PreparedIter<'a, Self, T>, A, K>
+        where A: Group<'a, T, K>, K: Hash + Eq {
+        let iterator = prepared(self, attribute.needs_preparation(), |objects| attribute.prepare(objects));
+        AttributeDistinctIter { iterator, key: attribute, seen: HashSet::new() }
+    }
+
+    /* Pair objects from this iterator with objects from another one that share a key (see
+       `Each`). Pairs come out in the order of this iterator, and then of the other one. The other
+       iterator is collected into an index up front, this one is paired lazily.
+     */
+    fn join<I, U, J>(self, other: I, key: J) -> AttributeJoinIter<'a, Self, J, T, U>
+        where I: Iterator<Item=objects::ItemWithData<'a, U>>, J: JoinKey<'a, T, U>, T: Clone, U: Clone {
+        let index = JoinIndex::new(other, |item_with_data| key.right_keys(item_with_data));
+        AttributeJoinIter { iterator: self, key, index, current: None }
+    }
+
     // TODO drop options
 }
 
@@ -1457,7 +1759,10 @@ pub trait AttributeGroupIterator<'a, K, T>: Sized + Iterator<Item=(K, Vec<object
         where A: Filter<'a, Item=T> {
         AttributeGroupFilterIter { iterator: self, attribute }
     }
-    // TODO filter_key
+
+    fn filter_key<F>(self, predicate: F) -> AttributeGroupKeyFilterIter<Self, F> where F: Fn(&K) -> bool {
+        AttributeGroupKeyFilterIter { iterator: self, predicate }
+    }
 
     fn map_into<A, Ta, Tb>(self, attribute: A)
                            -> AttributeGroupMapIter<Self, A, Ta, Tb>
@@ -1481,7 +1786,38 @@ pub trait AttributeGroupIterator<'a, K, T>: Sized + Iterator<Item=(K, Vec<object
             }).collect();
         vector.into_iter()
     }
-    // TODO sort_key, sort_key_by, sort_key_with, sort_values, sort_values_by, sort_values_with
+
+    fn sort_by_keys<S>(self, keys: S, directions: S::Directions)
+                       -> std::vec::IntoIter<(K, Vec<objects::ItemWithData<'a, T>>)>
+        where S: SortKeys<'a, T> {
+        self.sort_by_keys_with_nulls(keys, directions, Nulls::Last)
+    }
+
+    fn sort_by_keys_with_nulls<S>(self, keys: S, directions: S::Directions, nulls: Nulls)
+                                  -> std::vec::IntoIter<(K, Vec<objects::ItemWithData<'a, T>>)>
+        where S: SortKeys<'a, T> {
+        let vector: Vec<(K, Vec<objects::ItemWithData<'a, T>>)> =
+            self.map(|(key, mut vector)| {
+                keys.sort_by_keys(directions, nulls, &mut vector);
+                (key, vector)
+            }).collect();
+        vector.into_iter()
+    }
+
+    fn sort_key(self) -> std::vec::IntoIter<(K, Vec<objects::ItemWithData<'a, T>>)> where K: Ord {
+        self.sort_key_with_direction(sort::Direction::Descending)
+    }
+
+    fn sort_key_with_direction(self, direction: sort::Direction)
+                               -> std::vec::IntoIter<(K, Vec<objects::ItemWithData<'a, T>>)> where K: Ord {
+        let mut vector: Vec<(K, Vec<objects::ItemWithData<'a, T>>)> = self.collect();
+        vector.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if direction == sort::Direction::Descending {
+            vector.reverse()
+        }
+        vector.into_iter()
+    }
+    // TODO sort_values, sort_values_by, sort_values_with
 
     fn sample<S>(self, sampler: S)
                  -> std::vec::IntoIter<(K, Vec<objects::ItemWithData<'a, T>>)>
@@ -1492,7 +1828,16 @@ pub trait AttributeGroupIterator<'a, K, T>: Sized + Iterator<Item=(K, Vec<object
             }).collect();
         vector.into_iter()
     }
-    // TODO sample_key
+
+    fn sample_key<S>(self, sampler: S)
+                     -> std::vec::IntoIter<(K, Vec<objects::ItemWithData<'a, T>>)>
+        where S: KeySampler, K: Ord {
+        sampler.sample_keys(self.collect()).into_iter()
+    }
+
+    fn aggregate<A>(self, aggregator: A) -> AttributeGroupAggregateIter<Self, A> where A: Aggregator<'a, T> {
+        AttributeGroupAggregateIter { iterator: self, aggregator }
+    }
 
     fn ungroup(self) -> std::vec::IntoIter<objects::ItemWithData<'a, T>> {
         let vector: Vec<objects::ItemWithData<'a, T>> =
@@ -1513,6 +1858,8 @@ macro_rules! impl_comparison {
                 fn accept(&self, item_with_data: &objects::ItemWithData<'a, Self::Item>) -> bool {
                     self.0.get_opt(item_with_data).map_or($default, |n| n.$comparator(&self.1))
                 }
+                fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Item>]) { self.0.prepare(objects) }
+                fn needs_preparation(&self) -> bool { self.0.needs_preparation() }
             }
         }
     }
@@ -1532,6 +1879,11 @@ macro_rules! impl_binary {
                     $comparator(self.0.accept(item_with_data),
                                 self.1.accept(item_with_data))
                 }
+                fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Item>]) {
+                    self.0.prepare(objects);
+                    self.1.prepare(objects);
+                }
+                fn needs_preparation(&self) -> bool { self.0.needs_preparation() || self.1.needs_preparation() }
             }
         }
     }
@@ -1547,6 +1899,8 @@ macro_rules! impl_unary {
                 fn accept(&self, item_with_data: &objects::ItemWithData<'a, Self::Item>) -> bool {
                     $comparator(self.0.accept(item_with_data))
                 }
+                fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Item>]) { self.0.prepare(objects) }
+                fn needs_preparation(&self) -> bool { self.0.needs_preparation() }
             }
         }
     }
@@ -1561,6 +1915,8 @@ macro_rules! impl_existential {
                 fn accept(&self, item_with_data: &objects::ItemWithData<'a, Self::Item>) -> bool {
                     self.0.get_opt(item_with_data).$method()
                 }
+                fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Item>]) { self.0.prepare(objects) }
+                fn needs_preparation(&self) -> bool { self.0.needs_preparation() }
             }
         }
     }
@@ -1603,6 +1959,8 @@ macro_rules! impl_collection_membership {
                 fn accept(&self, item_with_data: &objects::ItemWithData<'a, Self::Item>) -> bool {
                     self.0.get_opt(item_with_data).map_or(false, |e| self.1.contains(&e))
                 }
+                fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Item>]) { self.0.prepare(objects) }
+                fn needs_preparation(&self) -> bool { self.0.needs_preparation() }
             }
             impl<'a, A, T, I> Filter<'a> for AnyIn<A, $collection_type<I>>
                 where A: OptionGetter<'a, IntoItem=Vec<I>>,
@@ -1805,6 +2163,11 @@ impl<'a, T> Sampler<'a, T> for Top {
         iter.take(self.0).collect()
     }
 }
+impl KeySampler for Top {
+    fn sample_keys<K, V>(&self, groups: Vec<(K, V)>) -> Vec<(K, V)> where K: Ord {
+        groups.into_iter().take(self.0).collect()
+    }
+}
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)] pub struct Seed(pub u128);
 impl Seed {
@@ -1821,6 +2184,14 @@ impl<'a, T> Sampler<'a, T> for Random {
         iter.choose_multiple(&mut rng, self.0)
     }
 }
+impl KeySampler for Random {
+    fn sample_keys<K, V>(&self, mut groups: Vec<(K, V)>) -> Vec<(K, V)> where K: Ord {
+        // Groups usually come in no particular order, so they are sorted for the sample to be repeatable.
+        groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut rng = Pcg64Mcg::from_seed(self.1.to_be_bytes());
+        groups.into_iter().choose_multiple(&mut rng, self.0)
+    }
+}
 
 // This combines Distinct and Random into one operation. Here Distinct's
 // criteria will be calculated only for the selected projects and projects that
@@ -1977,15 +2348,34 @@ impl<'a, A, T> Getter<'a> for Count<A> where A: Attribute<Object=T> + OptionCoun
     fn get(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Self::IntoItem {
         self.0.count(object).unwrap_or(0)
     }
+    fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Object>]) { self.0.prepare(objects) }
+    fn needs_preparation(&self) -> bool { self.0.needs_preparation() }
 }
 impl<'a, A, T> OptionGetter<'a> for Count<A> where A: Attribute<Object=T> + OptionCountable<'a> {
     type IntoItem = usize;
     fn get_opt(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem> {
         self.0.count(object)
     }
+    fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Object>]) { self.0.prepare(objects) }
+    fn needs_preparation(&self) -> bool { self.0.needs_preparation() }
+}
+
+/*
+ * `Count` on its own counts the objects in each group when aggregating, eg. `aggregate(Count)` or
+ * `aggregate(Select!(Count, Median(project::Stars)))`. This works for the constructor of `Count`
+ * because it is a function from `GroupMembers`, which only exists for this purpose.
+ */
+#[doc(hidden)] pub struct GroupMembers;
+impl Attribute for GroupMembers { type Object = (); }
+impl<'a, T, F> Aggregator<'a, T> for F where F: Fn(GroupMembers) -> Count<GroupMembers> {
+    type Output = usize;
+    fn aggregate(&self, objects: &Vec<objects::ItemWithData<'a, T>>) -> Self::Output {
+        objects.len()
+    }
 }
 
 trait CalculateStat<N, T>{ fn calculate(vector: Vec<N>) -> T; }
+trait CalculateStatWith<N, P, T>{ fn calculate(vector: Vec<N>, parameter: &P) -> T; }
 macro_rules! impl_calculator {
         ($name:ident -> $result:ty where N: $($requirements:path),+; $calculate:item) => {
             pub struct $name<A: Attribute>(pub A);
@@ -2009,6 +2399,43 @@ macro_rules! impl_calculator {
             impl<A, N, T> CalculateStat<N, Option<$result>> for $name<A> where A: Attribute<Object=T>, N: $($requirements +)+  {
                 $calculate
             }
+            impl<'a, A, N, T> Aggregator<'a, T> for $name<A>
+                where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=N>, N: $($requirements +)+ {
+                type Output = Option<$result>;
+                fn aggregate(&self, objects: &Vec<objects::ItemWithData<'a, T>>) -> Self::Output {
+                    Self::calculate(objects.iter().flat_map(|object| self.0.get_opt(object)).collect())
+                }
+            }
+        };
+        ($name:ident($parameter:ty) -> $result:ty where N: $($requirements:path),+; $calculate:item) => {
+            pub struct $name<A: Attribute>(pub A, pub $parameter);
+            impl<A, T> Attribute for $name<A> where A: Attribute<Object=T> {
+                type Object = T;
+            }
+            impl<'a, A, N, T> Getter<'a> for $name<A>
+                where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=Vec<N>>, N: $($requirements +)+ {
+                type IntoItem = Option<$result>;
+                fn get(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Self::IntoItem {
+                    self.0.get_opt(object).map(|object| Self::calculate(object, &self.1)).flatten()
+                }
+            }
+            impl<'a, A, N, T> OptionGetter<'a> for $name<A>
+                where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=Vec<N>>, N: $($requirements +)+ {
+                type IntoItem = $result;
+                fn get_opt(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem> {
+                    self.0.get_opt(object).map(|object| Self::calculate(object, &self.1)).flatten()
+                }
+            }
+            impl<A, N, T> CalculateStatWith<N, $parameter, Option<$result>> for $name<A> where A: Attribute<Object=T>, N: $($requirements +)+ {
+                $calculate
+            }
+            impl<'a, A, N, T> Aggregator<'a, T> for $name<A>
+                where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=N>, N: $($requirements +)+ {
+                type Output = Option<$result>;
+                fn aggregate(&self, objects: &Vec<objects::ItemWithData<'a, T>>) -> Self::Output {
+                    Self::calculate(objects.iter().flat_map(|object| self.0.get_opt(object)).collect(), &self.1)
+                }
+            }
         }
     }
 
@@ -2029,7 +2456,7 @@ impl_calculator!(Max -> N where N: Ord, Clone;
 impl_calculator!(MinMax -> (N, N) where N: Ord, Clone;
         fn calculate(vector: Vec<N>) -> Option<(N,N)> { vector.into_iter().minmax().into_option() }
     );
-impl_calculator!(Mean -> Fraction<N> where N: Sum;
+impl_calculator!(Mean -> Fraction<N> where N: std::iter::Sum;
         fn calculate(vector: Vec<N>) -> Option<Fraction<N>> {
             let length = vector.len();
             let sum = vector.into_iter().sum::<N>();
@@ -2040,7 +2467,7 @@ impl_calculator!(Mean -> Fraction<N> where N: Sum;
             }
         }
     );
-impl_calculator!(Median -> Fraction<N> where N: Ord, Clone, Sum;
+impl_calculator!(Median -> Fraction<N> where N: Ord, Clone, std::iter::Sum;
         fn calculate(mut items: Vec<N>) -> Option<Fraction<N>> {
             items.sort();
             let length = items.len();
@@ -2061,125 +2488,251 @@ impl_calculator!(Median -> Fraction<N> where N: Ord, Clone, Sum;
             }
         }
     );
-//
-// type BinKey = Option<i64>;
-// pub trait BinningFunction {
-//     type From;
-//     fn calculate_key(&self, value: Self::From) -> BinKey;
-//     fn calculate_limits(&self, key: BinKey) -> (Self::From, Self::From);
-//     fn bin(value: Self::From) -> Bin<Self> {
-//         Bin { value, binning_function: PhantomData }
-//     }
-// }
-//
-// pub struct Bin<F: BinningFunction> { value: F::From, binning_function: PhantomData<F> }
-// impl<F> Bin<F> where F: BinningFunction {
-//     // pub fn from(value: F::From) -> Self {
-//     //
-//     // }
-// }
-//
-// impl<F, T> std::convert::From<T> for Bin<F> where F: BinningFunction<From=T>, T: Div /*TODO*/ {
-//     fn from(value: T) -> Self {
-//         Bin { value, binning_function: PhantomData }
-//     }
-// }
-//
-// #[derive(Clone, Debug)]
-// pub struct Interval(pub usize);
-// impl BinningFunction for Interval {
-//     type From = f64;
-//     fn calculate_key(&self, value: Self::From) -> BinKey {
-//
-//         let bin = (value / self.0 as f64).to_i64();
-//
-//     }
-//     fn calculate_limits(&self, key: BinKey) -> (Self::From, Self::From) {
-//
-//     }
-// }
-
-// impl<T, F> Display for Bin<T, F> where F: BinningFunction<T> {
-//     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-//         write!(f, "{}..{}", self.binning_function.minimum(), self.binning_function.maximum())
-//     }
-// }
-
-// pub trait BinningFunction<T>: Clone {
-//     type Into;
-//     fn bin(&self, value: T) -> Bin<Self::Into, Self>;
-//
-//     fn minimum(&self, bin: Self::Into) -> T;
-//     fn maximum(&self, bin: Self::Into) -> T;
-//     // fn minimum(bin: Bin<Self::Into, Self>) -> T {
-//     //     bin.binning_function.minimum_value(bin.bin)
-//     // }
-//     // fn maximum(bin: Bin<Self::Into, Self>) -> T {
-//     //     bin.binning_function.minimum_value(bin.bin)
-//     // }
-// }
+impl_calculator!(Sum -> N where N: std::iter::Sum;
+        fn calculate(vector: Vec<N>) -> Option<N> { Some(vector.into_iter().sum()) }
+    );
+// Population variance and standard deviation.
+impl_calculator!(Variance -> OrdF64 where N: Number;
+        fn calculate(vector: Vec<N>) -> Option<OrdF64> { variance(&vector).map(OrdF64::from) }
+    );
+impl_calculator!(StdDev -> OrdF64 where N: Number;
+        fn calculate(vector: Vec<N>) -> Option<OrdF64> { variance(&vector).map(|variance| OrdF64::from(variance.sqrt())) }
+    );
+// The most common value, or the smallest of the most common values if there are several.
+impl_calculator!(Mode -> N where N: Ord, Clone;
+        fn calculate(vector: Vec<N>) -> Option<N> {
+            let mut counts: BTreeMap<N, usize> = BTreeMap::new();
+            vector.into_iter().for_each(|n| *counts.entry(n).or_insert(0) += 1);
+            counts.into_iter()
+                .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+                .map(|(n, _)| n)
+        }
+    );
+// Shannon entropy (in bits) of the shares that the values make up of their total, eg. of the
+// distribution of commits among the contributors of a project.
+impl_calculator!(Entropy -> OrdF64 where N: Number;
+        fn calculate(vector: Vec<N>) -> Option<OrdF64> {
+            let total = vector.iter().map(|n| n.as_f64()).sum::<f64>();
+            if total <= 0f64 {
+                return None
+            }
+            let entropy = vector.iter()
+                .map(|n| n.as_f64() / total)
+                .filter(|share| *share > 0f64)
+                .map(|share| -share * share.log2())
+                .sum::<f64>();
+            Some(OrdF64::from(entropy))
+        }
+    );
+// Gini coefficient of the values: 0 if they are all equal, approaching 1 if one value makes up
+// the entire total.
+impl_calculator!(Gini -> OrdF64 where N: Number;
+        fn calculate(vector: Vec<N>) -> Option<OrdF64> { gini(&vector).map(OrdF64::from) }
+    );
+// The value at the given percentile (0-100), using the nearest-rank method.
+impl_calculator!(Percentile(usize) -> N where N: Ord, Clone;
+        fn calculate(mut items: Vec<N>, percentile: &usize) -> Option<N> {
+            items.sort();
+            nearest_rank(&items, (*percentile).min(100) as f64 / 100f64)
+        }
+    );
+// The values that split the collection into the given number of equally-sized parts, eg. the
+// three quartiles for `Quantiles(attribute, 4)`, using the nearest-rank method.
+impl_calculator!(Quantiles(usize) -> Vec<N> where N: Ord, Clone;
+        fn calculate(mut items: Vec<N>, parts: &usize) -> Option<Vec<N>> {
+            if *parts == 0 || items.is_empty() {
+                return None
+            }
+            items.sort();
+            (1..*parts).map(|part| nearest_rank(&items, part as f64 / *parts as f64)).collect()
+        }
+    );
+// The number of values in each of the given number of equally wide bins spanning from the smallest
+// to the largest value, each bin with its lower bound.
+impl_calculator!(Histogram(usize) -> Vec<(OrdF64, usize)> where N: Number;
+        fn calculate(vector: Vec<N>, bins: &usize) -> Option<Vec<(OrdF64, usize)>> {
+            histogram(&vector, *bins).map(|histogram| {
+                histogram.into_iter().map(|(bound, count)| (OrdF64::from(bound), count)).collect()
+            })
+        }
+    );
 
-// macro_rules! impl_interval_binning_function {
-//     ($from:tt -> $to:tt) => {
-//         impl BinningFunction for Interval<usize> {
-//             type From = $from;
-//             type Into = $to;
-//             fn bin(&self, value: Self::From) -> Bin<Self> {
-//                 let binning_function = self.clone();
-//                 let bin = value / self.0;
-//                 Bin { value , bin, binning_function }
-//             }
-//         }
-//     }
-// }
+fn variance<N>(vector: &Vec<N>) -> Option<f64> where N: Number {
+    if vector.is_empty() {
+        return None
+    }
+    let length = vector.len() as f64;
+    let mean = vector.iter().map(|n| n.as_f64()).sum::<f64>() / length;
+    Some(vector.iter().map(|n| (n.as_f64() - mean).powi(2)).sum::<f64>() / length)
+}
+
+fn nearest_rank<N>(sorted: &Vec<N>, fraction: f64) -> Option<N> where N: Clone {
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.max(1) - 1).cloned()
+}
+
+fn gini<N>(vector: &Vec<N>) -> Option<f64> where N: Number {
+    let mut values: Vec<f64> = vector.iter().map(|n| n.as_f64()).collect();
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let length = values.len() as f64;
+    let total = values.iter().sum::<f64>();
+    if values.is_empty() || total <= 0f64 {
+        return None
+    }
+    let weighted = values.iter().enumerate()
+        .map(|(i, value)| (2f64 * (i + 1) as f64 - length - 1f64) * value)
+        .sum::<f64>();
+    Some(weighted / (length * total))
+}
+
+fn histogram<N>(vector: &Vec<N>, bins: usize) -> Option<Vec<(f64, usize)>> where N: Number {
+    if bins == 0 || vector.is_empty() {
+        return None
+    }
+    let values: Vec<f64> = vector.iter().map(|n| n.as_f64()).collect();
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0usize; bins];
+    for value in values {
+        let bin = if width > 0f64 { ((value - min) / width) as usize } else { 0 };
+        counts[bin.min(bins - 1)] += 1;
+    }
+    Some(counts.into_iter().enumerate().map(|(bin, count)| (min + bin as f64 * width, count)).collect())
+}
+/*
+ * The bucket that a value falls into, identified by its index among the buckets of a binning
+ * function, and labeled for printing (eg. `100..200`, `Q2 (3..8)`, or `2020-03`). Buckets are
+ * ordered by index, so they can be used as keys for `group_by` and `sort_by`.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bin { pub index: i64, pub label: String }
+impl Bin {
+    pub fn new<S>(index: i64, label: S) -> Self where S: Into<String> { Bin { index, label: label.into() } }
+}
+impl Display for Bin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "{}", self.label) }
+}
+
+pub trait BinningFunction<N> {
+    fn bin(&self, value: &N) -> Option<Bin>;
+    // Binning functions that depend on all values, like quantiles, compute their bounds here.
+    fn prepare(&self, _values: Vec<N>) {}
+    fn needs_preparation(&self) -> bool { false }
+}
+
+// Buckets of equal width: `Interval(100)` puts 0 to 99 into `0..100`, 100 to 199 into `100..200`, etc.
+#[derive(Clone, Copy, Debug)] pub struct Interval<M: Number>(pub M);
+impl<N, M> BinningFunction<N> for Interval<M> where N: Number, M: Number {
+    fn bin(&self, value: &N) -> Option<Bin> {
+        let width = self.0.as_f64();
+        if width <= 0f64 {
+            return None
+        }
+        let index = (value.as_f64() / width).floor();
+        Some(Bin::new(index as i64, format!("{}..{}", index * width, (index + 1f64) * width)))
+    }
+}
 
-// pub trait DivInto<T> {
-//     fn div_by(&self, other: &T) -> T;
-//     fn convert_into(&self) -> T;
-//     fn convert_from(other: &T) -> Self;
-// }
-//
-// impl<T> BinningFunction<T> for Interval<i64> where T: DivInto<i64> {
-//     type Into = i64;
-//     fn bin(&self, value: T) -> Bin<T, Self> {
-//         let binning_function = self.clone();
-//         let bin = value.div_by(&self.0);
-//         Bin { value , bin, binning_function }
-//     }
-//     fn minimum(&self, bin: Self::Into) -> T {
-//         T::convert_from(&(bin * self.0))
-//     }
-//     fn maximum(&self, bin: Self::Into) -> T {
-//         T::convert_from(&(((bin + 1) * self.0) - 1))
-//     }
-// }
+// Buckets growing by powers of the base: `LogScale(10)` makes `0..1`, `1..10`, `10..100`, etc.
+#[derive(Clone, Copy, Debug)] pub struct LogScale<M: Number>(pub M);
+impl<N, M> BinningFunction<N> for LogScale<M> where N: Number, M: Number {
+    fn bin(&self, value: &N) -> Option<Bin> {
+        let (base, value) = (self.0.as_f64(), value.as_f64());
+        if base <= 1f64 {
+            return None
+        }
+        if value < 1f64 {
+            return Some(Bin::new(0, "0..1"))
+        }
+        let mut exponent = (value.ln() / base.ln()).floor() as i32;
+        // Correct for rounding errors at the bucket bounds.
+        while base.powi(exponent) > value { exponent -= 1 }
+        while base.powi(exponent + 1) <= value { exponent += 1 }
+        Some(Bin::new(exponent as i64 + 1, format!("{}..{}", base.powi(exponent), base.powi(exponent + 1))))
+    }
+}
+
+/*
+ * Buckets holding equal shares of the values in the current iterator, eg. `Quantile(4)` splits it
+ * into quartiles `Q1` to `Q4`, using nearest-rank bounds. The bounds are only known once the whole
+ * iterator is available, so verbs that are otherwise lazy (eg. `map_into` or `filter_by`) collect
+ * the iterator first. For groups, the bounds are computed within each group.
+ */
+#[allow(non_snake_case)]
+pub fn Quantile(parts: usize) -> QuantileBinning {
+    QuantileBinning { parts, bounds: std::cell::RefCell::new(None) }
+}
+#[derive(Clone, Debug)] pub struct QuantileBinning { parts: usize, bounds: std::cell::RefCell<Option<Vec<f64>>> }
+impl<N> BinningFunction<N> for QuantileBinning where N: Number {
+    fn bin(&self, value: &N) -> Option<Bin> {
+        let value = value.as_f64();
+        self.bounds.borrow().as_ref().and_then(|bounds| {
+            let (minimum, maximum) = (*bounds.first()?, *bounds.last()?);
+            let cuts = &bounds[1..bounds.len() - 1];
+            let index = cuts.iter().filter(|cut| value > **cut).count();
+            let lower = if index == 0 { minimum } else { cuts[index - 1] };
+            let upper = if index == cuts.len() { maximum } else { cuts[index] };
+            Some(Bin::new(index as i64 + 1, format!("Q{} ({}..{})", index + 1, lower, upper)))
+        })
+    }
+    fn prepare(&self, values: Vec<N>) {
+        let mut values: Vec<f64> = values.into_iter().map(|value| value.as_f64()).collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let bounds = if self.parts == 0 || values.is_empty() {
+            None
+        } else {
+            let mut bounds = vec![values[0]];
+            bounds.extend((1..self.parts).flat_map(|part| nearest_rank(&values, part as f64 / self.parts as f64)));
+            bounds.push(values[values.len() - 1]);
+            Some(bounds)
+        };
+        self.bounds.replace(bounds);
+    }
+    fn needs_preparation(&self) -> bool { true }
+}
 
-//         100
-// 0 ->      0  0..99         (n) * N..( (n+1) * N )-1
-// 10->      0  0..99
-// 100->     1  100..199
-// 101->     1  100..199
-// 1000     10  1000..1099
-// -1 ->     0
+// Buckets of timestamps by calendar month (`2020-03`) or year (`2020`), in UTC.
+#[derive(Clone, Copy, Debug)] pub struct ByMonth;
+#[derive(Clone, Copy, Debug)] pub struct ByYear;
+impl BinningFunction<Timestamp> for ByMonth {
+    fn bin(&self, value: &Timestamp) -> Option<Bin> {
+        let date = NaiveDateTime::from_timestamp_opt(*value, 0)?;
+        Some(Bin::new(date.year() as i64 * 12 + date.month0() as i64, date.format("%Y-%m").to_string()))
+    }
+}
+impl BinningFunction<Timestamp> for ByYear {
+    fn bin(&self, value: &Timestamp) -> Option<Bin> {
+        let date = NaiveDateTime::from_timestamp_opt(*value, 0)?;
+        Some(Bin::new(date.year() as i64, date.format("%Y").to_string()))
+    }
+}
 
 pub struct Bucket<A: Attribute, F>(pub A, pub F);
 impl<A, F, T> Attribute for Bucket<A, F> where A: Attribute<Object=T> {
     type Object = T;
 }
-// impl<'a, A, F, T, I> OptionGetter<'a> for Bucket<A, F>
-//     where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=I>, F: BinningFunction<I> {
-//     type IntoItem = Bin<I, F>;
-//     fn get_opt(&self, object: &ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem> {
-//         self.0.get_opt(object).map(|item| self.1.bin(item))
-//     }
-// }
-// impl<'a, A, F, T, I> Getter<'a> for Bucket<A, F>
-//     where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=I>, F: BinningFunction<I> {
-//     type IntoItem = Option<Bin<I, F>>;
-//     fn get(&self, object: &ItemWithData<'a, Self::Object>) -> Self::IntoItem {
-//         self.0.get_opt(object).map(|item| self.1.bin(item))
-//     }
-// }
+impl<'a, A, F, T, I> OptionGetter<'a> for Bucket<A, F>
+    where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=I>, F: BinningFunction<I> {
+    type IntoItem = Bin;
+    fn get_opt(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem> {
+        self.0.get_opt(object).and_then(|item| self.1.bin(&item))
+    }
+    fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Object>]) {
+        self.0.prepare(objects);
+        self.1.prepare(objects.iter().flat_map(|object| self.0.get_opt(object)).collect())
+    }
+    fn needs_preparation(&self) -> bool { self.0.needs_preparation() || self.1.needs_preparation() }
+}
+impl<'a, A, F, T, I> Getter<'a> for Bucket<A, F>
+    where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=I>, F: BinningFunction<I> {
+    type IntoItem = Option<Bin>;
+    fn get(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Self::IntoItem {
+        self.get_opt(object)
+    }
+    fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Object>]) { OptionGetter::prepare(self, objects) }
+    fn needs_preparation(&self) -> bool { OptionGetter::needs_preparation(self) }
+}
 
 pub struct Ratio<A: Attribute<Object=T>, P: Attribute<Object=T>, T>(pub A, pub P);
 impl<A, P, T> Attribute for Ratio<A, P, T>
@@ -2212,6 +2765,261 @@ impl<'a, A, P, T> Getter<'a> for Ratio<A, P, T>
     }
 }
 
+/*
+ * Arguments of arithmetic expressions and of comparisons between attributes: either an attribute
+ * or a constant. Bare constants are `usize` or `f64`, so that literals such as `1` or `0.5` need no
+ * suffix. Constants of other types are wrapped in `Constant`, eg. `Constant(1_000_000_000i64)`.
+ */
+pub trait Operand<'a, T> {
+    type Value;
+    fn value(&self, object: &objects::ItemWithData<'a, T>) -> Option<Self::Value>;
+}
+
+impl<'a, A, T> Operand<'a, T> for A where A: Attribute<Object=T> + OptionGetter<'a> {
+    type Value = <A as OptionGetter<'a>>::IntoItem;
+    fn value(&self, object: &objects::ItemWithData<'a, T>) -> Option<Self::Value> {
+        self.get_opt(object)
+    }
+}
+
+macro_rules! impl_constant_operand {
+    ($($constant:ty),+) => {
+        $(impl<'a, T> Operand<'a, T> for $constant {
+            type Value = $constant;
+            fn value(&self, _object: &objects::ItemWithData<'a, T>) -> Option<Self::Value> {
+                Some(*self)
+            }
+        })+
+    }
+}
+
+impl_constant_operand!(usize, f64);
+
+#[derive(Debug, Clone, Copy)] pub struct Constant<N>(pub N);
+impl<'a, N, T> Operand<'a, T> for Constant<N> where N: Clone {
+    type Value = N;
+    fn value(&self, _object: &objects::ItemWithData<'a, T>) -> Option<Self::Value> {
+        Some(self.0.clone())
+    }
+}
+
+/// Numbers that arithmetic expressions can convert between.
+pub trait Number: Copy {
+    fn as_f64(self) -> f64;
+    fn from_f64(number: f64) -> Self;
+    fn is_zero(self) -> bool;
+    fn checked_abs(self) -> Option<Self>;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn checked_div(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_number {
+    (unsigned $($number:ty),+) => {
+        $(impl Number for $number {
+            fn as_f64(self) -> f64 { self as f64 }
+            fn from_f64(number: f64) -> Self { number as $number }
+            fn is_zero(self) -> bool { self == 0 }
+            fn checked_abs(self) -> Option<Self> { Some(self) }
+            fn checked_add(self, other: Self) -> Option<Self> { <$number>::checked_add(self, other) }
+            fn checked_sub(self, other: Self) -> Option<Self> { <$number>::checked_sub(self, other) }
+            fn checked_mul(self, other: Self) -> Option<Self> { <$number>::checked_mul(self, other) }
+            fn checked_div(self, other: Self) -> Option<Self> { <$number>::checked_div(self, other) }
+        })+
+    };
+    (signed $($number:ty),+) => {
+        $(impl Number for $number {
+            fn as_f64(self) -> f64 { self as f64 }
+            fn from_f64(number: f64) -> Self { number as $number }
+            fn is_zero(self) -> bool { self == 0 }
+            fn checked_abs(self) -> Option<Self> { <$number>::checked_abs(self) }
+            fn checked_add(self, other: Self) -> Option<Self> { <$number>::checked_add(self, other) }
+            fn checked_sub(self, other: Self) -> Option<Self> { <$number>::checked_sub(self, other) }
+            fn checked_mul(self, other: Self) -> Option<Self> { <$number>::checked_mul(self, other) }
+            fn checked_div(self, other: Self) -> Option<Self> { <$number>::checked_div(self, other) }
+        })+
+    };
+}
+
+impl_number!(unsigned usize, u64, u32, u16, u8);
+impl_number!(signed i64, i32, i16, i8);
+
+impl Number for f64 {
+    fn as_f64(self) -> f64 { self }
+    fn from_f64(number: f64) -> Self { number }
+    fn is_zero(self) -> bool { self == 0f64 }
+    fn checked_abs(self) -> Option<Self> { Some(f64::abs(self)) }
+    fn checked_add(self, other: Self) -> Option<Self> { Some(self + other) }
+    fn checked_sub(self, other: Self) -> Option<Self> { Some(self - other) }
+    fn checked_mul(self, other: Self) -> Option<Self> { Some(self * other) }
+    fn checked_div(self, other: Self) -> Option<Self> { if other.is_zero() { None } else { Some(self / other) } }
+}
+
+/*
+ * Arithmetic over two operands, the first of which must be an attribute, eg.
+ * `Sub(project::Commits, project::MainBranchCommits)` or `Mul(project::Stars, 2)`. Both operands
+ * must have the same type (see `Cast`). If either value is missing, so is the result. Results that
+ * do not fit the type, such as unsigned values that would go below zero, are missing values, and
+ * so are divisions by zero. Integer division rounds towards zero, as in Rust.
+ */
+macro_rules! impl_arithmetic {
+    ($name:ident, $checked:ident) => {
+        pub struct $name<A, B>(pub A, pub B);
+        impl<A, B, T> Attribute for $name<A, B> where A: Attribute<Object=T> {
+            type Object = T;
+        }
+        impl<'a, A, B, T, N> OptionGetter<'a> for $name<A, B>
+            where A: Attribute<Object=T> + Operand<'a, T, Value=N>, B: Operand<'a, T, Value=N>, N: Number {
+            type IntoItem = N;
+            fn get_opt(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem> {
+                match (self.0.value(object), self.1.value(object)) {
+                    (Some(a), Some(b)) => a.$checked(b),
+                    _ => None,
+                }
+            }
+        }
+        impl<'a, A, B, T, N> Getter<'a> for $name<A, B>
+            where A: Attribute<Object=T> + Operand<'a, T, Value=N>, B: Operand<'a, T, Value=N>, N: Number {
+            type IntoItem = Option<N>;
+            fn get(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Self::IntoItem {
+                self.get_opt(object)
+            }
+        }
+    };
+}
+
+impl_arithmetic!(Add, checked_add);
+impl_arithmetic!(Sub, checked_sub);
+impl_arithmetic!(Mul, checked_mul);
+impl_arithmetic!(Div, checked_div);
+
+/*
+ * Functions of a single numeric attribute. `Log` is the natural logarithm, which is missing for
+ * values that are not positive. `Abs` is missing for the smallest value of a signed integer type,
+ * whose absolute value does not fit the type. `Cast` converts between numeric types, rounding
+ * towards zero and saturating when converting to integers, eg. `Cast::<f64, _>(project::Stars)`.
+ */
+macro_rules! impl_numeric_function {
+    ($name:ident<$($parameter:ident),*> -> $result:ty; $number:ident => $function:expr) => {
+        impl<A, T, $($parameter),*> Attribute for $name<A, $($parameter),*> where A: Attribute<Object=T> {
+            type Object = T;
+        }
+        impl<'a, A, T, N, $($parameter),*> OptionGetter<'a> for $name<A, $($parameter),*>
+            where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=N>, N: Number, $($parameter: Number),* {
+            type IntoItem = $result;
+            fn get_opt(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem> {
+                self.0.get_opt(object).and_then(|$number| $function)
+            }
+        }
+        impl<'a, A, T, N, $($parameter),*> Getter<'a> for $name<A, $($parameter),*>
+            where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=N>, N: Number, $($parameter: Number),* {
+            type IntoItem = Option<$result>;
+            fn get(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Self::IntoItem {
+                self.get_opt(object)
+            }
+        }
+    }
+}
+
+pub struct Log<A: Attribute>(pub A);
+pub struct Abs<A: Attribute>(pub A);
+pub struct CastAttribute<A: Attribute, M>(pub A, pub PhantomData<M>);
+
+#[allow(non_snake_case)]
+pub fn Cast<M, A>(attribute: A) -> CastAttribute<A, M> where A: Attribute, M: Number {
+    CastAttribute(attribute, PhantomData)
+}
+
+impl_numeric_function!(Log<> -> f64; n => if n.as_f64() > 0f64 { Some(n.as_f64().ln()) } else { None });
+impl_numeric_function!(Abs<> -> N; n => n.checked_abs());
+impl_numeric_function!(CastAttribute<M> -> M; n => Some(M::from_f64(n.as_f64())));
+
+/*
+ * Comparisons between two operands, the first of which must be an attribute, eg.
+ * `Greater(Count(project::Authors), Count(project::Committers))`. Objects for which either value is
+ * missing are not accepted.
+ */
+macro_rules! impl_operand_comparison {
+    ($name:ident, $trait_limit:ident, $comparator:ident) => {
+        pub struct $name<A, B>(pub A, pub B);
+        impl<'a, A, B, T, N> Filter<'a> for $name<A, B>
+            where A: Attribute<Object=T> + Operand<'a, T, Value=N>, B: Operand<'a, T, Value=N>, N: $trait_limit {
+            type Item = T;
+            fn accept(&self, item_with_data: &objects::ItemWithData<'a, Self::Item>) -> bool {
+                match (self.0.value(item_with_data), self.1.value(item_with_data)) {
+                    (Some(a), Some(b)) => a.$comparator(&b),
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+impl_operand_comparison!(Less,           PartialOrd, lt);
+impl_operand_comparison!(LessOrEqual,    PartialOrd, le);
+impl_operand_comparison!(EqualTo,        PartialEq,  eq);
+impl_operand_comparison!(GreaterOrEqual, PartialOrd, ge);
+impl_operand_comparison!(Greater,        PartialOrd, gt);
+
+/* Join keys. A single attribute joins objects of the same type on equal values, a pair of
+   attributes joins objects of different types on equal values of the first attribute for the
+   left object and the second for the right object. `Each` marks an attribute that produces many
+   keys for one object on either side, eg. `(Each(user::ProjectIds), project::Id)` joins every
+   user with each of the projects they contributed to. Objects without a key are never joined.
+ */
+pub struct Each<A: Attribute>(pub A);
+
+impl<'a, A, T, K> JoinKey<'a, T, T> for A
+    where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=K>, K: Hash + Eq + Clone {
+    type Key = K;
+    fn left_keys(&self, object: &objects::ItemWithData<'a, T>) -> Vec<Self::Key> {
+        self.get_opt(object).into_iter().collect()
+    }
+    fn right_keys(&self, object: &objects::ItemWithData<'a, T>) -> Vec<Self::Key> {
+        self.get_opt(object).into_iter().collect()
+    }
+}
+
+impl<'a, A, B, T, U, K> JoinKey<'a, T, U> for (A, B)
+    where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=K>,
+          B: Attribute<Object=U> + OptionGetter<'a, IntoItem=K>,
+          K: Hash + Eq + Clone {
+    type Key = K;
+    fn left_keys(&self, object: &objects::ItemWithData<'a, T>) -> Vec<Self::Key> {
+        self.0.get_opt(object).into_iter().collect()
+    }
+    fn right_keys(&self, object: &objects::ItemWithData<'a, U>) -> Vec<Self::Key> {
+        self.1.get_opt(object).into_iter().collect()
+    }
+}
+
+impl<'a, A, B, T, U, K> JoinKey<'a, T, U> for (Each<A>, B)
+    where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=Vec<K>>,
+          B: Attribute<Object=U> + OptionGetter<'a, IntoItem=K>,
+          K: Hash + Eq + Clone {
+    type Key = K;
+    fn left_keys(&self, object: &objects::ItemWithData<'a, T>) -> Vec<Self::Key> {
+        (self.0).0.get_opt(object).unwrap_or_default()
+    }
+    fn right_keys(&self, object: &objects::ItemWithData<'a, U>) -> Vec<Self::Key> {
+        self.1.get_opt(object).into_iter().collect()
+    }
+}
+
+impl<'a, A, B, T, U, K> JoinKey<'a, T, U> for (A, Each<B>)
+    where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=K>,
+          B: Attribute<Object=U> + OptionGetter<'a, IntoItem=Vec<K>>,
+          K: Hash + Eq + Clone {
+    type Key = K;
+    fn left_keys(&self, object: &objects::ItemWithData<'a, T>) -> Vec<Self::Key> {
+        self.0.get_opt(object).into_iter().collect()
+    }
+    fn right_keys(&self, object: &objects::ItemWithData<'a, U>) -> Vec<Self::Key> {
+        (self.1).0.get_opt(object).unwrap_or_default()
+    }
+}
+
 /// Get an attribute's attribute.
 pub struct From<O: Attribute, A: Attribute> (pub O, pub A);
 
@@ -2321,9 +3129,139 @@ impl<'a, A, P, T, I> OptionCountable<'a> for FromEachIf<A, P>
     }
 }
 
+/*
+ * A user-defined attribute computed by a closure, eg.:
+ *
+ *     database.projects().filter_by(Computed(|project: &ItemWithData<Project>| project.url().ends_with(".rs")))
+ *
+ * The attribute can be used wherever built-in attributes are: for filtering if the closure
+ * returns `bool`, for sorting, selecting, sampling with strata, or within `Mean`, `Ratio`, etc.
+ * The closure's result is the value of the attribute as is. Use `OptionComputed` for closures that
+ * return an `Option`.
+ */
+#[allow(non_snake_case)]
+pub fn Computed<T, R, F>(function: F) -> ComputedAttribute<T, F> where F: Fn(&objects::ItemWithData<T>) -> R {
+    ComputedAttribute { function, object: PhantomData }
+}
+
+pub struct ComputedAttribute<T, F> { function: F, object: PhantomData<T> }
+
+impl<T, F> Attribute for ComputedAttribute<T, F> {
+    type Object = T;
+}
+
+impl<'a, T, F, R> Getter<'a> for ComputedAttribute<T, F> where F: Fn(&objects::ItemWithData<'a, T>) -> R {
+    type IntoItem = R;
+    fn get(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Self::IntoItem {
+        (self.function)(object)
+    }
+}
+
+impl<'a, T, F, R> OptionGetter<'a> for ComputedAttribute<T, F> where F: Fn(&objects::ItemWithData<'a, T>) -> R {
+    type IntoItem = R;
+    fn get_opt(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem> {
+        Some((self.function)(object))
+    }
+}
+
+impl<'a, T, F> Filter<'a> for ComputedAttribute<T, F> where F: Fn(&objects::ItemWithData<'a, T>) -> bool {
+    type Item = T;
+    fn accept(&self, item_with_data: &objects::ItemWithData<'a, Self::Item>) -> bool {
+        (self.function)(item_with_data)
+    }
+}
+
+/*
+ * A user-defined attribute computed by a closure that may not produce a value, eg.:
+ *
+ *     database.projects().sort_by(OptionComputed(|project: &ItemWithData<Project>| project.star_count()))
+ *
+ * Like the built-in optional attributes, `None` is a missing value rather than a value of its own:
+ * it is sorted first, left out of `Mean`, etc., and rejected when filtering.
+ */
+#[allow(non_snake_case)]
+pub fn OptionComputed<T, R, F>(function: F) -> OptionComputedAttribute<T, F> where F: Fn(&objects::ItemWithData<T>) -> Option<R> {
+    OptionComputedAttribute { function, object: PhantomData }
+}
+
+pub struct OptionComputedAttribute<T, F> { function: F, object: PhantomData<T> }
+
+impl<T, F> Attribute for OptionComputedAttribute<T, F> {
+    type Object = T;
+}
+
+impl<'a, T, F, R> Getter<'a> for OptionComputedAttribute<T, F> where F: Fn(&objects::ItemWithData<'a, T>) -> Option<R> {
+    type IntoItem = Option<R>;
+    fn get(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Self::IntoItem {
+        (self.function)(object)
+    }
+}
+
+impl<'a, T, F, R> OptionGetter<'a> for OptionComputedAttribute<T, F> where F: Fn(&objects::ItemWithData<'a, T>) -> Option<R> {
+    type IntoItem = R;
+    fn get_opt(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem> {
+        (self.function)(object)
+    }
+}
+
+impl<'a, T, F> Filter<'a> for OptionComputedAttribute<T, F> where F: Fn(&objects::ItemWithData<'a, T>) -> Option<bool> {
+    type Item = T;
+    fn accept(&self, item_with_data: &objects::ItemWithData<'a, Self::Item>) -> bool {
+        (self.function)(item_with_data).unwrap_or(false)
+    }
+}
+
+/*
+ * Remembers the values of an attribute per object id in the cache directory (see
+ * `Database::cached_value`), so that an expensive attribute, typically a `Computed` one, is
+ * computed once per object across runs. The name identifies the cache file, eg.:
+ *
+ *     Cached("test_ratio", Computed(|project: &ItemWithData<Project>| ...))
+ *
+ * The cache is not invalidated when the attribute changes: use a new name instead. If the name is
+ * already used by a cached attribute with different types, the value is computed without caching.
+ */
+pub struct Cached<A: Attribute>(pub &'static str, pub A);
+
+impl<A, T> Attribute for Cached<A> where A: Attribute<Object=T> {
+    type Object = T;
+}
+
+impl<'a, A, T, V> OptionGetter<'a> for Cached<A>
+    where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=V>,
+          T: Identifiable, T::Identity: Persistent + 'static,
+          V: Clone + Persistent + 'static {
+    type IntoItem = V;
+    fn get_opt(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem> {
+        match object.data.cached_value(self.0, object.item.id(), || self.1.get_opt(object)) {
+            Ok(value) => value,
+            Err(error) => {
+                eprintln!("WARNING! {}, computing the attribute without caching", error);
+                self.1.get_opt(object)
+            }
+        }
+    }
+    fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Object>]) { self.1.prepare(objects) }
+    fn needs_preparation(&self) -> bool { self.1.needs_preparation() }
+}
+
+impl<'a, A, T, V> Getter<'a> for Cached<A>
+    where A: Attribute<Object=T> + OptionGetter<'a, IntoItem=V>,
+          T: Identifiable, T::Identity: Persistent + 'static,
+          V: Clone + Persistent + 'static {
+    type IntoItem = Option<V>;
+    fn get(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Self::IntoItem {
+        self.get_opt(object)
+    }
+    fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Object>]) { OptionGetter::prepare(self, objects) }
+    fn needs_preparation(&self) -> bool { OptionGetter::needs_preparation(self) }
+}
+
+// Counts the keys of a `Select` for the length of its array of directions.
+macro_rules! count_one { ($i:tt) => { 1 } }
 macro_rules! impl_select {
         ($n:ident, $($ti:ident -> $i:tt),+) => {
-            pub struct $n<$($ti: Attribute,)+> ($(pub $ti,)+);
+            pub struct $n<$($ti,)+> ($(pub $ti,)+);
             impl<T, $($ti,)+> Attribute for $n<$($ti,)+>
                 where $($ti: Attribute<Object=T>,)+ {
                 type Object = T;
@@ -2334,6 +3272,10 @@ macro_rules! impl_select {
                 fn get_opt(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Option<Self::IntoItem> {
                     Some(($(self.$i.get_opt(object),)+))
                 }
+                fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Object>]) {
+                    $(self.$i.prepare(objects);)+
+                }
+                fn needs_preparation(&self) -> bool { false $(|| self.$i.needs_preparation())+ }
             }
             impl<'a, T, $($ti,)+> Getter<'a> for $n<$($ti,)+>
                 where $($ti: Attribute<Object=T> + Getter<'a>,)+ {
@@ -2342,6 +3284,32 @@ macro_rules! impl_select {
                 fn get(&self, object: &objects::ItemWithData<'a, Self::Object>) -> Self::IntoItem {
                     ($(self.$i.get(object),)+)
                 }
+                fn prepare(&self, objects: &[objects::ItemWithData<'a, Self::Object>]) {
+                    $(self.$i.prepare(objects);)+
+                }
+                fn needs_preparation(&self) -> bool { false $(|| self.$i.needs_preparation())+ }
+            }
+            impl<'a, T, $($ti,)+> SortKeys<'a, T> for $n<$($ti,)+>
+                where $($ti: Attribute<Object=T> + OptionGetter<'a>, $ti::IntoItem: Ord,)+ {
+                type Keys = ($(Option<$ti::IntoItem>,)+);
+                type Directions = [sort::Direction; 0 $(+ count_one!($i))+];
+                fn sort_keys(&self, object: &objects::ItemWithData<'a, T>) -> Self::Keys {
+                    ($(self.$i.get_opt(object),)+)
+                }
+                fn prepare(&self, objects: &[objects::ItemWithData<'a, T>]) {
+                    $(OptionGetter::prepare(&self.$i, objects);)+
+                }
+                fn compare_keys(a: &Self::Keys, b: &Self::Keys, directions: &Self::Directions, nulls: sort::Nulls) -> std::cmp::Ordering {
+                    std::cmp::Ordering::Equal
+                        $(.then_with(|| sort::compare(&a.$i, &b.$i, directions[$i], nulls)))+
+                }
+            }
+            impl<'a, T, $($ti,)+> Aggregator<'a, T> for $n<$($ti,)+>
+                where $($ti: Aggregator<'a, T>,)+ {
+                type Output = ($($ti::Output,)+);
+                fn aggregate(&self, objects: &Vec<objects::ItemWithData<'a, T>>) -> Self::Output {
+                    ($(self.$i.aggregate(objects),)+)
+                }
             }
         }
     }
@@ -2530,3 +3498,192 @@ impl<'a, A, T> Getter<'a> for FormatDuration<A>
         self.0.get_opt(object).map(|duration| Self::to_fuzzy_duration(duration))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::marker::PhantomData;
+    use crate::*;
+    use crate::objects::{Project, ProjectId, Commit, User, UserId};
+
+    // The type of the values of an operand of an expression over projects.
+    fn operand_value<'a, O>(_operand: &O) -> PhantomData<O::Value> where O: Operand<'a, Project> {
+        PhantomData
+    }
+
+    #[test] fn test_operand_values() {
+        let _: PhantomData<usize> = operand_value(&1);
+        let _: PhantomData<f64> = operand_value(&0.5);
+        let _: PhantomData<i64> = operand_value(&Constant(-1i64));
+        let _: PhantomData<usize> = operand_value(&project::Stars);
+        let _: PhantomData<usize> = operand_value(&Sub(project::Stars, 1));
+        let _: PhantomData<f64> = operand_value(&Cast::<f64, _>(project::Stars));
+        let _: PhantomData<f64> = operand_value(&Log(project::Stars));
+    }
+
+    // The type of the keys by which objects of types T and U are joined.
+    fn join_key<'a, J, T, U>(_key: &J) -> PhantomData<J::Key> where J: JoinKey<'a, T, U> {
+        PhantomData
+    }
+
+    #[test] fn test_join_keys() {
+        let _: PhantomData<ProjectId> = join_key::<_, Project, Project>(&project::Id);
+        let _: PhantomData<UserId> = join_key::<_, Commit, User>(&(commit::AuthorId, user::Id));
+        let _: PhantomData<ProjectId> = join_key::<_, User, Project>(&(Each(user::ProjectIds), project::Id));
+        let _: PhantomData<UserId> = join_key::<_, User, Project>(&(user::Id, Each(project::UserIds)));
+    }
+
+    #[test] fn test_compare_keys() {
+        type Keys = Select2<project::Stars, project::Created>;
+        let directions: <Keys as SortKeys<Project>>::Directions = [Desc, Asc];
+        let compare = |a, b, nulls| <Keys as SortKeys<Project>>::compare_keys(&a, &b, &directions, nulls);
+        assert_eq!(compare((Some(2), Some(5)), (Some(1), Some(1)), Nulls::Last), std::cmp::Ordering::Less);
+        assert_eq!(compare((Some(1), Some(5)), (Some(1), Some(1)), Nulls::Last), std::cmp::Ordering::Greater);
+        assert_eq!(compare((Some(1), None), (Some(1), Some(1)), Nulls::Last), std::cmp::Ordering::Greater);
+        assert_eq!(compare((Some(1), None), (Some(1), Some(1)), Nulls::First), std::cmp::Ordering::Less);
+        assert_eq!(compare((None, Some(1)), (Some(1), None), Nulls::Last), std::cmp::Ordering::Greater);
+        assert_eq!(compare((Some(1), Some(1)), (Some(1), Some(1)), Nulls::First), std::cmp::Ordering::Equal);
+    }
+
+    #[test] fn test_number() {
+        assert_eq!(Number::checked_add(u8::MAX, 1), None);
+        assert_eq!(Number::checked_sub(3usize, 5), None);
+        assert_eq!(Number::checked_sub(5usize, 3), Some(2));
+        assert_eq!(Number::checked_sub(3i64, 5), Some(-2));
+        assert_eq!(Number::checked_mul(i32::MAX, 2), None);
+        assert_eq!(Number::checked_div(-7i64, 2), Some(-3));
+        assert_eq!(Number::checked_div(7usize, 0), None);
+        assert_eq!(Number::checked_div(1f64, 4f64), Some(0.25));
+        assert_eq!(Number::checked_div(1f64, 0f64), None);
+        assert_eq!(Number::checked_abs(-3i16), Some(3));
+        assert_eq!(Number::checked_abs(i8::MIN), None);
+        assert_eq!(Number::checked_abs(-2.5f64), Some(2.5));
+
+        assert_eq!(<u8 as Number>::from_f64(300.7), 255);
+        assert_eq!(<u8 as Number>::from_f64(-1.0), 0);
+        assert_eq!(<i32 as Number>::from_f64(-2.9), -2);
+        assert_eq!(<usize as Number>::from_f64(f64::NAN), 0);
+        assert_eq!(Number::as_f64(7u64), 7.0);
+        assert!(Number::is_zero(0u32) && !Number::is_zero(0.1f64));
+    }
+
+    #[test] fn test_variance() {
+        assert_eq!(variance::<usize>(&vec![]), None);
+        assert_eq!(variance(&vec![5usize]), Some(0.0));
+        assert_eq!(variance(&vec![2i64, 4, 4, 4, 5, 5, 7, 9]), Some(4.0));
+        assert_eq!(variance(&vec![-1.0f64, 1.0]), Some(1.0));
+    }
+
+    #[test] fn test_nearest_rank() {
+        assert_eq!(nearest_rank::<usize>(&vec![], 0.5), None);
+        assert_eq!(nearest_rank(&vec![7], 0.0), Some(7));
+        assert_eq!(nearest_rank(&vec![7], 1.0), Some(7));
+        let sorted = vec![15, 20, 35, 40, 50];
+        assert_eq!(nearest_rank(&sorted, 0.0), Some(15));
+        assert_eq!(nearest_rank(&sorted, 0.05), Some(15));
+        assert_eq!(nearest_rank(&sorted, 0.3), Some(20));
+        assert_eq!(nearest_rank(&sorted, 0.4), Some(20));
+        assert_eq!(nearest_rank(&sorted, 0.5), Some(35));
+        assert_eq!(nearest_rank(&sorted, 1.0), Some(50));
+    }
+
+    #[test] fn test_gini() {
+        assert_eq!(gini::<usize>(&vec![]), None);
+        assert_eq!(gini(&vec![0usize, 0]), None);
+        assert_eq!(gini(&vec![3usize]), Some(0.0));
+        assert_eq!(gini(&vec![4usize, 4, 4, 4]), Some(0.0));
+        assert_eq!(gini(&vec![0usize, 0, 0, 8]), Some(0.75));
+        assert_eq!(gini(&vec![3usize, 1, 2]), gini(&vec![1usize, 2, 3]));
+        assert!((gini(&vec![1usize, 2, 3]).unwrap() - 2.0 / 9.0).abs() < 1e-12);
+    }
+
+    #[test] fn test_histogram() {
+        assert_eq!(histogram::<usize>(&vec![], 3), None);
+        assert_eq!(histogram(&vec![1usize, 2], 0), None);
+        assert_eq!(histogram(&vec![5usize], 3), Some(vec![(5.0, 1), (5.0, 0), (5.0, 0)]));
+        assert_eq!(histogram(&vec![2usize, 2, 2], 2), Some(vec![(2.0, 3), (2.0, 0)]));
+        // The largest value falls into the last bin, not past it.
+        assert_eq!(histogram(&vec![0usize, 1, 2, 3, 4], 2), Some(vec![(0.0, 2), (2.0, 3)]));
+        assert_eq!(histogram(&vec![0.0f64, 2.5, 5.0, 7.5, 10.0], 4),
+                   Some(vec![(0.0, 1), (2.5, 1), (5.0, 1), (7.5, 2)]));
+    }
+
+    #[test] fn test_interval() {
+        assert_eq!(Interval(100).bin(&0usize), Some(Bin::new(0, "0..100")));
+        assert_eq!(Interval(100).bin(&99usize), Some(Bin::new(0, "0..100")));
+        assert_eq!(Interval(100).bin(&100usize), Some(Bin::new(1, "100..200")));
+        assert_eq!(Interval(100).bin(&-1i64), Some(Bin::new(-1, "-100..0")));
+        assert_eq!(Interval(0.5).bin(&1.2f64), Some(Bin::new(2, "1..1.5")));
+        assert_eq!(Interval(0).bin(&5usize), None);
+    }
+
+    #[test] fn test_log_scale() {
+        assert_eq!(LogScale(10).bin(&0usize), Some(Bin::new(0, "0..1")));
+        assert_eq!(LogScale(10).bin(&1usize), Some(Bin::new(1, "1..10")));
+        assert_eq!(LogScale(10).bin(&9usize), Some(Bin::new(1, "1..10")));
+        assert_eq!(LogScale(10).bin(&10usize), Some(Bin::new(2, "10..100")));
+        assert_eq!(LogScale(10).bin(&1000usize), Some(Bin::new(4, "1000..10000")));
+        assert_eq!(LogScale(2).bin(&8usize), Some(Bin::new(4, "8..16")));
+        assert_eq!(LogScale(1).bin(&8usize), None);
+    }
+
+    #[test] fn test_quantile() {
+        let quartiles = Quantile(4);
+        assert_eq!(BinningFunction::<usize>::bin(&quartiles, &1), None);
+        BinningFunction::<usize>::prepare(&quartiles, vec![8, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(quartiles.bin(&1usize), Some(Bin::new(1, "Q1 (1..2)")));
+        assert_eq!(quartiles.bin(&2usize), Some(Bin::new(1, "Q1 (1..2)")));
+        assert_eq!(quartiles.bin(&3usize), Some(Bin::new(2, "Q2 (2..4)")));
+        assert_eq!(quartiles.bin(&8usize), Some(Bin::new(4, "Q4 (6..8)")));
+        BinningFunction::<usize>::prepare(&quartiles, vec![]);
+        assert_eq!(quartiles.bin(&1usize), None);
+    }
+
+    #[test] fn test_by_month_and_year() {
+        let timestamp: Timestamp = 1583020800; // 2020-03-01 00:00:00 UTC
+        assert_eq!(ByMonth.bin(&timestamp), Some(Bin::new(2020 * 12 + 2, "2020-03")));
+        assert_eq!(ByMonth.bin(&(timestamp - 1)), Some(Bin::new(2020 * 12 + 1, "2020-02")));
+        assert_eq!(ByYear.bin(&timestamp), Some(Bin::new(2020, "2020")));
+        assert_eq!(ByYear.bin(&0), Some(Bin::new(1970, "1970")));
+        assert!(ByMonth.bin(&(timestamp - 1)) < ByMonth.bin(&timestamp));
+    }
+
+    // Groups of projects by key. The groups are empty, since objects cannot exist without a database.
+    fn groups(keys: Vec<u32>) -> Vec<(u32, Vec<objects::ItemWithData<'static, Project>>)> {
+        keys.into_iter().map(|key| (key, Vec::new())).collect()
+    }
+
+    #[test] fn test_calculator_aggregates() {
+        assert!(<Mean<project::Stars> as CalculateStat<usize, _>>::calculate(vec![1, 2, 6]) == Some(Fraction::new(9, 3)));
+        assert!(<Median<project::Stars> as CalculateStat<usize, _>>::calculate(vec![4, 1, 3, 2]) == Some(Fraction::new(5, 2)));
+        assert_eq!(<Max<project::Stars> as CalculateStat<usize, _>>::calculate(vec![4, 1, 3]), Some(4));
+        assert_eq!(<Sum<project::Stars> as CalculateStat<usize, _>>::calculate(vec![]), Some(0));
+
+        let rows: Vec<(u32, usize)> = groups(vec![1, 2]).into_iter().aggregate(Count).collect();
+        assert_eq!(rows, vec![(1, 0), (2, 0)]);
+        let rows: Vec<(u32, Option<usize>)> = groups(vec![1]).into_iter().aggregate(Max(project::Stars)).collect();
+        assert_eq!(rows, vec![(1, None)]);
+        let rows: Vec<(u32, Option<Fraction<usize>>)> = groups(vec![1]).into_iter().aggregate(Mean(project::Stars)).collect();
+        assert!(rows[0].1.is_none());
+    }
+
+    #[test] fn test_select_aggregates() {
+        let rows: Vec<(u32, (usize, Option<Fraction<usize>>, Option<usize>))> = groups(vec![3, 1])
+            .into_iter().aggregate(Select!(Count, Median(project::Stars), Max(project::Stars))).collect();
+        assert_eq!(rows.iter().map(|(key, (count, _, _))| (*key, *count)).collect::<Vec<_>>(), vec![(3, 0), (1, 0)]);
+        assert!(rows.iter().all(|(_, (_, median, max))| median.is_none() && max.is_none()));
+    }
+
+    #[test] fn test_group_keys() {
+        let keys = |groups: Vec<(u32, Vec<objects::ItemWithData<Project>>)>| -> Vec<u32> {
+            groups.into_iter().map(|(key, _)| key).collect()
+        };
+        assert_eq!(keys(groups(vec![2, 3, 1]).into_iter().sort_key().collect()), vec![3, 2, 1]);
+        assert_eq!(keys(groups(vec![2, 3, 1]).into_iter().sort_key_with_direction(Asc).collect()), vec![1, 2, 3]);
+        assert_eq!(keys(groups(vec![2, 3, 1, 4]).into_iter().filter_key(|key| key % 2 == 0).collect()), vec![2, 4]);
+        assert_eq!(keys(groups(vec![2, 3, 1]).into_iter().sort_key().sample_key(Top(2)).collect()), vec![3, 2]);
+        // Random samples of keys do not depend on the order in which the groups come.
+        assert_eq!(keys(groups(vec![5, 2, 3, 1, 4]).into_iter().sample_key(Random(2, Seed(42))).collect()),
+                   keys(groups(vec![1, 2, 3, 4, 5]).into_iter().sample_key(Random(2, Seed(42))).collect()));
+        assert_eq!(groups(vec![5, 2, 3, 1, 4]).into_iter().sample_key(Random(2, Seed(42))).count(), 2);
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::BTreeSet;
+
+/**
+ * Detecting licenses from file contents.
+ *
+ * The contents of a file are matched against a bundled set of license texts identified by their
+ * SPDX identifiers. Both the file and the templates are normalized first (case, punctuation,
+ * whitespace, copyright lines, British spelling), and the similarity is the fraction of the
+ * template's word trigrams that also appear in the file. This makes the match robust against
+ * re-wrapping, reformatting, and the license being embedded in a longer document.
+ *
+ * Short permissive licenses are bundled in full. For the long licenses (Apache, the GPL family,
+ * MPL) we bundle characteristic excerpts: the preamble and the standard notice that is
+ * recommended for inclusion in source files. A full copy of such a license contains the whole
+ * excerpt. Source file headers usually carry only the standard notice, so they are additionally
+ * matched against the notices alone.
+ */
+pub struct LicenseTemplate {
+    pub spdx: &'static str,
+    pub text: &'static str,
+}
+
+pub static LICENSE_TEMPLATES: [LicenseTemplate; 13] = [
+    LicenseTemplate { spdx: "MIT",          text: include_str!("licenses/MIT.txt")          },
+    LicenseTemplate { spdx: "ISC",          text: include_str!("licenses/ISC.txt")          },
+    LicenseTemplate { spdx: "BSD-2-Clause", text: include_str!("licenses/BSD-2-Clause.txt") },
+    LicenseTemplate { spdx: "BSD-3-Clause", text: include_str!("licenses/BSD-3-Clause.txt") },
+    LicenseTemplate { spdx: "Unlicense",    text: include_str!("licenses/Unlicense.txt")    },
+    LicenseTemplate { spdx: "Zlib",         text: include_str!("licenses/Zlib.txt")         },
+    LicenseTemplate { spdx: "Apache-2.0",   text: include_str!("licenses/Apache-2.0.txt")   },
+    LicenseTemplate { spdx: "GPL-2.0",      text: include_str!("licenses/GPL-2.0.txt")      },
+    LicenseTemplate { spdx: "GPL-3.0",      text: include_str!("licenses/GPL-3.0.txt")      },
+    LicenseTemplate { spdx: "LGPL-2.1",     text: include_str!("licenses/LGPL-2.1.txt")     },
+    LicenseTemplate { spdx: "LGPL-3.0",     text: include_str!("licenses/LGPL-3.0.txt")     },
+    LicenseTemplate { spdx: "AGPL-3.0",     text: include_str!("licenses/AGPL-3.0.txt")     },
+    LicenseTemplate { spdx: "MPL-2.0",      text: include_str!("licenses/MPL-2.0.txt")      },
+];
+
+/**
+ * Standard notices that the long licenses recommend to put in the header of each source file.
+ * Short licenses are usually copied into headers whole and are matched by their full texts.
+ */
+pub static LICENSE_NOTICES: [LicenseTemplate; 7] = [
+    LicenseTemplate { spdx: "Apache-2.0", text: "Licensed under the Apache License, Version 2.0 (the \"License\"); you may not use this file except in compliance with the License. You may obtain a copy of the License at http://www.apache.org/licenses/LICENSE-2.0" },
+    LicenseTemplate { spdx: "GPL-2.0",    text: "This program is free software; you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation; either version 2 of the License, or (at your option) any later version." },
+    LicenseTemplate { spdx: "GPL-3.0",    text: "This program is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version." },
+    LicenseTemplate { spdx: "LGPL-2.1",   text: "This library is free software; you can redistribute it and/or modify it under the terms of the GNU Lesser General Public License as published by the Free Software Foundation; either version 2.1 of the License, or (at your option) any later version." },
+    LicenseTemplate { spdx: "LGPL-3.0",   text: "This program is free software: you can redistribute it and/or modify it under the terms of the GNU Lesser General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version." },
+    LicenseTemplate { spdx: "AGPL-3.0",   text: "This program is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version." },
+    LicenseTemplate { spdx: "MPL-2.0",    text: "This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/." },
+];
+
+/** Matches below this similarity are not considered to be licenses at all. */
+pub const LICENSE_SIMILARITY_THRESHOLD: f64 = 0.5;
+
+/** The confidence assigned to licenses that are only mentioned by name (eg. in a README). */
+pub const LICENSE_MENTION_CONFIDENCE: f64 = 0.5;
+
+/** The confidence assigned to licenses declared by an `SPDX-License-Identifier` tag. */
+pub const LICENSE_SPDX_TAG_CONFIDENCE: f64 = 1.0;
+
+/** Source file headers are only expected near the top of a file. */
+const HEADER_LINES: usize = 40;
+
+/**
+ * Files that are dedicated to holding a license: `LICENSE`, `LICENCE`, `COPYING`, and their
+ * variants with extensions or suffixes (eg. `LICENSE.md`, `COPYING.LESSER`, `LICENSE-MIT`).
+ */
+pub fn is_license_file(location: &str) -> bool {
+    let file_name = location.rsplit('/').next().unwrap_or(location).to_lowercase();
+    file_name.starts_with("license") || file_name.starts_with("licence")
+        || file_name.starts_with("copying")
+}
+
+pub fn is_readme_file(location: &str) -> bool {
+    let file_name = location.rsplit('/').next().unwrap_or(location).to_lowercase();
+    file_name.starts_with("readme")
+}
+
+pub fn normalize(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.trim_start().to_lowercase().starts_with("copyright"))
+        .map(|line| line.to_lowercase())
+        .map(|line| line.chars().map(|c| if c.is_alphanumeric() { c } else { ' ' }).collect::<String>())
+        .flat_map(|line| line.split_whitespace().map(|word| word.to_owned()).collect::<Vec<String>>())
+        .map(|word| if word == "licence" { "license".to_owned() } else { word })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn trigrams(normalized: &str) -> BTreeSet<(&str, &str, &str)> {
+    let words: Vec<&str> = normalized.split(' ').filter(|word| !word.is_empty()).collect();
+    words.windows(3).map(|window| (window[0], window[1], window[2])).collect()
+}
+
+/** The fraction of the template's trigrams that are found in the text. */
+pub fn similarity(text: &str, template: &str) -> f64 {
+    let text = normalize(text);
+    let template = normalize(template);
+    let text_trigrams = trigrams(&text);
+    let template_trigrams = trigrams(&template);
+    if template_trigrams.is_empty() { return 0f64 }
+    let shared = template_trigrams.iter().filter(|trigram| text_trigrams.contains(trigram)).count();
+    shared as f64 / template_trigrams.len() as f64
+}
+
+/**
+ * Finds the bundled license that best matches the text, returning its SPDX identifier and the
+ * similarity score.
+ *
+ * Some licenses are contained in others (BSD-2-Clause in BSD-3-Clause, GPL notices in each
+ * other), so among near-equal scores the longer template wins: it explains more of the text.
+ */
+pub fn match_license(text: &str) -> Option<(String, f64)> {
+    match_against(text, LICENSE_TEMPLATES.iter())
+}
+
+fn match_against<'a, I>(text: &str, templates: I) -> Option<(String, f64)> where I: Iterator<Item=&'a LicenseTemplate> {
+    let text = normalize(text);
+    let text_trigrams = trigrams(&text);
+    if text_trigrams.is_empty() { return None }
+
+    let mut best: Option<(&'static str, f64, usize)> = None;
+    for template in templates {
+        let normalized_template = normalize(template.text);
+        let template_trigrams = trigrams(&normalized_template);
+        if template_trigrams.is_empty() { continue }
+        let shared = template_trigrams.iter().filter(|trigram| text_trigrams.contains(trigram)).count();
+        let score = shared as f64 / template_trigrams.len() as f64;
+        if score < LICENSE_SIMILARITY_THRESHOLD { continue }
+        let better = match best {
+            None => true,
+            Some((_, best_score, best_size)) if (score - best_score).abs() <= 0.02 =>
+                template_trigrams.len() > best_size,
+            Some((_, best_score, _)) => score > best_score,
+        };
+        if better { best = Some((template.spdx, score, template_trigrams.len())) }
+    }
+    best.map(|(spdx, score, _)| (spdx.to_owned(), score))
+}
+
+/** Finds an `SPDX-License-Identifier: <expression>` tag and returns the expression. */
+pub fn spdx_identifier(text: &str) -> Option<String> {
+    const TAG: &str = "SPDX-License-Identifier:";
+    let start = text.find(TAG)? + TAG.len();
+    let expression: String = text[start..].trim_start().chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || " .+-()".contains(*c))
+        .collect();
+    let expression = expression.trim();
+    if expression.is_empty() { None } else { Some(expression.to_owned()) }
+}
+
+/**
+ * Finds a license mentioned by name, as is common in READMEs ("Licensed under the MIT license",
+ * "released under the GPLv3"). Longer names are tried first so that eg. LGPL is not mistaken for
+ * GPL.
+ */
+pub fn mentioned_license(text: &str) -> Option<String> {
+    let text = normalize(text);
+    let mentions: [(&str, &str); 18] = [
+        ("gnu affero general public license",           "AGPL-3.0"),
+        ("agplv3",                                      "AGPL-3.0"),
+        ("gnu lesser general public license version 3", "LGPL-3.0"),
+        ("gnu lesser general public license",           "LGPL-2.1"),
+        ("lgplv3",                                      "LGPL-3.0"),
+        ("lgpl",                                        "LGPL-2.1"),
+        ("gnu general public license version 3",        "GPL-3.0"),
+        ("gnu general public license version 2",        "GPL-2.0"),
+        ("gplv3",                                       "GPL-3.0"),
+        ("gplv2",                                       "GPL-2.0"),
+        ("apache license version 2 0",                  "Apache-2.0"),
+        ("apache 2 0",                                  "Apache-2.0"),
+        ("mozilla public license",                      "MPL-2.0"),
+        ("bsd 3 clause",                                "BSD-3-Clause"),
+        ("bsd 2 clause",                                "BSD-2-Clause"),
+        ("mit license",                                 "MIT"),
+        ("isc license",                                 "ISC"),
+        ("unlicense",                                   "Unlicense"),
+    ];
+    let padded = format!(" {} ", text);
+    mentions.iter()
+        .find(|(phrase, _)| padded.contains(&format!(" {} ", phrase)))
+        .map(|(_, spdx)| spdx.to_string())
+}
+
+/**
+ * Detects the license declared in a source file's header: an SPDX tag, a standard license notice,
+ * or a whole license text among the first lines of the file.
+ */
+pub fn header_license(text: &str) -> Option<String> {
+    let header = text.lines().take(HEADER_LINES).collect::<Vec<&str>>().join("\n");
+    if let Some(expression) = spdx_identifier(&header) {
+        return Some(expression)
+    }
+    match_against(&header, LICENSE_NOTICES.iter().chain(LICENSE_TEMPLATES.iter()))
+        .map(|(spdx, _)| spdx)
+}
+
+/**
+ * Detects the license of a file that is either a dedicated license file or a README. Returns the
+ * SPDX identifier and the confidence of detection.
+ */
+pub fn detect_license(location: &str, text: &str) -> Option<(String, f64)> {
+    if let Some(expression) = spdx_identifier(text) {
+        return Some((expression, LICENSE_SPDX_TAG_CONFIDENCE))
+    }
+    if let Some(matched) = match_license(text) {
+        return Some(matched)
+    }
+    if is_readme_file(location) {
+        return mentioned_license(text).map(|spdx| (spdx, LICENSE_MENTION_CONFIDENCE))
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use crate::licenses::*;
+
+    #[test] fn test_match_reformatted_mit() {
+        let text = "Copyright (C) 2020 Somebody\n\n\
+                    Permission is hereby granted, free of charge, to any person obtaining a copy of \
+                    this software and associated documentation files (the \"Software\"), to deal in \
+                    the Software without restriction, including without limitation the rights to use, \
+                    copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the \
+                    Software, and to permit persons to whom the Software is furnished to do so, \
+                    subject to the following conditions: The above copyright notice and this \
+                    permission notice shall be included in all copies or substantial portions of the \
+                    Software. THE SOFTWARE IS PROVIDED \"AS IS\", WITHOUT WARRANTY OF ANY KIND, \
+                    EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF \
+                    MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO \
+                    EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR \
+                    OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING \
+                    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN \
+                    THE SOFTWARE.";
+        let (spdx, confidence) = match_license(text).unwrap();
+        assert_eq!(spdx, "MIT");
+        assert!(confidence > 0.9);
+    }
+
+    #[test] fn test_prefer_longer_bsd() {
+        let (spdx, _) = match_license(include_str!("licenses/BSD-3-Clause.txt")).unwrap();
+        assert_eq!(spdx, "BSD-3-Clause");
+        let (spdx, _) = match_license(include_str!("licenses/BSD-2-Clause.txt")).unwrap();
+        assert_eq!(spdx, "BSD-2-Clause");
+    }
+
+    #[test] fn test_header_license() {
+        let source = "// SPDX-License-Identifier: Apache-2.0\nfn main() {}\n";
+        assert_eq!(header_license(source), Some("Apache-2.0".to_owned()));
+        let source = "/*\n * This program is free software: you can redistribute it and/or modify\n \
+                      * it under the terms of the GNU General Public License as published by\n \
+                      * the Free Software Foundation, either version 3 of the License, or\n \
+                      * (at your option) any later version.\n */\nint main() {}\n";
+        assert_eq!(header_license(source), Some("GPL-3.0".to_owned()));
+        let source = "fn main() {}\n";
+        assert_eq!(header_license(source), None);
+        let readme = "# Project\n\nThis project is released under the MIT License.\n";
+        assert_eq!(detect_license("README.md", readme), Some(("MIT".to_owned(), LICENSE_MENTION_CONFIDENCE)));
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::objects::{Dependency, Ecosystem};
+
+/**
+ * Recognizing and parsing package manifests.
+ *
+ * Manifests are recognized by their file name (regardless of the directory they are in). Each
+ * manifest is parsed leniently: we only look for declared dependencies, and anything we do not
+ * understand is skipped rather than reported as an error. Parsing is syntactic, so dependencies
+ * declared through variables, computed in build scripts, or inherited from parent projects are not
+ * found.
+ */
+pub fn manifest_ecosystem(location: &str) -> Option<Ecosystem> {
+    let file_name = location.rsplit('/').next().unwrap_or(location);
+    match file_name {
+        "package.json"                          => Some(Ecosystem::NPM),
+        "Cargo.toml"                            => Some(Ecosystem::Cargo),
+        "pom.xml"                               => Some(Ecosystem::Maven),
+        "build.gradle" | "build.gradle.kts"     => Some(Ecosystem::Gradle),
+        "requirements.txt" | "setup.py"         => Some(Ecosystem::PyPI),
+        "go.mod"                                => Some(Ecosystem::Go),
+        "Gemfile"                               => Some(Ecosystem::RubyGems),
+        "composer.json"                         => Some(Ecosystem::Packagist),
+        _                                       => None,
+    }
+}
+
+pub fn is_manifest(location: &str) -> bool {
+    manifest_ecosystem(location).is_some()
+}
+
+pub fn parse_manifest(location: &str, contents: &str) -> Vec<Dependency> {
+    let file_name = location.rsplit('/').next().unwrap_or(location);
+    match file_name {
+        "package.json"                      => parse_package_json(contents),
+        "Cargo.toml"                        => parse_cargo_toml(contents),
+        "pom.xml"                           => parse_pom_xml(contents),
+        "build.gradle" | "build.gradle.kts" => parse_build_gradle(contents),
+        "requirements.txt"                  => parse_requirements_txt(contents),
+        "setup.py"                          => parse_setup_py(contents),
+        "go.mod"                            => parse_go_mod(contents),
+        "Gemfile"                           => parse_gemfile(contents),
+        "composer.json"                     => parse_composer_json(contents),
+        _                                   => vec![],
+    }
+}
+
+fn version_spec(version: &str) -> Option<String> {
+    let version = version.trim();
+    if version.is_empty() { None } else { Some(version.to_owned()) }
+}
+
+fn parse_json_dependency_objects(contents: &str, keys: &[&str], ecosystem: Ecosystem) -> Vec<Dependency> {
+    let json: Value = match serde_json::from_str(contents) {
+        Ok(json) => json,
+        Err(_) => return vec![],
+    };
+    keys.iter()
+        .flat_map(|key| json.get(key).and_then(|object| object.as_object()))
+        .flat_map(|object| object.iter())
+        .map(|(name, version)| {
+            Dependency::new(name.clone(), version.as_str().and_then(version_spec), ecosystem)
+        })
+        .collect()
+}
+
+pub fn parse_package_json(contents: &str) -> Vec<Dependency> {
+    parse_json_dependency_objects(contents,
+        &["dependencies", "devDependencies", "peerDependencies", "optionalDependencies"],
+        Ecosystem::NPM)
+}
+
+pub fn parse_composer_json(contents: &str) -> Vec<Dependency> {
+    // Platform requirements (PHP itself and its extensions) are not packages.
+    parse_json_dependency_objects(contents, &["require", "require-dev"], Ecosystem::Packagist)
+        .into_iter()
+        .filter(|dependency| dependency.name != "php" && !dependency.name.starts_with("ext-"))
+        .collect()
+}
+
+pub fn parse_cargo_toml(contents: &str) -> Vec<Dependency> {
+    let section_header = Regex::new(r"^\[\s*([^\]]+?)\s*\]$").unwrap();
+    let inline_version = Regex::new(r#"version\s*=\s*"([^"]*)""#).unwrap();
+
+    let mut dependencies: BTreeMap<String, Option<String>> = BTreeMap::new();
+    let mut in_dependency_table = false;      // [dependencies], [dev-dependencies], ...
+    let mut single_dependency: Option<String> = None; // [dependencies.serde]
+
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(captures) = section_header.captures(line) {
+            let section = captures.get(1).unwrap().as_str();
+            let (table, name) = match section.rfind("dependencies") {
+                Some(index) => (&section[..index + "dependencies".len()], section[index + "dependencies".len()..].trim_start_matches('.')),
+                None => ("", ""),
+            };
+            in_dependency_table = !table.is_empty() && name.is_empty();
+            single_dependency = if !table.is_empty() && !name.is_empty() {
+                dependencies.entry(name.to_owned()).or_insert(None);
+                Some(name.to_owned())
+            } else {
+                None
+            };
+            continue;
+        }
+        let (key, value) = match line.find('=') {
+            Some(index) => (line[..index].trim().trim_matches('"'), line[index + 1..].trim()),
+            None => continue,
+        };
+        if let Some(name) = &single_dependency {
+            if key == "version" {
+                dependencies.insert(name.clone(), version_spec(value.trim_matches('"')));
+            }
+        } else if in_dependency_table {
+            let version = if value.starts_with('{') {
+                inline_version.captures(value).and_then(|captures| version_spec(captures.get(1).unwrap().as_str()))
+            } else {
+                version_spec(value.trim_matches('"'))
+            };
+            dependencies.insert(key.to_owned(), version);
+        }
+    }
+
+    dependencies.into_iter()
+        .map(|(name, version)| Dependency::new(name, version, Ecosystem::Cargo))
+        .collect()
+}
+
+pub fn parse_pom_xml(contents: &str) -> Vec<Dependency> {
+    let dependency = Regex::new(r"(?s)<dependency>(.*?)</dependency>").unwrap();
+    let group_id = Regex::new(r"<groupId>\s*([^<]*?)\s*</groupId>").unwrap();
+    let artifact_id = Regex::new(r"<artifactId>\s*([^<]*?)\s*</artifactId>").unwrap();
+    let version = Regex::new(r"<version>\s*([^<]*?)\s*</version>").unwrap();
+
+    dependency.captures_iter(contents).filter_map(|captures| {
+        let block = captures.get(1).unwrap().as_str();
+        let artifact = artifact_id.captures(block)?.get(1).unwrap().as_str();
+        let name = match group_id.captures(block) {
+            Some(group) => format!("{}:{}", group.get(1).unwrap().as_str(), artifact),
+            None => artifact.to_owned(),
+        };
+        let version = version.captures(block).and_then(|captures| version_spec(captures.get(1).unwrap().as_str()));
+        Some(Dependency::new(name, version, Ecosystem::Maven))
+    }).collect()
+}
+
+pub fn parse_build_gradle(contents: &str) -> Vec<Dependency> {
+    let dependency = Regex::new(concat!(
+        r#"(?m)^\s*(?:implementation|api|compile|compileOnly|runtimeOnly|runtime|testImplementation|"#,
+        r#"testCompile|testRuntimeOnly|androidTestImplementation|kapt|annotationProcessor)"#,
+        r#"\s*\(?\s*['"]([^'":\s]+):([^'":\s]+)(?::([^'"\s]+))?['"]"#)).unwrap();
+
+    dependency.captures_iter(contents).map(|captures| {
+        let name = format!("{}:{}", captures.get(1).unwrap().as_str(), captures.get(2).unwrap().as_str());
+        let version = captures.get(3).and_then(|version| version_spec(version.as_str()));
+        Dependency::new(name, version, Ecosystem::Gradle)
+    }).collect()
+}
+
+fn parse_python_requirement(requirement: &str) -> Option<Dependency> {
+    let requirement = requirement.split(';').next().unwrap().trim(); // environment markers
+    if requirement.is_empty() || requirement.starts_with('-') || requirement.contains("://") {
+        return None;
+    }
+    let name_end = requirement.find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.'))
+        .unwrap_or(requirement.len());
+    let name = &requirement[..name_end];
+    if name.is_empty() {
+        return None;
+    }
+    let rest = requirement[name_end..].trim();
+    let rest = if rest.starts_with('[') { // extras
+        rest.find(']').map_or("", |index| &rest[index + 1..])
+    } else {
+        rest
+    };
+    Some(Dependency::new(name.to_owned(), version_spec(rest), Ecosystem::PyPI))
+}
+
+pub fn parse_requirements_txt(contents: &str) -> Vec<Dependency> {
+    contents.lines()
+        .map(|line| line.split(" #").next().unwrap())
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(parse_python_requirement)
+        .collect()
+}
+
+pub fn parse_setup_py(contents: &str) -> Vec<Dependency> {
+    let requirements = Regex::new(r"(?:install_requires|tests_require|setup_requires)\s*=\s*\[").unwrap();
+    requirements.find_iter(contents)
+        .flat_map(|list_start| quoted_strings_until_bracket(&contents[list_start.end()..]))
+        .flat_map(|requirement| parse_python_requirement(&requirement))
+        .collect()
+}
+
+// Strings in a Python list up to its closing bracket. Brackets inside strings (extras, as in
+// "requests[security]") do not close the list.
+fn quoted_strings_until_bracket(list: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut current: Option<(char, String)> = None;
+    for c in list.chars() {
+        match current.as_mut() {
+            Some((quote, _)) if c == *quote => strings.push(current.take().unwrap().1),
+            Some((_, string)) => string.push(c),
+            None if c == '"' || c == '\'' => current = Some((c, String::new())),
+            None if c == ']' => break,
+            None => {}
+        }
+    }
+    strings
+}
+
+pub fn parse_go_mod(contents: &str) -> Vec<Dependency> {
+    let mut dependencies = Vec::new();
+    let mut in_require_block = false;
+    for line in contents.lines() {
+        let line = line.split("//").next().unwrap().trim();
+        if in_require_block {
+            if line == ")" {
+                in_require_block = false;
+                continue;
+            }
+        } else if line.starts_with("require") {
+            let rest = line["require".len()..].trim();
+            if rest.starts_with('(') {
+                in_require_block = true;
+                continue;
+            }
+        } else {
+            continue;
+        }
+
+        let line = if line.starts_with("require") { line["require".len()..].trim() } else { line };
+        let mut parts = line.split_whitespace();
+        if let Some(name) = parts.next() {
+            dependencies.push(Dependency::new(name.to_owned(), parts.next().and_then(version_spec), Ecosystem::Go));
+        }
+    }
+    dependencies
+}
+
+pub fn parse_gemfile(contents: &str) -> Vec<Dependency> {
+    let gem = Regex::new(r#"(?m)^\s*gem\s+['"]([^'"]+)['"]((?:\s*,\s*['"][^'"]*['"])*)"#).unwrap();
+    let string = Regex::new(r#"['"]([^'"]*)['"]"#).unwrap();
+
+    gem.captures_iter(contents).map(|captures| {
+        let name = captures.get(1).unwrap().as_str().to_owned();
+        let version = captures.get(2).map(|versions| {
+            string.captures_iter(versions.as_str())
+                .map(|captures| captures.get(1).unwrap().as_str().to_owned())
+                .collect::<Vec<String>>()
+                .join(", ")
+        });
+        Dependency::new(name, version.as_ref().and_then(|version| version_spec(version)), Ecosystem::RubyGems)
+    }).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::manifests::*;
+
+    fn dependencies(list: Vec<Dependency>) -> Vec<(String, Option<String>)> {
+        list.into_iter().map(|dependency| (dependency.name, dependency.version)).collect()
+    }
+
+    #[test]
+    fn test_cargo_toml() {
+        let contents = r#"
+            [package]
+            name = "djanco"
+            version = "0.1.0"
+
+            [dependencies]
+            anyhow = "1.0.31"    # error handling
+            serde = { version = "1.0", features = ["derive"] }
+            parasite = { git = "https://github.com/PRL-PRG/codedj-parasite.git" }
+
+            [dependencies.itertools]
+            version = "0.10"
+        "#;
+
+        let expected_result = vec![
+            ("anyhow".to_owned(), Some("1.0.31".to_owned())),
+            ("itertools".to_owned(), Some("0.10".to_owned())),
+            ("parasite".to_owned(), None),
+            ("serde".to_owned(), Some("1.0".to_owned())),
+        ];
+
+        assert_eq!(expected_result, dependencies(parse_cargo_toml(contents)));
+    }
+
+    #[test]
+    fn test_requirements_txt() {
+        let contents = "# comment\nrequests>=2.0\nnumpy\n-r other.txt\nDjango[argon2]==3.1 ; python_version > '3'\n";
+
+        let expected_result = vec![
+            ("requests".to_owned(), Some(">=2.0".to_owned())),
+            ("numpy".to_owned(), None),
+            ("Django".to_owned(), Some("==3.1".to_owned())),
+        ];
+
+        assert_eq!(expected_result, dependencies(parse_requirements_txt(contents)));
+    }
+
+    #[test]
+    fn test_setup_py() {
+        let contents = r#"
+            setup(
+                name="example",
+                install_requires=["requests[security]>=2", 'numpy',
+                                  "futures; python_version < '3'"],
+                tests_require=['pytest'],
+            )
+        "#;
+
+        let expected_result = vec![
+            ("requests".to_owned(), Some(">=2".to_owned())),
+            ("numpy".to_owned(), None),
+            ("futures".to_owned(), None),
+            ("pytest".to_owned(), None),
+        ];
+
+        assert_eq!(expected_result, dependencies(parse_setup_py(contents)));
+    }
+
+    #[test]
+    fn test_go_mod() {
+        let contents = "module example.com/x\n\nrequire github.com/a/b v1.2.3\nrequire (\n\tgolang.org/x/text v0.3.0 // indirect\n)\n";
+
+        let expected_result = vec![
+            ("github.com/a/b".to_owned(), Some("v1.2.3".to_owned())),
+            ("golang.org/x/text".to_owned(), Some("v0.3.0".to_owned())),
+        ];
+
+        assert_eq!(expected_result, dependencies(parse_go_mod(contents)));
+    }
+}
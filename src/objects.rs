@@ -16,189 +16,11 @@ use crate::database::Database;
 use crate::time::Duration;
 use crate::iterators::*;
 use crate::weights_and_measures::Weighed;
+use crate::licenses;
+use crate::classification::{self, CommitRules};
 use crate::{Timestamp, Store, Percentage};
 
-#[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
-pub enum Language {
-    ASM, ASP, ActionScript, C, Cpp, CSharp, CoffeeScript, Lisp, Cobol, CSS, Clojure, D, Eiffel,
-    Elixir, Elm, Erlang, FSharp, Fortran, Go, Groovy, HTML, Haskell, Java, JavaScript, Julia,
-    Kotlin, Lua, ObjectiveC, OCaml, PHP, Pascal, Python, Perl, /*Prolog,*/ R, Racket, Ruby, Rust,
-    Scala, SQL, Scheme, Swift, TypeScript, VisualBasic,
-    // special category for languages we do not yet recognize and anything we do not know
-    Other
-}
-
-// HTML, CSS, Jupyter Notebook, Shell, Rich Text Format, Dart, R, Makefile, Vue, TeX, Vim script, Meson, Roff, CMake, Smarty, MATLAB, Elixir, Julia, F#,
-impl Language {
-    pub fn from_str(string: &str) -> Option<Self> {
-        match string.to_lowercase().as_str() {
-            "asm" | "assembly" => Some(Language::ASM),
-            "asp" | "classic asp" => Some(Language::ASP),
-            "actionscript" => Some(Language::ActionScript),
-            "c"   => Some(Language::C),
-            "c++" => Some(Language::Cpp),
-            "c#" => Some(Language::CSharp),
-            "coffeescript" => Some(Language::CoffeeScript),
-            "common lisp" | "lisp" => Some(Language::Lisp),
-            "cobol" => Some(Language::Cobol),
-            "css" => Some(Language::CSS),
-            "clojure" => Some(Language::Clojure),
-            "d" => Some(Language::D),
-            "eiffel" => Some(Language::Eiffel),
-            "elixir" => Some(Language::Elixir),
-            "elm" => Some(Language::Elm),
-            "erlang" => Some(Language::Erlang),
-            "f#" => Some(Language::FSharp),
-            "fortran" => Some(Language::Fortran),
-            "go" => Some(Language::Go),
-            "groovy" => Some(Language::Groovy),
-            "html" => Some(Language::HTML),
-            "haskell" => Some(Language::Haskell),
-            "java" => Some(Language::Java),
-            "javascript" => Some(Language::JavaScript),
-            "julia" => Some(Language::Julia),
-            "kotlin" => Some(Language::Kotlin),
-            "lua" => Some(Language::Lua),
-            "objective-c" | "objective c" | "objectivec" => Some(Language::ObjectiveC),
-            "ocaml" => Some(Language::OCaml),
-            "php" => Some(Language::PHP),
-            "pascal" => Some(Language::Pascal),
-            "python" => Some(Language::Python),
-            "perl" | "perl 6" | "perl6" => Some(Language::Perl),
-            //"prolog" => Some(Language::Prolog),
-            "r" => Some(Language::R),
-            "racket" => Some(Language::Racket),
-            "ruby" => Some(Language::Ruby),
-            "rust" => Some(Language::Rust),
-            "scala" => Some(Language::Scala),
-            "sql" | "sqlpl" => Some(Language::SQL),
-            "scheme" => Some(Language::Scheme),
-            "swift" => Some(Language::Swift),
-            "typescript" => Some(Language::TypeScript),
-            "visual basic" | "visual basic .net" => Some(Language::VisualBasic),
-            _ => None,
-        }
-    }
-
-    fn from_path(path: &str) -> Option<Self> {
-        std::path::Path::new(path).extension().map(|extension| {
-            extension.to_str().map(|extension| Language::from_extension(extension))
-        }).flatten().flatten()
-    }
-
-    /** Returns the language associated with given extension. 
-     
-        If the extension is *not* recognized, returns Language::Other. If the extension is recognized as not belonging to a source code file (say images, etc.) should return None. 
-
-        TODO implement the above?
-     */
-    fn from_extension(extension: &str) -> Option<Self> {
-        match extension {
-            "c" | "h"                                               => Some(Language::C),
-            "C" | "cc" | "cpp" | "cxx" | "c++" | "hpp"              => Some(Language::Cpp),
-            "m" | "mm" | "M"                                        => Some(Language::ObjectiveC),
-            "go"                                                    => Some(Language::Go),
-            "java"                                                  => Some(Language::Java),
-            "coffee" | "litcoffee"                                  => Some(Language::CoffeeScript),
-            "js" | "mjs"                                            => Some(Language::JavaScript),
-            "ts" | "tsx"                                            => Some(Language::TypeScript),
-            "rb"                                                    => Some(Language::Ruby),
-            "rs"                                                    => Some(Language::Rust),
-            "py" | "pyi" | "pyc" | "pyd" | "pyo" | "pyw" | "pyz"    => Some(Language::Python),
-            "plx" | "pl" | "pm" | "xs" | "t" | "pod"                => Some(Language::Perl),
-            "clj" | "cljs" | "cljc" | "edn"                         => Some(Language::Clojure),
-            "erl" | "hrl"                                           => Some(Language::Erlang),
-            "hs" | "lhs"                                            => Some(Language::Haskell),
-            "scala" | "sc"                                          => Some(Language::Scala),
-            "r" | "rscript"                                         => Some(Language::R),
-            "php" | "phtml" | "php3" | "php4" | "php5" |
-            "php7" | "phps" | "php-s" | "pht" | "phar"              => Some(Language::PHP),
-            "vb"                                                    => Some(Language::VisualBasic),
-            // also: bas, cls, ctl, ctx, dca, ddf,dep, dob, dox, dsr, dsx, dws, frm, frx, log, oca,
-            //       pag, pgx, res, tbl, vbg, vbl, vbp, vbr, vbw, vbz, wct, apparently
-            "swift"                                                 => Some(Language::Swift),
-            "scm" | "ss" | "sls" | "sps" | "sld"                    => Some(Language::Scheme),
-            "rkt"                                                   => Some(Language::Racket),
-            "sql" | "pls" | "pks"                                   => Some(Language::SQL),
-            //"pl" | "pro"                                            => Some(Language::Prolog), // conflict
-            "pp" | "pas" | "inc"                                    => Some(Language::Pascal),
-            "ml" | "mli"                                            => Some(Language::OCaml),
-            "lua"                                                   => Some(Language::Lua),
-            "kt" | "kts"                                            => Some(Language::Kotlin),
-            "jl"                                                    => Some(Language::Julia),
-            "html" | "htm"                                          => Some(Language::HTML),
-            "groovy" | "gvy" | "gy" | "gsh"                         => Some(Language::Groovy),
-            "f90" | "for" | "f"                                     => Some(Language::Fortran),
-            "fs" | "fsi"                                            => Some(Language::FSharp),
-            "elm"                                                   => Some(Language::Elm),
-            "ex" | "exs"                                            => Some(Language::Elixir),
-            "e"                                                     => Some(Language::Eiffel),
-            "d"                                                     => Some(Language::D),
-            "css"                                                   => Some(Language::CSS),
-            "cbl" | "cob" | "cpy"                                   => Some(Language::Cobol),
-            "lisp" | "lsp" | "l" | "cl" | "fasl"                    => Some(Language::Lisp),
-            "cs"                                                    => Some(Language::CSharp),
-            "as" | "swf"                                            => Some(Language::ActionScript),
-            "asp"                                                   => Some(Language::ASP),
-            "asm" | "s"                                             => Some(Language::ASM),
-            _                                                       => Some(Language::Other),
-        }
-    }
-}
-
-impl Display for Language {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let string = match self {
-            Language::ASM => "Assembly",
-            Language::C => "C",
-            Language::Cpp => "C++",
-            Language::ObjectiveC => "Objective-C",
-            Language::Go => "Go",
-            Language::Java => "Java",
-            Language::CoffeeScript => "CoffeeScript",
-            Language::JavaScript => "JavaScript",
-            Language::TypeScript => "TypeScript",
-            Language::Ruby => "Ruby",
-            Language::Rust => "Rust",
-            Language::PHP => "PHP",
-            Language::Python => "Python",
-            Language::Perl => "Perl",
-            Language::Clojure => "Clojure",
-            Language::Erlang => "Erlang",
-            Language::Haskell => "Haskell",
-            Language::Scala => "Scala",
-            //Language::Other(language) => language,
-            Language::ASP => "ASP",
-            Language::ActionScript => "ActionScript",
-            Language::CSharp => "C#",
-            Language::Lisp => "LISP",
-            Language::Cobol => "Cobol",
-            Language::CSS => "CSS",
-            Language::D => "D",
-            Language::Eiffel => "Eiffel",
-            Language::Elixir => "Elixir",
-            Language::Elm => "Elm",
-            Language::FSharp => "F#",
-            Language::Fortran => "Fortran",
-            Language::Groovy => "Groovy",
-            Language::HTML => "HTML",
-            Language::Julia => "Julia",
-            Language::Kotlin => "Kotlin",
-            Language::Lua => "Lua",
-            Language::OCaml => "OCaml",
-            Language::Pascal => "Pascal",
-            //Language::Prolog => "Prolog",
-            Language::R => "R",
-            Language::Racket => "Racket",
-            Language::SQL => "SQL",
-            Language::Scheme => "Scheme",
-            Language::Swift => "Swift",
-            Language::VisualBasic => "Visual Basic",
-            Language::Other => "Other"
-        };
-        f.write_str(string)
-    }
-}
+pub use crate::languages::{Language, LanguageKind};
 
 /**== Object IDs ================================================================================**/
 #[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Debug)] pub struct ProjectId(pub u64);
@@ -452,6 +274,7 @@ impl Project {
     pub fn is_abandoned      (&self, store: &Database)    -> Option<bool>                   { store.project_is_abandoned(&self.id)                    }
     pub fn project_locs      (&self, store: &Database)    -> Option<usize>                  { store.project_locs(&self.id)                    }
     pub fn duplicated_code      (&self, store: &Database)    -> Option<f64>                 { store.project_duplicated_code(&self.id)                 }
+    pub fn near_duplication_ratio(&self, store: &Database)   -> Option<f64>                 { store.project_near_duplication_ratio(&self.id)          }
     pub fn is_valid      (&self, store: &Database)    -> Option<bool>                       { store.project_is_valid(&self.id)                        }
     pub fn all_forks        (&self, store: &Database) -> Option<Vec<ProjectId>>             { store.project_all_forks(&self.id)               }
     pub fn all_forks_count  (&self, store: &Database) -> Option<usize>                      { store.project_all_forks_count(&self.id)         }
@@ -459,6 +282,44 @@ impl Project {
     pub fn max_user_lifetime  (&self, store: &Database) -> Option<i64>                      { store.project_max_user_lifetime(&self.id)     }
     pub fn max_h_index1       (&self, store: &Database) -> Option<u64>                      { store.project_max_h_index1(&self.id) }
     pub fn max_h_index2       (&self, store: &Database) -> Option<u64>                      { store.project_max_h_index2(&self.id) }
+    pub fn fork_network     (&self, store: &Database) -> Option<ForkNetwork>                { store.project_fork_network(&self.id)            }
+    pub fn team_structure   (&self, store: &Database) -> Option<TeamStructure>              { store.project_team_structure(&self.id)          }
+    pub fn dependencies     (&self, store: &Database) -> Option<Vec<Dependency>>            { store.project_dependencies(&self.id)            }
+    pub fn dependency_count (&self, store: &Database) -> Option<usize>                      { store.project_dependency_count(&self.id)        }
+    pub fn detected_license (&self, store: &Database) -> Option<DetectedLicense>            { store.project_detected_license(&self.id)        }
+    pub fn header_licenses  (&self, store: &Database) -> Option<Vec<(String, usize)>>       { store.project_header_licenses(&self.id)         }
+    pub fn header_license_count(&self, store: &Database) -> Option<usize>                   { self.header_licenses(store).map(|licenses| licenses.len()) }
+    pub fn tooling          (&self, store: &Database) -> Option<Vec<Tool>>                  { store.project_tooling(&self.id)                 }
+    pub fn bug_fix_ratio    (&self, store: &Database) -> Option<f64>                        { store.project_bug_fix_ratio(&self.id)           }
+    pub fn tooling_count    (&self, store: &Database) -> Option<usize>                      { store.project_tooling_count(&self.id)           }
+    pub fn tool_adoption    (&self, store: &Database) -> Option<Vec<(Tool, Timestamp)>>     { store.project_tool_adoption(&self.id)           }
+    pub fn tool_adoption_count(&self, store: &Database) -> Option<usize>                    { self.tool_adoption(store).map(|tools| tools.len()) }
+    pub fn ci_adopted_at    (&self, store: &Database) -> Option<Timestamp>                  {
+        self.tool_adoption(store)?.into_iter().find(|(tool, _)| tool.is_ci()).map(|(_, timestamp)| timestamp)
+    }
+    pub fn line_author_ids  (&self, store: &Database) -> Option<Vec<(UserId, usize)>>       { store.project_line_author_ids(&self.id)         }
+    pub fn authorship_shares(&self, store: &Database) -> Option<Vec<(User, f64)>>           { store.project_authorship_shares(&self.id)       }
+    pub fn line_author_count(&self, store: &Database) -> Option<usize>                      { self.line_author_ids(store).map(|authors| authors.len()) }
+    pub fn line_counts      (&self, store: &Database) -> Option<LineCounts>                 { store.project_line_counts(&self.id)             }
+    pub fn sloc             (&self, store: &Database) -> Option<usize>                      { self.line_counts(store).map(|counts| counts.code()) }
+    pub fn comment_density  (&self, store: &Database) -> Option<f64>                        { self.line_counts(store).map(|counts| counts.comment_density()).flatten() }
+    pub fn test_file_count  (&self, store: &Database) -> Option<usize>                      { store.project_test_code(&self.id).map(|(test_files, _, _)| test_files) }
+    pub fn test_loc_ratio   (&self, store: &Database) -> Option<f64>                        {
+        store.project_test_code(&self.id)
+            .filter(|(_, _, locs)| *locs > 0)
+            .map(|(_, test_locs, locs)| test_locs as f64 / locs as f64)
+    }
+    pub fn team_density     (&self, store: &Database) -> Option<f64>                        { self.team_structure(store).map(|team| team.density()) }
+    pub fn team_clustering  (&self, store: &Database) -> Option<f64>                        { self.team_structure(store).map(|team| team.clustering()) }
+    pub fn upstream_id      (&self, store: &Database) -> Option<ProjectId>                  { store.project_fork_upstream(&self.id)           }
+    pub fn upstream         (&self, store: &Database) -> Option<Project>                    { self.upstream_id(store).map(|id| store.project(&id)).flatten() }
+    pub fn is_upstream      (&self, store: &Database) -> Option<bool>                       { self.upstream_id(store).map(|id| id == self.id) }
+    pub fn fork_network_size(&self, store: &Database) -> Option<usize>                      { self.fork_network(store).map(|network| network.size()) }
+    pub fn fork_details     (&self, store: &Database) -> Option<Fork>                       { self.fork_network(store).map(|network| network.fork(&self.id)).flatten() }
+    pub fn divergence_commit_id(&self, store: &Database) -> Option<CommitId>                { self.fork_details(store).map(|fork| fork.divergence_commit_id()).flatten() }
+    pub fn divergence_commit(&self, store: &Database) -> Option<Commit>                     { self.divergence_commit_id(store).map(|id| store.commit(&id)).flatten() }
+    pub fn unique_commit_count(&self, store: &Database) -> Option<usize>                    { self.fork_details(store).map(|fork| fork.unique_commit_count()) }
+    pub fn contributed_back (&self, store: &Database) -> Option<bool>                       { self.fork_details(store).map(|fork| fork.contributed_back()) }
     pub fn head_trees   (&self, store: &Database) -> Option<Vec<(String, Vec<(PathId, SnapshotId)>)>> {
         store.project_head_trees(&self.id)
     }    
@@ -533,17 +394,33 @@ impl From<(String, CommitId, String)> for Head {
 /** Contains the path id and snapshot id of the change.
  
     If the file has been deleted as part of the change, snapshot is None. Otherwise snapshot is the snapshot id. 
+
+    Changes obtained from a commit also know which commit made them, which is needed to find the
+    previous version of the file (eg. for `diff`). Changes in a `Tree` do not.
  */
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Change {
     pub(crate) path: PathId,
     //pub(crate) hash: u64, // TODO could change into HeadId
     pub(crate) snapshot: Option<SnapshotId>,
+    pub(crate) commit: Option<CommitId>,
 }
 
 impl Change {
     pub fn new(path: PathId, /*hash: u64,*/ snapshot: Option<SnapshotId>) -> Self {
-        Change { path, snapshot }
+        Change { path, snapshot, commit: None }
+    }
+    pub fn in_commit(commit: CommitId, path: PathId, snapshot: Option<SnapshotId>) -> Self {
+        Change { path, snapshot, commit: Some(commit) }
+    }
+    pub fn commit_id(&self) -> Option<CommitId> {
+        self.commit
+    }
+    /** Diff against the version of the path in the (first) parent of the commit that made the change.
+        A file created by the change is diffed against an empty file, and a deleted file is diffed
+        against an empty file in its place. */
+    pub fn diff(&self, store: &Database) -> Option<Diff> {
+        store.change_diff(&self.commit?, &self.path, self.snapshot)
     }
     pub fn snapshot_id(&self) -> Option<SnapshotId> {
         self.snapshot.clone()
@@ -555,6 +432,28 @@ impl Change {
     pub fn path(&self, store: &Database) -> Option<Path> { store.path(&self.path) }
 }
 
+/** One line of a file, as returned by `Database::blame`: its number (counting from 0), its contents,
+    and the commit that last changed it together with that commit's author. */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlamedLine {
+    pub(crate) line: usize,
+    pub(crate) commit: CommitId,
+    pub(crate) author: UserId,
+    pub(crate) contents: String,
+}
+
+impl BlamedLine {
+    pub fn new(line: usize, commit: CommitId, author: UserId, contents: String) -> Self {
+        BlamedLine { line, commit, author, contents }
+    }
+    pub fn line(&self) -> usize { self.line }
+    pub fn commit_id(&self) -> CommitId { self.commit }
+    pub fn author_id(&self) -> UserId { self.author }
+    pub fn contents(&self) -> &str { self.contents.as_str() }
+    pub fn commit(&self, store: &Database) -> Option<Commit> { store.commit(&self.commit) }
+    pub fn author(&self, store: &Database) -> Option<User> { store.user(&self.author) }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct User { pub(crate) id: UserId, /*pub(crate) name: String,*/ pub(crate) email: String }
 impl User {
@@ -575,6 +474,22 @@ impl User {
     pub fn h_index2               (&self, store: &Database)   -> Option<u64>           { store.user_h_index2(&self.id)  }
     pub fn project_ids           (&self, store: &Database)   -> Option<Vec<ProjectId>> { store.user_project_ids(&self.id) }
     pub fn project_ids_count     (&self, store: &Database)   -> Option<usize>         { store.user_project_ids_count(&self.id) }
+
+    pub fn collaborator_ids      (&self, store: &Database)   -> Option<Vec<UserId>>   { store.user_collaborator_ids(&self.id) }
+    pub fn collaborators         (&self, store: &Database)   -> Option<Vec<User>>     { store.user_collaborators(&self.id) }
+    pub fn collaborator_count    (&self, store: &Database)   -> Option<usize>         { store.user_collaborator_count(&self.id) }
+    pub fn centrality            (&self, store: &Database, measure: CentralityMeasure) -> Option<f64> { store.user_centrality(&self.id, measure) }
+    pub fn surviving_lines       (&self, store: &Database)   -> Option<usize>         { store.user_surviving_lines(&self.id) }
+
+    pub fn collaborator_ids_between(&self, store: &Database, since: Timestamp, until: Timestamp) -> Option<Vec<UserId>> { 
+        store.user_collaborator_ids_between(&self.id, since, until) 
+    }
+    pub fn collaborators_between(&self, store: &Database, since: Timestamp, until: Timestamp) -> Option<Vec<User>> { 
+        self.collaborator_ids_between(store, since, until).map(|ids| ids.reify(store)) 
+    }
+    pub fn collaborator_count_between(&self, store: &Database, since: Timestamp, until: Timestamp) -> Option<usize> { 
+        self.collaborator_ids_between(store, since, until).map(|ids| ids.len()) 
+    }
 }
 impl Identifiable for User {
     type Identity = UserId;
@@ -625,6 +540,19 @@ impl Commit {
     pub fn hash               (&self, store: &Database) -> Option<String>                     {  store.commit_hash(&self.id)                        }
     pub fn message            (&self, store: &Database) -> Option<String>                     {  store.commit_message(&self.id)                     }
     pub fn message_length     (&self, store: &Database) -> Option<usize>                      {  self.message(store).map(|s| s.len()) }
+    pub fn category           (&self, store: &Database) -> Option<CommitCategory>              {  store.commit_category(&self.id)                    }
+    pub fn category_with      (&self, rules: &CommitRules, store: &Database) -> Option<CommitCategory> { self.message(store).map(|message| rules.classify(&message)) }
+    pub fn issue_refs         (&self, store: &Database) -> Option<Vec<String>>                 {  store.commit_issue_refs(&self.id)                  }
+    pub fn issue_ref_count    (&self, store: &Database) -> Option<usize>                      {  self.issue_refs(store).map(|refs| refs.len())      }
+    pub fn is_revert          (&self, store: &Database) -> Option<bool>                       {  self.category(store).map(|category| category == CommitCategory::Revert) }
+    pub fn is_bug_fix         (&self, store: &Database) -> Option<bool>                       {
+        let issue_refs = self.issue_refs(store).unwrap_or_default();
+        self.category(store).map(|category| classification::is_bug_fix(category, &issue_refs))
+    }
+    pub fn introduced_bug_fix_ids(&self, store: &Database) -> Vec<CommitId>                  {  store.commit_introduced_bug_fix_ids(&self.id)      }
+    pub fn introduced_bug_fixes(&self, store: &Database) -> Vec<Commit>                      {  self.introduced_bug_fix_ids(store).reify(store)    }
+    pub fn introduced_bug_count(&self, store: &Database) -> usize                            {  self.introduced_bug_fix_ids(store).len()           }
+    pub fn is_bug_introducing (&self, store: &Database) -> bool                              {  self.introduced_bug_count(store) > 0               }
 
     pub fn author_timestamp   (&self, store: &Database) -> Option<Timestamp>                        {  store.commit_author_timestamp(&self.id)            }
     pub fn committer_timestamp(&self, store: &Database) -> Option<Timestamp>                        {  store.commit_committer_timestamp(&self.id)         }
@@ -666,6 +594,9 @@ impl Commit {
     pub fn languages_count(& self, store: &Database) -> Option<usize> {
         store.commit_languages_count(&self.id)
     }
+    pub fn touches_tests(&self, store: &Database) -> Option<bool> {
+        store.commit_touches_tests(&self.id)
+    }
     pub fn tree(&self, store: &Database) -> Tree {
         store.commit_trees(&self.id)
     }
@@ -697,7 +628,14 @@ pub struct Path { pub(crate) id: PathId, pub(crate) location: String }
 impl Path {
     pub fn new(id: PathId, location: String) -> Self { Path { id, location } }
     pub fn location(&self) -> String { self.location.clone() }
+    /** Language of the file as determined from its extension alone. */
     pub fn language(&self) -> Option<Language> { Language::from_path(self.location.as_str()) }
+    /** Language of the file as determined from its extension and, for files without an extension
+        or with ambiguous extensions, from its contents. */
+    pub fn detected_language(&self, store: &Database) -> Option<Language> { store.path_language(&self.id) }
+    /** Whether the file is test code, judging by its location and the conventions of its language
+        (see `crate::testcode`). */
+    pub fn is_test(&self, store: &Database) -> Option<bool> { store.path_is_test(&self.id) }
     pub fn location_as_file_path(&self) -> PathBuf {
         PathBuf::from(&self.location)
     }
@@ -738,6 +676,7 @@ impl Snapshot {
     pub fn contents(&self) -> Cow<str> { self.contents.to_str_lossy() }
     pub fn contents_owned(&self) -> String { self.contents.to_str_lossy().to_string() }
     pub fn contains(&self, needle: &str) -> bool { self.contents().contains(needle) }
+    pub fn license_header(&self) -> Option<String> { licenses::header_license(&self.contents()) }
     pub fn write_contents_to<'a, S>(&self, path: S) -> Result<(), std::io::Error> where S: Into<PathBuf> {
         let path = path.into();
         let dir = {
@@ -763,9 +702,33 @@ impl Snapshot {
     pub fn snapshot_locs(&self, store: &Database) -> Option<usize> { 
         store.snapshot_locs(&self.id)
     }
+    pub fn line_counts(&self, store: &Database) -> Option<LineCounts> {
+        store.snapshot_line_counts(&self.id)
+    }
+    pub fn code_lines(&self, store: &Database) -> Option<usize> {
+        self.line_counts(store).map(|counts| counts.code())
+    }
+    pub fn comment_lines(&self, store: &Database) -> Option<usize> {
+        self.line_counts(store).map(|counts| counts.comment())
+    }
+    pub fn blank_lines(&self, store: &Database) -> Option<usize> {
+        self.line_counts(store).map(|counts| counts.blank())
+    }
     pub fn snapshot_has_contents(&self, store: &Database) -> bool {
         store.snapshot_has_contents(&self.id)
     }
+    pub fn near_duplicate_ids(&self, store: &Database) -> Vec<SnapshotId> {
+        store.snapshot_near_duplicate_ids(&self.id)
+    }
+    pub fn near_duplicates(&self, store: &Database) -> Vec<Snapshot> {
+        self.near_duplicate_ids(store).reify(store)
+    }
+    pub fn near_duplicate_count(&self, store: &Database) -> usize {
+        self.near_duplicate_ids(store).len()
+    }
+    pub fn clone_class(&self, store: &Database) -> Option<SnapshotId> {
+        store.snapshot_clone_class(&self.id)
+    }
 }
 impl Identifiable for Snapshot {
     type Identity = SnapshotId;
@@ -811,7 +774,7 @@ impl Tree {
     // }
     pub fn snapshot_count(&self) -> usize { self.files.values().count() }
     pub fn changes(&self) -> Vec<Change> { 
-        self.files.iter().map(|(path_id, snapshot_id)| Change { path: *path_id, snapshot: *snapshot_id }).collect()
+        self.files.iter().map(|(path_id, snapshot_id)| Change { path: *path_id, snapshot: *snapshot_id, commit: None }).collect()
     }
     // pub fn changes_with_data<'a>(&self, store: &'a Database) -> Vec<ItemWithData<'a, Change>> { 
     //     self.changes().attach_data_to_each(store)
@@ -865,6 +828,442 @@ impl<'a> ItemWithData<'a, Tree> {
     }
 }
 
+/** Package ecosystem a dependency comes from, identified by the manifest that declares it. */
+#[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub enum Ecosystem { NPM, Cargo, Maven, Gradle, PyPI, Go, RubyGems, Packagist }
+
+impl Display for Ecosystem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let string = match self {
+            Ecosystem::NPM => "npm",
+            Ecosystem::Cargo => "cargo",
+            Ecosystem::Maven => "maven",
+            Ecosystem::Gradle => "gradle",
+            Ecosystem::PyPI => "pypi",
+            Ecosystem::Go => "go",
+            Ecosystem::RubyGems => "rubygems",
+            Ecosystem::Packagist => "packagist",
+        };
+        f.write_str(string)
+    }
+}
+
+/** Category of a commit, as determined from its message (see `crate::classification`). */
+#[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub enum CommitCategory {
+    BugFix, Feature, Refactor, Documentation, Test, Build, CI, Style, Performance, Chore, Revert,
+    Merge, Other,
+}
+
+impl Display for CommitCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let string = match self {
+            CommitCategory::BugFix => "bugfix",
+            CommitCategory::Feature => "feature",
+            CommitCategory::Refactor => "refactor",
+            CommitCategory::Documentation => "documentation",
+            CommitCategory::Test => "test",
+            CommitCategory::Build => "build",
+            CommitCategory::CI => "ci",
+            CommitCategory::Style => "style",
+            CommitCategory::Performance => "performance",
+            CommitCategory::Chore => "chore",
+            CommitCategory::Revert => "revert",
+            CommitCategory::Merge => "merge",
+            CommitCategory::Other => "other",
+        };
+        f.write_str(string)
+    }
+}
+
+/** Category of a development tool: continuous integration, build system, linter or formatter,
+    or containerization. */
+#[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub enum ToolKind { CI, Build, Linter, Container }
+
+impl Display for ToolKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let string = match self {
+            ToolKind::CI => "ci",
+            ToolKind::Build => "build",
+            ToolKind::Linter => "linter",
+            ToolKind::Container => "container",
+        };
+        f.write_str(string)
+    }
+}
+
+/** A development tool used by a project, identified by its configuration file (see
+    `crate::tooling`). */
+#[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub enum Tool {
+    GitHubActions, TravisCI, Jenkins, GitLabCI, CircleCI, AppVeyor, AzurePipelines,
+    Make, CMake, Maven, Gradle, NPM, Cargo, Bazel,
+    ESLint, Prettier, Pylint, Flake8, RuboCop, Checkstyle, Clippy, Rustfmt, GolangCILint, PreCommit,
+    Docker, DockerCompose,
+}
+
+impl Tool {
+    pub fn kind(&self) -> ToolKind {
+        match self {
+            Tool::GitHubActions | Tool::TravisCI | Tool::Jenkins | Tool::GitLabCI | Tool::CircleCI
+            | Tool::AppVeyor | Tool::AzurePipelines => ToolKind::CI,
+            Tool::Make | Tool::CMake | Tool::Maven | Tool::Gradle | Tool::NPM | Tool::Cargo
+            | Tool::Bazel => ToolKind::Build,
+            Tool::ESLint | Tool::Prettier | Tool::Pylint | Tool::Flake8 | Tool::RuboCop
+            | Tool::Checkstyle | Tool::Clippy | Tool::Rustfmt | Tool::GolangCILint
+            | Tool::PreCommit => ToolKind::Linter,
+            Tool::Docker | Tool::DockerCompose => ToolKind::Container,
+        }
+    }
+    pub fn is_ci(&self) -> bool { self.kind() == ToolKind::CI }
+}
+
+impl Display for Tool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let string = match self {
+            Tool::GitHubActions => "github-actions",
+            Tool::TravisCI => "travis-ci",
+            Tool::Jenkins => "jenkins",
+            Tool::GitLabCI => "gitlab-ci",
+            Tool::CircleCI => "circleci",
+            Tool::AppVeyor => "appveyor",
+            Tool::AzurePipelines => "azure-pipelines",
+            Tool::Make => "make",
+            Tool::CMake => "cmake",
+            Tool::Maven => "maven",
+            Tool::Gradle => "gradle",
+            Tool::NPM => "npm",
+            Tool::Cargo => "cargo",
+            Tool::Bazel => "bazel",
+            Tool::ESLint => "eslint",
+            Tool::Prettier => "prettier",
+            Tool::Pylint => "pylint",
+            Tool::Flake8 => "flake8",
+            Tool::RuboCop => "rubocop",
+            Tool::Checkstyle => "checkstyle",
+            Tool::Clippy => "clippy",
+            Tool::Rustfmt => "rustfmt",
+            Tool::GolangCILint => "golangci-lint",
+            Tool::PreCommit => "pre-commit",
+            Tool::Docker => "docker",
+            Tool::DockerCompose => "docker-compose",
+        };
+        f.write_str(string)
+    }
+}
+
+/** A dependency declared in a package manifest. 
+
+    The version is the version specification as written in the manifest (eg. `^1.2.0` or `>=2.0`),
+    or None if the manifest does not constrain the version.
+ */
+#[derive(Clone, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub struct Dependency {
+    pub(crate) name: String,
+    pub(crate) version: Option<String>,
+    pub(crate) ecosystem: Ecosystem,
+}
+
+impl Dependency {
+    pub fn new(name: String, version: Option<String>, ecosystem: Ecosystem) -> Self {
+        Dependency { name, version, ecosystem }
+    }
+    pub fn name(&self) -> String { self.name.clone() }
+    pub fn version(&self) -> Option<String> { self.version.clone() }
+    pub fn ecosystem(&self) -> Ecosystem { self.ecosystem }
+}
+
+impl Display for Dependency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}:{}@{}", self.ecosystem, self.name, version),
+            None => write!(f, "{}:{}", self.ecosystem, self.name),
+        }
+    }
+}
+
+/** A license detected from the contents of a file in the project's default-branch tree.
+
+    The license is identified by its SPDX identifier (or SPDX expression, if the license was 
+    declared by an `SPDX-License-Identifier` tag). Confidence ranges from 0 to 1: it is the 
+    similarity to the bundled license text, 1 for SPDX tags, and 0.5 for licenses that are only 
+    mentioned by name in a README.
+ */
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct DetectedLicense {
+    pub(crate) spdx: String,
+    pub(crate) confidence: f64,
+    pub(crate) path: PathId,
+}
+
+impl DetectedLicense {
+    pub fn new(spdx: String, confidence: f64, path: PathId) -> Self {
+        DetectedLicense { spdx, confidence, path }
+    }
+    pub fn spdx(&self) -> String { self.spdx.clone() }
+    pub fn confidence(&self) -> f64 { self.confidence }
+    pub fn path_id(&self) -> PathId { self.path }
+    pub fn path(&self, store: &Database) -> Option<Path> { store.path(&self.path) }
+}
+
+impl Display for DetectedLicense {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:.2})", self.spdx, self.confidence)
+    }
+}
+
+/** Numbers of code, comment, and blank lines in a file or a project.
+
+    A line is blank if it contains only whitespace and a comment line if all its contents are
+    inside comments. All other lines, including those with code followed by a comment, are code
+    lines. Comments are recognized using the comment syntax of the file's language (see
+    `crate::sloc`).
+ */
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct LineCounts {
+    pub(crate) code: usize,
+    pub(crate) comment: usize,
+    pub(crate) blank: usize,
+}
+
+impl LineCounts {
+    pub fn new(code: usize, comment: usize, blank: usize) -> Self {
+        LineCounts { code, comment, blank }
+    }
+    pub fn code(&self) -> usize { self.code }
+    pub fn comment(&self) -> usize { self.comment }
+    pub fn blank(&self) -> usize { self.blank }
+    pub fn total(&self) -> usize { self.code + self.comment + self.blank }
+    /** Ratio of comment lines to non-blank lines, or `None` if there are no non-blank lines. */
+    pub fn comment_density(&self) -> Option<f64> {
+        match self.code + self.comment {
+            0 => None,
+            lines => Some(self.comment as f64 / lines as f64),
+        }
+    }
+}
+
+impl std::ops::Add for LineCounts {
+    type Output = LineCounts;
+    fn add(self, other: LineCounts) -> LineCounts {
+        LineCounts::new(self.code + other.code, self.comment + other.comment, self.blank + other.blank)
+    }
+}
+
+impl Display for LineCounts {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} code, {} comment, {} blank", self.code, self.comment, self.blank)
+    }
+}
+
+/** A line of a hunk of a diff: either present in both versions, or only in the new one, or only in
+    the old one. */
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum DiffLine {
+    Context(String),
+    Added(String),
+    Removed(String),
+}
+
+impl DiffLine {
+    pub fn text(&self) -> &str {
+        match self { DiffLine::Context(text) | DiffLine::Added(text) | DiffLine::Removed(text) => text.as_str() }
+    }
+}
+
+impl Display for DiffLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffLine::Context(text) => write!(f, " {}", text),
+            DiffLine::Added(text) => write!(f, "+{}", text),
+            DiffLine::Removed(text) => write!(f, "-{}", text),
+        }
+    }
+}
+
+/** A hunk of a unified diff: a run of changed lines with some unchanged lines around them. Line
+    numbers count from 1, as in `diff -u`; the start of an empty range is the line before it. */
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Hunk {
+    pub(crate) old_start: usize,
+    pub(crate) old_lines: usize,
+    pub(crate) new_start: usize,
+    pub(crate) new_lines: usize,
+    pub(crate) lines: Vec<DiffLine>,
+}
+
+impl Hunk {
+    pub fn old_start(&self) -> usize { self.old_start }
+    pub fn old_lines(&self) -> usize { self.old_lines }
+    pub fn new_start(&self) -> usize { self.new_start }
+    pub fn new_lines(&self) -> usize { self.new_lines }
+    pub fn lines(&self) -> &Vec<DiffLine> { &self.lines }
+    pub fn header(&self) -> String {
+        format!("@@ -{},{} +{},{} @@", self.old_start, self.old_lines, self.new_start, self.new_lines)
+    }
+}
+
+/** A line-based diff between two versions of a file (see `crate::diff`).
+
+    The similarity is the fraction of lines the two versions have in common: twice the number of
+    unchanged lines divided by the total number of lines in both versions. Two empty files are
+    identical. */
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Diff {
+    pub(crate) hunks: Vec<Hunk>,
+    pub(crate) added: usize,
+    pub(crate) removed: usize,
+    pub(crate) similarity: f64,
+}
+
+impl Diff {
+    pub fn hunks(&self) -> &Vec<Hunk> { &self.hunks }
+    pub fn hunk_count(&self) -> usize { self.hunks.len() }
+    pub fn added(&self) -> usize { self.added }
+    pub fn removed(&self) -> usize { self.removed }
+    pub fn churn(&self) -> usize { self.added + self.removed }
+    pub fn similarity(&self) -> f64 { self.similarity }
+    pub fn is_empty(&self) -> bool { self.hunks.is_empty() }
+    /** The diff in the unified format, with file headers naming the old and new versions. */
+    pub fn to_unified(&self, old_name: &str, new_name: &str) -> String {
+        format!("--- {}\n+++ {}\n{}", old_name, new_name, self)
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for hunk in self.hunks.iter() {
+            writeln!(f, "{}", hunk.header())?;
+            for line in hunk.lines.iter() {
+                writeln!(f, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/** Measures of collaboration within a project's team.
+
+    Authors of the project form a graph in which two authors are connected if they modified the 
+    same path. Density is the ratio of existing connections to all possible connections. Clustering
+    is the average local clustering coefficient of the authors.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TeamStructure {
+    pub(crate) size: usize,
+    pub(crate) collaborations: usize,
+    pub(crate) density: f64,
+    pub(crate) clustering: f64,
+}
+
+impl TeamStructure {
+    pub fn new(size: usize, collaborations: usize, density: f64, clustering: f64) -> Self {
+        TeamStructure { size, collaborations, density, clustering }
+    }
+    pub fn size(&self) -> usize { self.size }
+    pub fn collaboration_count(&self) -> usize { self.collaborations }
+    pub fn density(&self) -> f64 { self.density }
+    pub fn clustering(&self) -> f64 { self.clustering }
+}
+
+/** Centrality measures of a user in the collaboration network. */
+#[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub enum CentralityMeasure {
+    /// Number of collaborators divided by the number of other users in the network.
+    Degree,
+    /// PageRank of the user in the collaboration network.
+    PageRank,
+}
+
+/** A group of projects that share commits.
+
+    The upstream is the oldest project in the network, every other project is considered its fork.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ForkNetwork {
+    pub(crate) upstream: ProjectId,
+    pub(crate) forks: Vec<Fork>,
+}
+
+impl ForkNetwork {
+    pub fn new(upstream: ProjectId, forks: Vec<Fork>) -> Self { ForkNetwork { upstream, forks } }
+    pub fn upstream_id(&self) -> ProjectId { self.upstream.clone() }
+    pub fn upstream(&self, store: &Database) -> Option<Project> { store.project(&self.upstream) }
+    pub fn forks(&self) -> Vec<Fork> { self.forks.clone() }
+    pub fn fork(&self, id: &ProjectId) -> Option<Fork> { self.forks.iter().find(|fork| fork.project == *id).map(|fork| fork.clone()) }
+    pub fn fork_ids(&self) -> Vec<ProjectId> { self.forks.iter().map(|fork| fork.project).collect() }
+    pub fn fork_projects(&self, store: &Database) -> Vec<Project> { self.fork_ids().into_iter().flat_map(|id| store.project(&id)).collect() }
+    pub fn fork_count(&self) -> usize { self.forks.len() }
+    pub fn project_ids(&self) -> Vec<ProjectId> { 
+        std::iter::once(self.upstream).chain(self.forks.iter().map(|fork| fork.project)).collect() 
+    }
+    pub fn size(&self) -> usize { self.forks.len() + 1 }
+}
+
+/** Describes how a single fork relates to the upstream of its fork network.
+
+    The divergence commit is the last commit the fork shares with the upstream before it starts 
+    adding commits of its own, where the fork's own commits are those not on the upstream's 
+    first-parent history. Unique commits are commits that are not found in the upstream.
+    `contributed_back` is a heuristic: it is true if the upstream contains a commit made after the 
+    divergence commit, authored by someone who also authored the fork's own commits after it.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Fork {
+    pub(crate) project: ProjectId,
+    pub(crate) divergence: Option<CommitId>,
+    pub(crate) unique_commits: usize,
+    pub(crate) contributed_back: bool,
+}
+
+impl Fork {
+    pub fn new(project: ProjectId, divergence: Option<CommitId>, unique_commits: usize, contributed_back: bool) -> Self {
+        Fork { project, divergence, unique_commits, contributed_back }
+    }
+    pub fn project_id(&self) -> ProjectId { self.project.clone() }
+    pub fn project(&self, store: &Database) -> Option<Project> { store.project(&self.project) }
+    pub fn divergence_commit_id(&self) -> Option<CommitId> { self.divergence.clone() }
+    pub fn divergence_commit(&self, store: &Database) -> Option<Commit> { self.divergence.map(|id| store.commit(&id)).flatten() }
+    pub fn unique_commit_count(&self) -> usize { self.unique_commits }
+    pub fn contributed_back(&self) -> bool { self.contributed_back }
+}
+
+impl<'a> ItemWithData<'a, ForkNetwork> {
+    pub fn upstream_id(&self) -> ProjectId { self.item.upstream_id() }
+    pub fn upstream(&self) -> Option<Project> { self.item.upstream(&self.data) }
+    pub fn upstream_with_data<'b>(&'b self) -> Option<ItemWithData<'a, Project>> {
+        self.item.upstream(&self.data).attach_data_to_inner(self.data)
+    }
+    pub fn forks(&self) -> Vec<Fork> { self.item.forks() }
+    pub fn forks_with_data<'b>(&'b self) -> Vec<ItemWithData<'a, Fork>> {
+        self.item.forks().attach_data_to_each(self.data)
+    }
+    pub fn fork_ids(&self) -> Vec<ProjectId> { self.item.fork_ids() }
+    pub fn fork_projects(&self) -> Vec<Project> { self.item.fork_projects(&self.data) }
+    pub fn fork_projects_with_data<'b>(&'b self) -> Vec<ItemWithData<'a, Project>> {
+        self.item.fork_projects(&self.data).attach_data_to_each(self.data)
+    }
+    pub fn fork_count(&self) -> usize { self.item.fork_count() }
+    pub fn project_ids(&self) -> Vec<ProjectId> { self.item.project_ids() }
+    pub fn size(&self) -> usize { self.item.size() }
+}
+
+impl<'a> ItemWithData<'a, Fork> {
+    pub fn project_id(&self) -> ProjectId { self.item.project_id() }
+    pub fn project(&self) -> Option<Project> { self.item.project(&self.data) }
+    pub fn project_with_data<'b>(&'b self) -> Option<ItemWithData<'a, Project>> {
+        self.item.project(&self.data).attach_data_to_inner(self.data)
+    }
+    pub fn divergence_commit_id(&self) -> Option<CommitId> { self.item.divergence_commit_id() }
+    pub fn divergence_commit(&self) -> Option<Commit> { self.item.divergence_commit(&self.data) }
+    pub fn divergence_commit_with_data<'b>(&'b self) -> Option<ItemWithData<'a, Commit>> {
+        self.item.divergence_commit(&self.data).attach_data_to_inner(self.data)
+    }
+    pub fn unique_commit_count(&self) -> usize { self.item.unique_commit_count() }
+    pub fn contributed_back(&self) -> bool { self.item.contributed_back() }
+}
+
 pub trait ItemWithoutData where Self: Sized {
     fn attach_data<'a>(self, data: &'a Database) -> ItemWithData<'a, Self>;
 }
@@ -1018,6 +1417,7 @@ impl<'a> ItemWithData<'a, Project> {
     pub fn is_abandoned (&self)        -> Option<bool>                    { self.item.is_abandoned(&self.data) }
     pub fn project_locs (&self)        -> Option<usize>                   { self.item.project_locs(&self.data) }
     pub fn duplicated_code (&self)        -> Option<f64>                  { self.item.duplicated_code(&self.data) }
+    pub fn near_duplication_ratio(&self)  -> Option<f64>                  { self.item.near_duplication_ratio(&self.data) }
     pub fn substore   (&self)    -> Option<Store>                         { self.item.substore(&self.data)     }
     pub fn is_valid   (&self)    -> Option<bool>                          { self.item.is_valid(&self.data)     }
     pub fn project_max_experience   (&self)    -> Option<i32>             { self.item.project_max_experience(&self.data)     }
@@ -1115,6 +1515,108 @@ impl<'a> ItemWithData<'a, Project> {
     pub fn all_forks_count(&self) -> Option<usize> {
         self.item.all_forks_count(&self.data)
     }
+    pub fn fork_network(&self) -> Option<ForkNetwork> {
+        self.item.fork_network(&self.data)
+    }
+    pub fn team_structure(&self) -> Option<TeamStructure> {
+        self.item.team_structure(&self.data)
+    }
+    pub fn dependencies(&self) -> Option<Vec<Dependency>> {
+        self.item.dependencies(&self.data)
+    }
+    pub fn dependency_count(&self) -> Option<usize> {
+        self.item.dependency_count(&self.data)
+    }
+    pub fn detected_license(&self) -> Option<DetectedLicense> {
+        self.item.detected_license(&self.data)
+    }
+    pub fn header_licenses(&self) -> Option<Vec<(String, usize)>> {
+        self.item.header_licenses(&self.data)
+    }
+    pub fn header_license_count(&self) -> Option<usize> {
+        self.item.header_license_count(&self.data)
+    }
+    pub fn tooling(&self) -> Option<Vec<Tool>> {
+        self.item.tooling(&self.data)
+    }
+    pub fn bug_fix_ratio(&self) -> Option<f64> {
+        self.item.bug_fix_ratio(&self.data)
+    }
+    pub fn tooling_count(&self) -> Option<usize> {
+        self.item.tooling_count(&self.data)
+    }
+    pub fn tool_adoption(&self) -> Option<Vec<(Tool, Timestamp)>> {
+        self.item.tool_adoption(&self.data)
+    }
+    pub fn tool_adoption_count(&self) -> Option<usize> {
+        self.item.tool_adoption_count(&self.data)
+    }
+    pub fn ci_adopted_at(&self) -> Option<Timestamp> {
+        self.item.ci_adopted_at(&self.data)
+    }
+    pub fn line_author_ids(&self) -> Option<Vec<(UserId, usize)>> {
+        self.item.line_author_ids(&self.data)
+    }
+    pub fn authorship_shares(&self) -> Option<Vec<(User, f64)>> {
+        self.item.authorship_shares(&self.data)
+    }
+    pub fn line_author_count(&self) -> Option<usize> {
+        self.item.line_author_count(&self.data)
+    }
+    pub fn line_counts(&self) -> Option<LineCounts> {
+        self.item.line_counts(&self.data)
+    }
+    pub fn sloc(&self) -> Option<usize> {
+        self.item.sloc(&self.data)
+    }
+    pub fn comment_density(&self) -> Option<f64> {
+        self.item.comment_density(&self.data)
+    }
+    pub fn test_file_count(&self) -> Option<usize> {
+        self.item.test_file_count(&self.data)
+    }
+    pub fn test_loc_ratio(&self) -> Option<f64> {
+        self.item.test_loc_ratio(&self.data)
+    }
+    pub fn team_density(&self) -> Option<f64> {
+        self.item.team_density(&self.data)
+    }
+    pub fn team_clustering(&self) -> Option<f64> {
+        self.item.team_clustering(&self.data)
+    }
+    pub fn fork_network_with_data<'b>(&'b self) -> Option<ItemWithData<'a, ForkNetwork>> {
+        self.item.fork_network(&self.data).attach_data_to_inner(self.data)
+    }
+    pub fn upstream_id(&self) -> Option<ProjectId> {
+        self.item.upstream_id(&self.data)
+    }
+    pub fn upstream(&self) -> Option<Project> {
+        self.item.upstream(&self.data)
+    }
+    pub fn upstream_with_data<'b>(&'b self) -> Option<ItemWithData<'a, Project>> {
+        self.item.upstream(&self.data).attach_data_to_inner(self.data)
+    }
+    pub fn is_upstream(&self) -> Option<bool> {
+        self.item.is_upstream(&self.data)
+    }
+    pub fn fork_network_size(&self) -> Option<usize> {
+        self.item.fork_network_size(&self.data)
+    }
+    pub fn divergence_commit_id(&self) -> Option<CommitId> {
+        self.item.divergence_commit_id(&self.data)
+    }
+    pub fn divergence_commit(&self) -> Option<Commit> {
+        self.item.divergence_commit(&self.data)
+    }
+    pub fn divergence_commit_with_data<'b>(&'b self) -> Option<ItemWithData<'a, Commit>> {
+        self.item.divergence_commit(&self.data).attach_data_to_inner(self.data)
+    }
+    pub fn unique_commit_count(&self) -> Option<usize> {
+        self.item.unique_commit_count(&self.data)
+    }
+    pub fn contributed_back(&self) -> Option<bool> {
+        self.item.contributed_back(&self.data)
+    }
     pub fn head_trees(&self) -> Option<Vec<(String, Vec<(PathId, SnapshotId)>)>> {
         self.item.head_trees(&self.data)
     }
@@ -1144,8 +1646,20 @@ impl<'a> ItemWithData<'a, Snapshot> {
     pub fn contents(&self) -> Cow<str> { self.item.contents() }
     pub fn contents_owned(&self) -> String { self.item.contents_owned() }
     pub fn contains(&self, needle: &str) -> bool { self.item.contains(needle) }
+    pub fn license_header(&self) -> Option<String> { self.item.license_header() }
     pub fn snapshot_locs (&self)        -> Option<usize>                    { self.item.snapshot_locs(&self.data) }
+    pub fn line_counts   (&self)        -> Option<LineCounts>               { self.item.line_counts(&self.data) }
+    pub fn code_lines    (&self)        -> Option<usize>                    { self.item.code_lines(&self.data) }
+    pub fn comment_lines (&self)        -> Option<usize>                    { self.item.comment_lines(&self.data) }
+    pub fn blank_lines   (&self)        -> Option<usize>                    { self.item.blank_lines(&self.data) }
     pub fn snapshot_has_contents(&self) -> bool { self.item.snapshot_has_contents(&self.data) }
+    pub fn near_duplicate_ids(&self) -> Vec<SnapshotId> { self.item.near_duplicate_ids(&self.data) }
+    pub fn near_duplicates(&self) -> Vec<Snapshot> { self.item.near_duplicates(&self.data) }
+    pub fn near_duplicate_count(&self) -> usize { self.item.near_duplicate_count(&self.data) }
+    pub fn clone_class(&self) -> Option<SnapshotId> { self.item.clone_class(&self.data) }
+    pub fn near_duplicates_with_data<'b>(&'b self) -> Vec<ItemWithData<'a, Snapshot>> {
+        self.item.near_duplicates(self.data).attach_data_to_each(self.data)
+    }
     pub fn unique_projects(&self) -> usize { self.item.unique_projects(&self.data) }
     pub fn original_project(&self) -> ProjectId { self.item.original_project(&self.data) }
 }
@@ -1168,6 +1682,25 @@ impl<'a> ItemWithData<'a, User> {
     pub fn h_index2               (&self)   -> Option<u64>           { self.item.h_index2(&self.data)                } 
     pub fn project_ids           (&self)   -> Option<Vec<ProjectId>> { self.item.project_ids(&self.data)           }
     pub fn project_ids_count     (&self)   -> Option<usize>         { self.item.project_ids_count(&self.data)      }
+    pub fn collaborator_ids      (&self)   -> Option<Vec<UserId>>   { self.item.collaborator_ids(&self.data)       }
+    pub fn collaborators         (&self)   -> Option<Vec<User>>     { self.item.collaborators(&self.data)          }
+    pub fn collaborator_count    (&self)   -> Option<usize>         { self.item.collaborator_count(&self.data)     }
+    pub fn centrality            (&self, measure: CentralityMeasure) -> Option<f64> { self.item.centrality(&self.data, measure) }
+    pub fn surviving_lines       (&self)   -> Option<usize>         { self.item.surviving_lines(&self.data)        }
+
+    pub fn collaborator_ids_between(&self, since: Timestamp, until: Timestamp) -> Option<Vec<UserId>> { 
+        self.item.collaborator_ids_between(&self.data, since, until) 
+    }
+    pub fn collaborator_count_between(&self, since: Timestamp, until: Timestamp) -> Option<usize> { 
+        self.item.collaborator_count_between(&self.data, since, until) 
+    }
+
+    pub fn collaborators_with_data<'b>(&'b self) -> Option<Vec<ItemWithData<'a, User>>> {
+        self.item.collaborators(&self.data).attach_data_to_each(self.data)
+    }
+    pub fn collaborators_between_with_data<'b>(&'b self, since: Timestamp, until: Timestamp) -> Option<Vec<ItemWithData<'a, User>>> {
+        self.item.collaborators_between(&self.data, since, until).attach_data_to_each(self.data)
+    }
 
     pub fn authored_commits_with_data<'b>(&'b self) -> Option<Vec<ItemWithData<'a, Commit>>> {
         self.item.authored_commits(&self.data).attach_data_to_each(self.data)
@@ -1190,6 +1723,19 @@ impl<'a> ItemWithData<'a, Commit> {
     pub fn hash               (&self) -> Option<String>                     { self.item.hash(&self.data)                 }
     pub fn message            (&self) -> Option<String>                     { self.item.message(&self.data)              }
     pub fn message_length     (&self) -> Option<usize>                      { self.item.message_length(&self.data)       }
+    pub fn category           (&self) -> Option<CommitCategory>             { self.item.category(&self.data)             }
+    pub fn category_with      (&self, rules: &CommitRules) -> Option<CommitCategory> { self.item.category_with(rules, &self.data) }
+    pub fn issue_refs         (&self) -> Option<Vec<String>>                { self.item.issue_refs(&self.data)           }
+    pub fn issue_ref_count    (&self) -> Option<usize>                      { self.item.issue_ref_count(&self.data)      }
+    pub fn is_revert          (&self) -> Option<bool>                       { self.item.is_revert(&self.data)            }
+    pub fn is_bug_fix         (&self) -> Option<bool>                       { self.item.is_bug_fix(&self.data)           }
+    pub fn introduced_bug_fix_ids(&self) -> Vec<CommitId>                   { self.item.introduced_bug_fix_ids(&self.data) }
+    pub fn introduced_bug_fixes(&self) -> Vec<Commit>                       { self.item.introduced_bug_fixes(&self.data) }
+    pub fn introduced_bug_count(&self) -> usize                             { self.item.introduced_bug_count(&self.data) }
+    pub fn is_bug_introducing (&self) -> bool                               { self.item.is_bug_introducing(&self.data)   }
+    pub fn introduced_bug_fixes_with_data<'b>(&'b self) -> Vec<ItemWithData<'a, Commit>> {
+        self.item.introduced_bug_fixes(self.data).attach_data_to_each(self.data)
+    }
     pub fn author_timestamp   (&self) -> Option<Timestamp>                  { self.item.author_timestamp(&self.data)     }
     pub fn committer_timestamp(&self) -> Option<Timestamp>                  { self.item.committer_timestamp(&self.data)  }
     pub fn changes            (&self) -> Option<Vec<Change>>                { self.item.changes(&self.data)              }
@@ -1232,6 +1778,7 @@ impl<'a> ItemWithData<'a, Commit> {
     pub fn projects_count(& self) -> Option<usize> { self.item.projects_count(& self.data) }
     pub fn languages(&self) -> Option<Vec<Language>> { self.item.languages(&self.data) }
     pub fn languages_count(& self) -> Option<usize> { self.item.languages_count(& self.data) }
+    pub fn touches_tests(&self) -> Option<bool> { self.item.touches_tests(&self.data) }
 
 
     pub fn preceding_commit_ids(&self) -> Vec<CommitId> {
@@ -1250,7 +1797,10 @@ impl<'a> ItemWithData<'a, Commit> {
 impl<'a> ItemWithData<'a, Path> {
     pub fn id      (&self) -> PathId           { self.item.id()       }
     pub fn location(&self) -> String           { self.item.location() }
-    pub fn language(&self) -> Option<Language> { self.item.language() }
+    pub fn language(&self) -> Option<Language> { self.item.detected_language(&self.data) }
+    pub fn extension_language(&self) -> Option<Language> { self.item.language() }
+    pub fn language_kind(&self) -> Option<LanguageKind> { self.language().and_then(|language| language.kind()) }
+    pub fn is_test (&self) -> Option<bool>     { self.item.is_test(&self.data) }
 }
 
 impl<'a> ItemWithData<'a, Head> {
@@ -1282,6 +1832,8 @@ impl<'a> ItemWithData<'a, Change> {
     pub fn snapshot_id(&self) -> Option<SnapshotId> { self.item.snapshot_id() }
     pub fn path(&self) -> Option<Path> { self.item.path(&self.data) }
     pub fn snapshot(&self) -> Option<Snapshot> { self.item.snapshot(&self.data) }
+    pub fn commit_id(&self) -> Option<CommitId> { self.item.commit_id() }
+    pub fn diff(&self) -> Option<Diff> { self.item.diff(&self.data) }
 
     pub fn path_with_data<'b> (&'b self) -> Option<ItemWithData<'a, Path>> {
         self.item.path(self.data).attach_data_to_inner(self.data)
@@ -1,8 +1,9 @@
 use std::cmp::Ordering;
 use std::f64::*;
 use std::hash::{Hash, Hasher};
+use std::fmt::{Display, Formatter};
 
-#[derive(PartialEq, PartialOrd, Copy, Clone)]
+#[derive(PartialEq, PartialOrd, Copy, Clone, Debug)]
 pub struct OrdF64(f64);
 
 impl OrdF64 {
@@ -12,6 +13,10 @@ impl OrdF64 {
 impl Into<f64> for OrdF64 { fn into(self)    -> f64  { self.0    } }
 impl From<f64> for OrdF64 { fn from(n: f64)  -> Self { OrdF64(n) } }
 
+impl Display for OrdF64 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { write!(f, "{}", self.0) }
+}
+
 impl Eq for OrdF64 {}
 
 impl Ord for OrdF64 {
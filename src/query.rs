@@ -0,0 +1,828 @@
+use std::cmp::{Ordering, Reverse};
+use std::fmt::{Display, Formatter};
+
+use anyhow::{anyhow, bail, Context, Result};
+use itertools::Itertools;
+use regex::Regex;
+
+use crate::attrib::Sampler;
+use crate::attrib::sort::Direction;
+use crate::attributes::{AttributeEntry, Entity, Queryable, Type, Value};
+use crate::csv::CSV;
+use crate::database::Database;
+use crate::objects::{self, ItemWithData};
+use crate::{Top, Random, Seed};
+
+/**
+ * A textual query language that mirrors the DSL, so that queries can be written and run without
+ * compiling them, eg.:
+ *
+ * ```text
+ * projects | filter AtLeast(Stars, 100) | sort Commits | sample Random(50, 42) | select Id, URL
+ * ```
+ *
+ * A query starts with the objects to query (`projects`, `commits`, `users`, or `paths`) followed by
+ * stages separated by `|`:
+ *
+ *  - `filter <condition>` (or `filter_by`) keeps objects that satisfy the condition: `AtLeast`,
+ *    `AtMost`, `LessThan`, `MoreThan`, `Equal` (an attribute and a literal), `Exists`, `Missing`
+ *    (an attribute), `Same`, `Contains`, `Matches` (a string attribute and a string), `And`, `Or`,
+ *    `Not` (conditions), or the name of a boolean attribute,
+ *  - `sort <attribute> [asc|desc]` (or `sort_by`) sorts objects, descending by default,
+ *  - `sample Top(n)` or `sample Random(n, seed)` selects some of the objects,
+ *  - `select <attribute>, ...` (or `map_into`) picks the columns of the result. It must be the last
+ *    stage; without it, the result contains the ids of the objects.
+ *
+ * Attributes are the ones in the `project::`, `commit::`, `user::` and `path::` modules, named
+ * either plainly (`Stars`) or with their module (`project::Stars`). `Count(attribute)` is the size
+ * of a collection attribute. Collections used where a single value is expected, ie. in comparisons
+ * and sorting, are counted implicitly, so `sort Commits` is `sort_by(Count(project::Commits))`.
+ *
+ * Queries are parsed by `Query::parse` and type-checked against the attribute registry (see
+ * `attributes::Queryable`) before they are executed by `Query::execute`.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    pub source: Entity,
+    pub stages: Vec<Stage>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Stage {
+    Filter(Condition),
+    Sort(Expression, Direction),
+    Sample(Sampling),
+    Select(Vec<Expression>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expression {
+    Attribute(String),
+    Count(Box<Expression>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison { LessThan, AtMost, Equal, AtLeast, MoreThan }
+
+impl Comparison {
+    fn from_name(name: &str) -> Option<Comparison> {
+        match name {
+            "LessThan" => Some(Comparison::LessThan),
+            "AtMost"   => Some(Comparison::AtMost),
+            "Equal"    => Some(Comparison::Equal),
+            "AtLeast"  => Some(Comparison::AtLeast),
+            "MoreThan" => Some(Comparison::MoreThan),
+            _          => None,
+        }
+    }
+    fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::LessThan => ordering == Ordering::Less,
+            Comparison::AtMost   => ordering != Ordering::Greater,
+            Comparison::Equal    => ordering == Ordering::Equal,
+            Comparison::AtLeast  => ordering != Ordering::Less,
+            Comparison::MoreThan => ordering == Ordering::Greater,
+        }
+    }
+    // Objects for which the attribute is missing are treated the same way as by the DSL's filters.
+    fn holds_for_missing(&self) -> bool {
+        match self {
+            Comparison::AtLeast | Comparison::MoreThan => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    Holds(Expression),
+    Compare(Comparison, Expression, Value),
+    Exists(Expression),
+    Missing(Expression),
+    Same(Expression, String),
+    Contains(Expression, String),
+    Matches(Expression, String),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sampling { Top(usize), Random(usize, u128) }
+
+impl Display for Query {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source.source())?;
+        for stage in self.stages.iter() {
+            write!(f, " | {}", stage)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stage::Filter(condition) => write!(f, "filter {}", condition),
+            Stage::Sort(expression, Direction::Descending) => write!(f, "sort {}", expression),
+            Stage::Sort(expression, Direction::Ascending) => write!(f, "sort {} asc", expression),
+            Stage::Sample(Sampling::Top(n)) => write!(f, "sample Top({})", n),
+            Stage::Sample(Sampling::Random(n, seed)) => write!(f, "sample Random({}, {})", n, seed),
+            Stage::Select(expressions) => write!(f, "select {}", expressions.iter().join(", ")),
+        }
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expression::Attribute(name) => write!(f, "{}", name),
+            Expression::Count(expression) => write!(f, "Count({})", expression),
+        }
+    }
+}
+
+fn write_literal(f: &mut Formatter<'_>, value: &Value) -> std::fmt::Result {
+    match value {
+        Value::String(string) => write_string(f, string),
+        value => write!(f, "{}", value),
+    }
+}
+
+// The inverse of how strings are tokenized: backslashes are only escaped where they would
+// otherwise be read as escapes.
+fn write_string(f: &mut Formatter<'_>, string: &str) -> std::fmt::Result {
+    let characters: Vec<char> = string.chars().collect();
+    write!(f, "\"")?;
+    for (index, character) in characters.iter().enumerate() {
+        match (character, characters.get(index + 1)) {
+            ('"', _) => write!(f, "\\\"")?,
+            ('\\', None) | ('\\', Some('"')) | ('\\', Some('\\')) => write!(f, "\\\\")?,
+            (character, _) => write!(f, "{}", character)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Holds(expression) => write!(f, "{}", expression),
+            Condition::Compare(comparison, expression, value) => {
+                write!(f, "{:?}({}, ", comparison, expression)?;
+                write_literal(f, value)?;
+                write!(f, ")")
+            }
+            Condition::Exists(expression) => write!(f, "Exists({})", expression),
+            Condition::Missing(expression) => write!(f, "Missing({})", expression),
+            Condition::Same(expression, string)
+            | Condition::Contains(expression, string)
+            | Condition::Matches(expression, string) => {
+                let name = match self {
+                    Condition::Same(_, _) => "Same",
+                    Condition::Contains(_, _) => "Contains",
+                    _ => "Matches",
+                };
+                write!(f, "{}({}, ", name, expression)?;
+                write_string(f, string)?;
+                write!(f, ")")
+            }
+            Condition::And(left, right) => write!(f, "And({}, {})", left, right),
+            Condition::Or(left, right) => write!(f, "Or({}, {})", left, right),
+            Condition::Not(condition) => write!(f, "Not({})", condition),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Identifier(String),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Pipe,
+    Comma,
+    Open,
+    Close,
+    Separator,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Identifier(identifier) => write!(f, "`{}`", identifier),
+            Token::Integer(integer) => write!(f, "`{}`", integer),
+            Token::Float(float) => write!(f, "`{}`", float),
+            Token::String(string) => write!(f, "{:?}", string),
+            Token::Pipe => write!(f, "`|`"),
+            Token::Comma => write!(f, "`,`"),
+            Token::Open => write!(f, "`(`"),
+            Token::Close => write!(f, "`)`"),
+            Token::Separator => write!(f, "`::`"),
+        }
+    }
+}
+
+// Tokens are paired with the column they start at (counting from 1), for error messages.
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>> {
+    let characters: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < characters.len() {
+        let column = index + 1;
+        let character = characters[index];
+        let next = characters.get(index + 1).cloned();
+        match character {
+            c if c.is_whitespace() => { index += 1; continue }
+            '|' => tokens.push((column, Token::Pipe)),
+            ',' => tokens.push((column, Token::Comma)),
+            '(' => tokens.push((column, Token::Open)),
+            ')' => tokens.push((column, Token::Close)),
+            ':' if next == Some(':') => { tokens.push((column, Token::Separator)); index += 1 }
+            '"' => {
+                // Only \" and \\ are escapes, so that regular expressions can be written as usual.
+                let mut string = String::new();
+                index += 1;
+                loop {
+                    match (characters.get(index), characters.get(index + 1)) {
+                        (None, _) => bail!("unterminated string starting at column {}", column),
+                        (Some('"'), _) => break,
+                        (Some('\\'), Some(escaped)) if *escaped == '"' || *escaped == '\\' => {
+                            string.push(*escaped);
+                            index += 2;
+                        }
+                        (Some(c), _) => { string.push(*c); index += 1 }
+                    }
+                }
+                tokens.push((column, Token::String(string)));
+            }
+            c if c.is_ascii_digit() || (c == '-' && next.map_or(false, |c| c.is_ascii_digit())) => {
+                let start = index;
+                index += 1;
+                while index < characters.len() && (characters[index].is_ascii_digit() || characters[index] == '.') {
+                    index += 1;
+                }
+                let number: String = characters[start..index].iter().collect();
+                let token = if number.contains('.') {
+                    Token::Float(number.parse().with_context(|| format!("invalid number `{}` at column {}", number, column))?)
+                } else {
+                    Token::Integer(number.parse().with_context(|| format!("invalid number `{}` at column {}", number, column))?)
+                };
+                tokens.push((column, token));
+                continue
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = index;
+                while index < characters.len() && (characters[index].is_alphanumeric() || characters[index] == '_') {
+                    index += 1;
+                }
+                tokens.push((column, Token::Identifier(characters[start..index].iter().collect())));
+                continue
+            }
+            c => bail!("unexpected character `{}` at column {}", c, column),
+        }
+        index += 1;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+    fn column(&self) -> usize {
+        self.tokens.get(self.position).map_or(self.end, |(column, _)| *column)
+    }
+    fn next(&mut self, expected: &str) -> Result<Token> {
+        let token = self.tokens.get(self.position).map(|(_, token)| token.clone())
+            .ok_or_else(|| anyhow!("expected {} at column {}, but the query ended", expected, self.end))?;
+        self.position += 1;
+        Ok(token)
+    }
+    fn unexpected<T>(&self, expected: &str, token: Token) -> Result<T> {
+        bail!("expected {} at column {}, but found {}", expected, self.tokens[self.position - 1].0, token)
+    }
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        let description = expected.to_string();
+        match self.next(&description)? {
+            token if token == expected => Ok(()),
+            token => self.unexpected(&description, token),
+        }
+    }
+    fn identifier(&mut self, expected: &str) -> Result<String> {
+        match self.next(expected)? {
+            Token::Identifier(identifier) => Ok(identifier),
+            token => self.unexpected(expected, token),
+        }
+    }
+    fn integer(&mut self) -> Result<i64> {
+        match self.next("an integer")? {
+            Token::Integer(integer) => Ok(integer),
+            token => self.unexpected("an integer", token),
+        }
+    }
+    fn string(&mut self) -> Result<String> {
+        match self.next("a string")? {
+            Token::String(string) => Ok(string),
+            token => self.unexpected("a string", token),
+        }
+    }
+    fn literal(&mut self) -> Result<Value> {
+        match self.next("a literal")? {
+            Token::Integer(integer) => Ok(Value::Int(integer)),
+            Token::Float(float) => Ok(Value::Float(float)),
+            Token::String(string) => Ok(Value::String(string)),
+            Token::Identifier(identifier) if identifier == "true" => Ok(Value::Bool(true)),
+            Token::Identifier(identifier) if identifier == "false" => Ok(Value::Bool(false)),
+            token => self.unexpected("a literal", token),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query> {
+        let column = self.column();
+        let source = self.identifier("`projects`, `commits`, `users`, or `paths`")?;
+        let source = Entity::from_source(&source).ok_or_else(|| {
+            anyhow!("expected `projects`, `commits`, `users`, or `paths` at column {}, but found `{}`", column, source)
+        })?;
+        let mut stages = Vec::new();
+        while self.peek().is_some() {
+            self.expect(Token::Pipe)?;
+            stages.push(self.stage()?);
+        }
+        Ok(Query { source, stages })
+    }
+
+    fn stage(&mut self) -> Result<Stage> {
+        let column = self.column();
+        let verb = self.identifier("`filter`, `sort`, `sample`, or `select`")?;
+        match verb.as_str() {
+            "filter" | "filter_by" => Ok(Stage::Filter(self.condition()?)),
+            "sort" | "sort_by" => {
+                let expression = self.expression()?;
+                let direction = match self.peek() {
+                    Some(Token::Identifier(direction)) => match direction.to_lowercase().as_str() {
+                        "asc" | "ascending" => Direction::Ascending,
+                        "desc" | "descending" => Direction::Descending,
+                        _ => bail!("expected `asc`, `desc`, or `|` at column {}, but found `{}`", self.column(), direction),
+                    },
+                    _ => return Ok(Stage::Sort(expression, Direction::Descending)),
+                };
+                self.position += 1;
+                Ok(Stage::Sort(expression, direction))
+            }
+            "sample" => Ok(Stage::Sample(self.sampling()?)),
+            "select" | "map_into" => {
+                let mut expressions = vec![self.expression()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.position += 1;
+                    expressions.push(self.expression()?);
+                }
+                Ok(Stage::Select(expressions))
+            }
+            _ => bail!("expected `filter`, `sort`, `sample`, or `select` at column {}, but found `{}`", column, verb),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression> {
+        let mut name = self.identifier("an attribute")?;
+        if name == "Count" && self.peek() == Some(&Token::Open) {
+            self.position += 1;
+            let expression = self.expression()?;
+            self.expect(Token::Close)?;
+            return Ok(Expression::Count(Box::new(expression)))
+        }
+        if self.peek() == Some(&Token::Separator) {
+            self.position += 1;
+            name = format!("{}::{}", name, self.identifier("an attribute")?);
+        }
+        Ok(Expression::Attribute(name))
+    }
+
+    fn condition(&mut self) -> Result<Condition> {
+        let name = match (self.tokens.get(self.position), self.tokens.get(self.position + 1)) {
+            (Some((_, Token::Identifier(name))), Some((_, Token::Open))) if name != "Count" => name.clone(),
+            _ => return Ok(Condition::Holds(self.expression()?)),
+        };
+        let column = self.column();
+        self.position += 2;
+        let condition = if let Some(comparison) = Comparison::from_name(&name) {
+            let expression = self.expression()?;
+            self.expect(Token::Comma)?;
+            Condition::Compare(comparison, expression, self.literal()?)
+        } else {
+            match name.as_str() {
+                "Exists" => Condition::Exists(self.expression()?),
+                "Missing" => Condition::Missing(self.expression()?),
+                "Same" | "Contains" | "Matches" => {
+                    let expression = self.expression()?;
+                    self.expect(Token::Comma)?;
+                    let string = self.string()?;
+                    match name.as_str() {
+                        "Same" => Condition::Same(expression, string),
+                        "Contains" => Condition::Contains(expression, string),
+                        _ => Condition::Matches(expression, string),
+                    }
+                }
+                "And" | "Or" => {
+                    let left = Box::new(self.condition()?);
+                    self.expect(Token::Comma)?;
+                    let right = Box::new(self.condition()?);
+                    if name == "And" { Condition::And(left, right) } else { Condition::Or(left, right) }
+                }
+                "Not" => Condition::Not(Box::new(self.condition()?)),
+                _ => bail!("unknown filter `{}` at column {}", name, column),
+            }
+        };
+        self.expect(Token::Close)?;
+        Ok(condition)
+    }
+
+    fn sampling(&mut self) -> Result<Sampling> {
+        let column = self.column();
+        let name = self.identifier("`Top` or `Random`")?;
+        self.expect(Token::Open)?;
+        let size = self.size()?;
+        let sampling = match name.as_str() {
+            "Top" => Sampling::Top(size),
+            "Random" => {
+                self.expect(Token::Comma)?;
+                // Both `Random(50, 42)` and `Random(50, Seed(42))` are accepted.
+                let seed = if self.peek() == Some(&Token::Identifier("Seed".to_owned())) {
+                    self.position += 1;
+                    self.expect(Token::Open)?;
+                    let seed = self.size()?;
+                    self.expect(Token::Close)?;
+                    seed
+                } else {
+                    self.size()?
+                };
+                Sampling::Random(size, seed as u128)
+            }
+            _ => bail!("expected `Top` or `Random` at column {}, but found `{}`", column, name),
+        };
+        self.expect(Token::Close)?;
+        Ok(sampling)
+    }
+
+    fn size(&mut self) -> Result<usize> {
+        let column = self.column();
+        let integer = self.integer()?;
+        if integer < 0 {
+            bail!("expected a non-negative integer at column {}, but found `{}`", column, integer)
+        }
+        Ok(integer as usize)
+    }
+}
+
+impl Query {
+    /** Parses a query, without checking whether its attributes exist and have the right types. */
+    pub fn parse(text: &str) -> Result<Query> {
+        let tokens = tokenize(text)?;
+        let end = text.chars().count() + 1;
+        Parser { tokens, position: 0, end }.query()
+    }
+
+    /** Checks that the attributes used by the query exist and have the right types. */
+    pub fn check(&self) -> Result<()> {
+        match self.source {
+            Entity::Project  => self.compile::<objects::Project>().map(|_| ()),
+            Entity::Commit   => self.compile::<objects::Commit>().map(|_| ()),
+            Entity::User     => self.compile::<objects::User>().map(|_| ()),
+            Entity::Path     => self.compile::<objects::Path>().map(|_| ()),
+            Entity::Snapshot => bail!("snapshots cannot be queried"),
+        }
+    }
+
+    /** Type-checks and runs the query. */
+    pub fn execute(&self, database: &Database) -> Result<Table> {
+        match self.source {
+            Entity::Project  => Ok(self.compile::<objects::Project>()?.execute(database.projects())),
+            Entity::Commit   => Ok(self.compile::<objects::Commit>()?.execute(database.commits())),
+            Entity::User     => Ok(self.compile::<objects::User>()?.execute(database.users())),
+            Entity::Path     => Ok(self.compile::<objects::Path>()?.execute(database.paths())),
+            Entity::Snapshot => bail!("snapshots cannot be queried"),
+        }
+    }
+
+    fn compile<T: Queryable>(&self) -> Result<Plan<T>> {
+        let attributes = T::attributes();
+        let mut steps = Vec::new();
+        let mut columns = None;
+        for stage in self.stages.iter() {
+            if columns.is_some() {
+                bail!("`select` must be the last stage of a query, but it is followed by `{}`", stage)
+            }
+            match stage {
+                Stage::Filter(condition) =>
+                    steps.push(Step::Filter(Predicate::compile(condition, &attributes)?)),
+                Stage::Sort(expression, direction) =>
+                    steps.push(Step::Sort(Accessor::compile(expression, &attributes)?.scalar(expression)?, *direction)),
+                Stage::Sample(sampling) =>
+                    steps.push(Step::Sample(*sampling)),
+                Stage::Select(expressions) => {
+                    columns = Some(expressions.iter().map(|expression| {
+                        Accessor::compile(expression, &attributes).map(|accessor| (expression.to_string(), accessor))
+                    }).collect::<Result<Vec<_>>>()?)
+                }
+            }
+        }
+        let columns = match columns {
+            Some(columns) => columns,
+            None => {
+                let id = Expression::Attribute("Id".to_owned());
+                vec![(id.to_string(), Accessor::compile(&id, &attributes)?)]
+            }
+        };
+        Ok(Plan { steps, columns })
+    }
+}
+
+impl std::str::FromStr for Query {
+    type Err = anyhow::Error;
+    fn from_str(text: &str) -> Result<Self> { Query::parse(text) }
+}
+
+struct Accessor<T> {
+    entry: AttributeEntry<T>,
+    count: bool,
+}
+
+impl<T: Queryable> Accessor<T> {
+    fn compile(expression: &Expression, attributes: &[AttributeEntry<T>]) -> Result<Accessor<T>> {
+        match expression {
+            Expression::Attribute(name) => {
+                let (module, name) = match name.find("::") {
+                    Some(index) => (Some(&name[..index]), &name[index + 2..]),
+                    None => (None, name.as_str()),
+                };
+                if let Some(module) = module {
+                    if module != T::ENTITY.module() {
+                        bail!("`{}::{}` cannot be used in a query of {}", module, name, T::ENTITY.source())
+                    }
+                }
+                attributes.iter()
+                    .find(|entry| entry.name == name)
+                    .map(|entry| Accessor { entry: entry.clone(), count: false })
+                    .ok_or_else(|| anyhow!("there is no attribute `{}::{}`", T::ENTITY.module(), name))
+            }
+            Expression::Count(inner) => {
+                let accessor = Accessor::compile(inner, attributes)?;
+                if accessor.count || !accessor.value_type().is_collection() {
+                    bail!("`{}` is not a collection, so it cannot be counted, it is {}", inner, accessor.value_type())
+                }
+                Ok(Accessor { count: true, ..accessor })
+            }
+        }
+    }
+
+    // Collections are counted where a single value is expected.
+    fn scalar(self, expression: &Expression) -> Result<Accessor<T>> {
+        match self.value_type() {
+            Type::List(_) => Ok(Accessor { count: true, ..self }),
+            Type::Tuple(_) => bail!("`{}` is a tuple, which cannot be compared", expression),
+            _ => Ok(self),
+        }
+    }
+
+    fn value_type(&self) -> Type {
+        if self.count { Type::Int } else { self.entry.value_type.clone() }
+    }
+
+    fn get<'a>(&self, object: &ItemWithData<'a, T>) -> Option<Value> {
+        if !self.count {
+            return (self.entry.getter)(object)
+        }
+        match self.entry.counter {
+            Some(counter) => counter(object).map(|count| Value::Int(count as i64)),
+            None => (self.entry.getter)(object).map(|value| match value {
+                Value::List(values) => Value::Int(values.len() as i64),
+                value => value,
+            }),
+        }
+    }
+}
+
+enum Predicate<T> {
+    Holds(Accessor<T>),
+    Compare(Comparison, Accessor<T>, Value),
+    Exists(Accessor<T>),
+    Missing(Accessor<T>),
+    Same(Accessor<T>, String),
+    Contains(Accessor<T>, String),
+    Matches(Accessor<T>, Regex),
+    And(Box<Predicate<T>>, Box<Predicate<T>>),
+    Or(Box<Predicate<T>>, Box<Predicate<T>>),
+    Not(Box<Predicate<T>>),
+}
+
+impl<T: Queryable> Predicate<T> {
+    fn compile(condition: &Condition, attributes: &[AttributeEntry<T>]) -> Result<Predicate<T>> {
+        let string_accessor = |expression: &Expression| -> Result<Accessor<T>> {
+            let accessor = Accessor::compile(expression, attributes)?;
+            match accessor.value_type() {
+                Type::String => Ok(accessor),
+                other => bail!("`{}` must be a string in `{}`, but it is {}", expression, condition, other),
+            }
+        };
+        Ok(match condition {
+            Condition::Holds(expression) => {
+                let accessor = Accessor::compile(expression, attributes)?;
+                match accessor.value_type() {
+                    Type::Bool => Predicate::Holds(accessor),
+                    other => bail!("`{}` is not a condition, it is {}", expression, other),
+                }
+            }
+            Condition::Compare(comparison, expression, literal) => {
+                let accessor = Accessor::compile(expression, attributes)?.scalar(expression)?;
+                let value = match (accessor.value_type(), literal) {
+                    (Type::Int, Value::Int(_)) | (Type::Int, Value::Float(_))
+                    | (Type::Float, Value::Int(_)) | (Type::Float, Value::Float(_))
+                    | (Type::Bool, Value::Bool(_)) | (Type::String, Value::String(_)) => literal.clone(),
+                    (Type::Id(entity), Value::Int(id)) if *id >= 0 => Value::Id(entity, *id as u64),
+                    (value_type, _) => bail!("`{}` is {}, so it cannot be compared with the literal in `{}`", expression, value_type, condition),
+                };
+                Predicate::Compare(*comparison, accessor, value)
+            }
+            Condition::Exists(expression) => Predicate::Exists(Accessor::compile(expression, attributes)?),
+            Condition::Missing(expression) => Predicate::Missing(Accessor::compile(expression, attributes)?),
+            Condition::Same(expression, string) => Predicate::Same(string_accessor(expression)?, string.clone()),
+            Condition::Contains(expression, string) => Predicate::Contains(string_accessor(expression)?, string.clone()),
+            Condition::Matches(expression, pattern) => {
+                let regex = Regex::new(pattern).with_context(|| format!("invalid regular expression in `{}`", condition))?;
+                Predicate::Matches(string_accessor(expression)?, regex)
+            }
+            Condition::And(left, right) => Predicate::And(Box::new(Predicate::compile(left, attributes)?), Box::new(Predicate::compile(right, attributes)?)),
+            Condition::Or(left, right) => Predicate::Or(Box::new(Predicate::compile(left, attributes)?), Box::new(Predicate::compile(right, attributes)?)),
+            Condition::Not(condition) => Predicate::Not(Box::new(Predicate::compile(condition, attributes)?)),
+        })
+    }
+
+    fn accept<'a>(&self, object: &ItemWithData<'a, T>) -> bool {
+        let string = |accessor: &Accessor<T>| match accessor.get(object) {
+            Some(Value::String(string)) => Some(string),
+            _ => None,
+        };
+        match self {
+            Predicate::Holds(accessor) => accessor.get(object) == Some(Value::Bool(true)),
+            Predicate::Compare(comparison, accessor, value) => accessor.get(object)
+                .map_or(comparison.holds_for_missing(), |actual| comparison.holds(actual.cmp(value))),
+            Predicate::Exists(accessor) => accessor.get(object).is_some(),
+            Predicate::Missing(accessor) => accessor.get(object).is_none(),
+            Predicate::Same(accessor, expected) => string(accessor).map_or(false, |actual| &actual == expected),
+            Predicate::Contains(accessor, expected) => string(accessor).map_or(false, |actual| actual.contains(expected.as_str())),
+            Predicate::Matches(accessor, regex) => string(accessor).map_or(false, |actual| regex.is_match(&actual)),
+            Predicate::And(left, right) => left.accept(object) && right.accept(object),
+            Predicate::Or(left, right) => left.accept(object) || right.accept(object),
+            Predicate::Not(predicate) => !predicate.accept(object),
+        }
+    }
+}
+
+enum Step<T> {
+    Filter(Predicate<T>),
+    Sort(Accessor<T>, Direction),
+    Sample(Sampling),
+}
+
+struct Plan<T> {
+    steps: Vec<Step<T>>,
+    columns: Vec<(String, Accessor<T>)>,
+}
+
+impl<T: Queryable> Plan<T> {
+    fn execute<'a, I>(&self, objects: I) -> Table where I: Iterator<Item=ItemWithData<'a, T>> {
+        // Filters before the first sort or sample are applied as the objects are loaded.
+        let leading_filters = self.steps.iter().take_while(|step| match step { Step::Filter(_) => true, _ => false }).count();
+        let mut objects: Vec<ItemWithData<'a, T>> = objects.filter(|object| {
+            self.steps[..leading_filters].iter().all(|step| match step {
+                Step::Filter(predicate) => predicate.accept(object),
+                _ => unreachable!(),
+            })
+        }).collect();
+
+        for step in self.steps[leading_filters..].iter() {
+            match step {
+                Step::Filter(predicate) => objects.retain(|object| predicate.accept(object)),
+                Step::Sort(accessor, Direction::Ascending) => objects.sort_by_cached_key(|object| accessor.get(object)),
+                Step::Sort(accessor, Direction::Descending) => objects.sort_by_cached_key(|object| Reverse(accessor.get(object))),
+                Step::Sample(Sampling::Top(size)) => objects = Top(*size).sample_from(objects),
+                Step::Sample(Sampling::Random(size, seed)) => objects = Random(*size, Seed(*seed)).sample_from(objects),
+            }
+        }
+
+        let rows = objects.iter().map(|object| {
+            self.columns.iter().map(|(_, accessor)| accessor.get(object).unwrap_or(Value::Null)).collect()
+        }).collect();
+        Table {
+            columns: self.columns.iter().map(|(name, _)| name.clone()).collect(),
+            rows,
+        }
+    }
+}
+
+/** The result of a query: a row of values for each object, one value per selected attribute. */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn len(&self) -> usize { self.rows.len() }
+    pub fn is_empty(&self) -> bool { self.rows.is_empty() }
+
+    /** Formats some of the rows as a text table with aligned columns. Values longer than
+        `max_width` characters are truncated. */
+    pub fn to_text(&self, rows: std::ops::Range<usize>, max_width: usize) -> String {
+        let truncate = |text: String| {
+            if text.chars().count() <= max_width { return text }
+            let mut truncated: String = text.chars().take(max_width.saturating_sub(3)).collect();
+            truncated.push_str("...");
+            truncated
+        };
+        let rows = &self.rows[rows.start.min(self.rows.len())..rows.end.min(self.rows.len())];
+        let cells: Vec<Vec<String>> = std::iter::once(self.columns.clone())
+            .chain(rows.iter().map(|row| row.iter().map(|value| value.to_string()).collect()))
+            .map(|row: Vec<String>| row.into_iter().map(truncate).collect())
+            .collect();
+        let widths: Vec<usize> = (0..self.columns.len()).map(|column| {
+            cells.iter().map(|row| row[column].chars().count()).max().unwrap_or(0)
+        }).collect();
+        cells.iter().map(|row| {
+            row.iter().zip(widths.iter())
+                .map(|(cell, width)| format!("{:width$}", cell, width = *width))
+                .join("  ")
+                .trim_end()
+                .to_owned()
+        }).join("\n")
+    }
+
+    /** Writes the table to a CSV file, with the selected attributes as column headers. */
+    pub fn into_csv(self, location: impl Into<String>) -> Result<(), std::io::Error> {
+        let headers: Vec<&str> = self.columns.iter().map(|column| column.as_str()).collect();
+        self.rows.iter().into_csv_with_headers(headers, location)
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.columns.iter().join("\t"))?;
+        for row in self.rows.iter() {
+            writeln!(f, "{}", row.iter().join("\t"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::query::*;
+
+    #[test]
+    fn test_parse() {
+        let query = Query::parse("projects | filter AtLeast(Stars, 100) | sort Commits | sample Random(50, 42) | select Id, URL").unwrap();
+        assert_eq!(query, Query {
+            source: Entity::Project,
+            stages: vec![
+                Stage::Filter(Condition::Compare(Comparison::AtLeast, Expression::Attribute("Stars".to_owned()), Value::Int(100))),
+                Stage::Sort(Expression::Attribute("Commits".to_owned()), Direction::Descending),
+                Stage::Sample(Sampling::Random(50, 42)),
+                Stage::Select(vec![Expression::Attribute("Id".to_owned()), Expression::Attribute("URL".to_owned())]),
+            ],
+        });
+
+        let text = r#"commits | filter And(Not(IsRevert), Matches(commit::Message, "(?i)\bfix\b \"x\"")) | sort Count(Parents) asc | sample Top(10)"#;
+        assert_eq!(Query::parse(text).unwrap().to_string(), text);
+        assert_eq!(Query::parse("users | sort_by Experience | sample Random(5, Seed(1)) | map_into Email").unwrap().to_string(),
+                   "users | sort Experience | sample Random(5, 1) | select Email");
+
+        assert!(Query::parse("snapshots").is_err());
+        assert!(Query::parse("projects | filter AtLeast(Stars 100)").is_err());
+        assert!(Query::parse("projects | sample Top(-1)").is_err());
+        assert!(Query::parse("projects | select Id,").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let check = |text: &str| Query::parse(text).unwrap().check();
+        assert!(check("projects | filter AtLeast(Stars, 100) | sort Commits | sample Random(50, 42) | select Id, URL").is_ok());
+        assert!(check("projects | filter And(IsFork, Equal(project::Language, \"Rust\")) | select Count(Commits), Languages").is_ok());
+        assert!(check("paths | filter Equal(Id, 42)").is_ok());
+        assert!(check("commits | filter MoreThan(Parents, 1) | select Hash").is_ok());
+
+        assert!(check("projects | filter AtLeast(Popularity, 100)").is_err()); // no such attribute
+        assert!(check("projects | filter commit::IsRevert").is_err());         // wrong entity
+        assert!(check("projects | filter Stars").is_err());                    // not a bool
+        assert!(check("projects | filter AtLeast(Stars, \"many\")").is_err());  // wrong literal
+        assert!(check("projects | filter Same(Stars, \"100\")").is_err());      // not a string
+        assert!(check("projects | filter Matches(URL, \"(\")").is_err());      // invalid regex
+        assert!(check("projects | select Count(Stars)").is_err());             // not a collection
+        assert!(check("projects | select Id | sort Stars").is_err());          // select is not last
+    }
+}
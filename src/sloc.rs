@@ -0,0 +1,212 @@
+use crate::objects::{Language, LineCounts};
+
+/**
+ * Comment syntax of a language: markers that start comments running to the end of the line, pairs
+ * of markers that delimit block comments, and characters that delimit string literals (so that
+ * comment markers inside strings are not mistaken for comments).
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct CommentSyntax {
+    pub line: &'static [&'static str],
+    pub block: &'static [(&'static str, &'static str)],
+    pub strings: &'static [char],
+}
+
+const C_LIKE_COMMENTS:          CommentSyntax = CommentSyntax { line: &["//"], block: &[("/*", "*/")], strings: &['"', '\''] };
+const C_LIKE_NO_CHARS_COMMENTS: CommentSyntax = CommentSyntax { line: &["//"], block: &[("/*", "*/")], strings: &['"'] };
+const GO_COMMENTS:              CommentSyntax = CommentSyntax { line: &["//"], block: &[("/*", "*/")], strings: &['"', '\'', '`'] };
+const JAVASCRIPT_COMMENTS:      CommentSyntax = CommentSyntax { line: &["//"], block: &[("/*", "*/")], strings: &['"', '\'', '`'] };
+const D_COMMENTS:               CommentSyntax = CommentSyntax { line: &["//"], block: &[("/*", "*/"), ("/+", "+/")], strings: &['"', '\'', '`'] };
+const PHP_COMMENTS:             CommentSyntax = CommentSyntax { line: &["//", "#"], block: &[("/*", "*/")], strings: &['"', '\''] };
+const CSS_COMMENTS:             CommentSyntax = CommentSyntax { line: &[], block: &[("/*", "*/")], strings: &['"', '\''] };
+const HASH_COMMENTS:            CommentSyntax = CommentSyntax { line: &["#"], block: &[], strings: &['"', '\''] };
+const PYTHON_COMMENTS:          CommentSyntax = CommentSyntax { line: &["#"], block: &[], strings: &['"', '\''] };
+const RUBY_COMMENTS:            CommentSyntax = CommentSyntax { line: &["#"], block: &[("=begin", "=end")], strings: &['"', '\''] };
+const PERL_COMMENTS:            CommentSyntax = CommentSyntax { line: &["#"], block: &[("=pod", "=cut"), ("=head1", "=cut")], strings: &['"', '\''] };
+const JULIA_COMMENTS:           CommentSyntax = CommentSyntax { line: &["#"], block: &[("#=", "=#")], strings: &['"'] };
+const COFFEESCRIPT_COMMENTS:    CommentSyntax = CommentSyntax { line: &["#"], block: &[("###", "###")], strings: &['"', '\''] };
+const NIM_COMMENTS:             CommentSyntax = CommentSyntax { line: &["#"], block: &[("#[", "]#")], strings: &['"', '\''] };
+const POWERSHELL_COMMENTS:      CommentSyntax = CommentSyntax { line: &["#"], block: &[("<#", "#>")], strings: &['"', '\''] };
+const HCL_COMMENTS:             CommentSyntax = CommentSyntax { line: &["#", "//"], block: &[("/*", "*/")], strings: &['"'] };
+const NIX_COMMENTS:             CommentSyntax = CommentSyntax { line: &["#"], block: &[("/*", "*/")], strings: &['"'] };
+const HASKELL_COMMENTS:         CommentSyntax = CommentSyntax { line: &["--"], block: &[("{-", "-}")], strings: &['"'] };
+const LUA_COMMENTS:             CommentSyntax = CommentSyntax { line: &["--"], block: &[("--[[", "]]")], strings: &['"', '\''] };
+const SQL_COMMENTS:             CommentSyntax = CommentSyntax { line: &["--"], block: &[("/*", "*/")], strings: &['"', '\''] };
+const DASH_DASH_COMMENTS:       CommentSyntax = CommentSyntax { line: &["--"], block: &[], strings: &['"'] };
+const APPLESCRIPT_COMMENTS:     CommentSyntax = CommentSyntax { line: &["--", "#"], block: &[("(*", "*)")], strings: &['"'] };
+const LISP_COMMENTS:            CommentSyntax = CommentSyntax { line: &[";"], block: &[("#|", "|#")], strings: &['"'] };
+const SEMICOLON_COMMENTS:       CommentSyntax = CommentSyntax { line: &[";"], block: &[], strings: &['"'] };
+const PERCENT_COMMENTS:         CommentSyntax = CommentSyntax { line: &["%"], block: &[], strings: &['"'] };
+const PROLOG_COMMENTS:          CommentSyntax = CommentSyntax { line: &["%"], block: &[("/*", "*/")], strings: &['"', '\''] };
+const MATLAB_COMMENTS:          CommentSyntax = CommentSyntax { line: &["%"], block: &[("%{", "%}")], strings: &['"', '\''] };
+const ML_COMMENTS:              CommentSyntax = CommentSyntax { line: &[], block: &[("(*", "*)")], strings: &['"'] };
+const FSHARP_COMMENTS:          CommentSyntax = CommentSyntax { line: &["//"], block: &[("(*", "*)")], strings: &['"'] };
+const PASCAL_COMMENTS:          CommentSyntax = CommentSyntax { line: &["//"], block: &[("{", "}"), ("(*", "*)")], strings: &['\''] };
+const BASIC_COMMENTS:           CommentSyntax = CommentSyntax { line: &["'", "REM ", "Rem ", "rem "], block: &[], strings: &['"'] };
+const FORTRAN_COMMENTS:         CommentSyntax = CommentSyntax { line: &["!"], block: &[], strings: &['"', '\''] };
+const MARKUP_COMMENTS:          CommentSyntax = CommentSyntax { line: &[], block: &[("<!--", "-->")], strings: &[] };
+const HANDLEBARS_COMMENTS:      CommentSyntax = CommentSyntax { line: &[], block: &[("{{!--", "--}}"), ("{{!", "}}"), ("<!--", "-->")], strings: &[] };
+const BATCH_COMMENTS:           CommentSyntax = CommentSyntax { line: &["::", "REM ", "rem ", "@REM ", "@rem "], block: &[], strings: &['"'] };
+const COBOL_COMMENTS:           CommentSyntax = CommentSyntax { line: &["*>"], block: &[], strings: &['"', '\''] };
+const VIM_COMMENTS:             CommentSyntax = CommentSyntax { line: &["\""], block: &[], strings: &['\''] };
+const NONE_COMMENTS:            CommentSyntax = CommentSyntax { line: &[], block: &[], strings: &[] };
+
+/** The comment syntax of a language. Languages without comments, or that we do not know the syntax
+    of, get an empty syntax: all their non-blank lines count as code. */
+pub fn comment_syntax(language: Language) -> CommentSyntax {
+    use Language::*;
+    match language {
+        C | Cpp | CSharp | Java | Kotlin | Scala | Groovy | Swift | ObjectiveC | ObjectiveCpp
+        | Dart | Cuda | GLSL | HLSL | Metal | Solidity | Vala | Haxe | ActionScript | Apex
+        | AspectJ | Arduino | Processing | Less | SCSS | Verilog | SystemVerilog | ProtocolBuffer
+        | Jsonnet | Hack | Odin | V | OpenSCAD | Pawn | SourcePawn | Squirrel | UnrealScript | XC
+        | NesC | EC | Scilab | POVRaySDL | Stan | ANTLR | CapNProto | ShaderLab | Yacc | Lex | Ragel | Frege | Bluespec
+        | SQF | DM | LSL | Zig | Thrift | GAML | Nextflow | CartoCSS | ChucK | Logos => C_LIKE_COMMENTS,
+        Rust | TypeScript | QSharp | Reason | Gherkin | RenderScript | Slice | P4 | MQL4 | MQL5
+        | Cycript | Volt | Nearley | AGS | ObjectiveJ | Jasmin | Pep8 => C_LIKE_NO_CHARS_COMMENTS,
+        Go => GO_COMMENTS,
+        JavaScript | Svelte | EmberScript | QML | QtScript | Mako => JAVASCRIPT_COMMENTS,
+        D => D_COMMENTS,
+        PHP => PHP_COMMENTS,
+        CSS => CSS_COMMENTS,
+        Python | Starlark | GDScript | Sage => PYTHON_COMMENTS,
+        Ruby => RUBY_COMMENTS,
+        Perl | Raku => PERL_COMMENTS,
+        Julia => JULIA_COMMENTS,
+        CoffeeScript | LiveScript => COFFEESCRIPT_COMMENTS,
+        Nim => NIM_COMMENTS,
+        PowerShell => POWERSHELL_COMMENTS,
+        HCL => HCL_COMMENTS,
+        Nix => NIX_COMMENTS,
+        Shell | R | Elixir | Makefile | CMake | YAML | Dockerfile | Tcl | Awk | Crystal | Puppet
+        | Sed | Gnuplot | Meson | ApacheConf | Nginx | BitBake | GDB | SaltStack | Zeek | Boo
+        | Fancy | Hy | NCL | RAML | Sieve | Stata | QMake | Slash | RobotFramework
+        | RPC | Terra | Zimpl | WDL | CommonWorkflowLanguage | Io | Max | OpenPolicyAgent
+        | Parrot | M4 | GAP => HASH_COMMENTS,
+        Haskell | Elm | Agda | Idris | PureScript | Dhall | Futhark => HASKELL_COMMENTS,
+        Lua => LUA_COMMENTS,
+        SQL | PLSQL | PLpgSQL | TSQL | HiveQL | SQLPL => SQL_COMMENTS,
+        Ada | VHDL | Eiffel | Lean => DASH_DASH_COMMENTS,
+        AppleScript => APPLESCRIPT_COMMENTS,
+        Lisp | Scheme | Racket | Clojure | EmacsLisp | NewLisp | PicoLisp | Arc | CLIPS => LISP_COMMENTS,
+        ASM | AutoHotkey | AutoIt | Rebol | Red | NSIS | InnoSetup | Smali | LLVM | MLIR | MuPAD
+        | WebAssembly | LilyPond => SEMICOLON_COMMENTS,
+        Erlang | TeX | PostScript | Mercury | Oz => PERCENT_COMMENTS,
+        Prolog => PROLOG_COMMENTS,
+        MATLAB | M => MATLAB_COMMENTS,
+        OCaml | StandardML | Coq | Mathematica | Modula2 | Modula3 | ComponentPascal | FStar => ML_COMMENTS,
+        FSharp => FSHARP_COMMENTS,
+        Pascal => PASCAL_COMMENTS,
+        VisualBasic | VBA | VBScript | PureBasic | BlitzBasic | BlitzMax | XBase => BASIC_COMMENTS,
+        Fortran => FORTRAN_COMMENTS,
+        HTML | XML | XSLT | Vue | Markdown | XProc | WebOntologyLanguage | Blade | Liquid => MARKUP_COMMENTS,
+        Handlebars => HANDLEBARS_COMMENTS,
+        Batchfile => BATCH_COMMENTS,
+        Cobol => COBOL_COMMENTS,
+        VimScript => VIM_COMMENTS,
+        _ => NONE_COMMENTS,
+    }
+}
+
+/**
+ * Counts code, comment, and blank lines in the contents of a file, following the conventions of
+ * cloc: a line is blank if it contains only whitespace, a comment line if everything on it is
+ * inside comments, and a code line otherwise (including lines with code followed by a comment).
+ *
+ * Strings are tracked within a line only, so that comment markers inside string literals are not
+ * counted; multi-line strings and nested block comments are not recognized.
+ */
+pub fn count_lines(contents: &str, syntax: &CommentSyntax) -> LineCounts {
+    let mut counts = LineCounts::default();
+    let mut block_end: Option<&'static str> = None;
+
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            counts.blank += 1;
+            continue;
+        }
+
+        let mut has_code = false;
+        let mut has_comment = false;
+        let mut string: Option<char> = None;
+        let mut rest = line;
+
+        'line: while let Some(c) = rest.chars().next() {
+            if let Some(end) = block_end {
+                has_comment = true;
+                if rest.starts_with(end) {
+                    block_end = None;
+                    rest = &rest[end.len()..];
+                } else {
+                    rest = &rest[c.len_utf8()..];
+                }
+                continue;
+            }
+            if let Some(quote) = string {
+                if c == '\\' {
+                    rest = &rest[c.len_utf8()..];
+                    if let Some(escaped) = rest.chars().next() {
+                        rest = &rest[escaped.len_utf8()..];
+                    }
+                } else {
+                    if c == quote { string = None; }
+                    rest = &rest[c.len_utf8()..];
+                }
+                continue;
+            }
+            if c.is_whitespace() {
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            for (start, end) in syntax.block.iter() {
+                if rest.starts_with(start) {
+                    has_comment = true;
+                    block_end = Some(end);
+                    rest = &rest[start.len()..];
+                    continue 'line;
+                }
+            }
+            if syntax.line.iter().any(|marker| rest.starts_with(marker)) {
+                has_comment = true;
+                break;
+            }
+            if syntax.strings.contains(&c) {
+                string = Some(c);
+            }
+            has_code = true;
+            rest = &rest[c.len_utf8()..];
+        }
+
+        if has_code {
+            counts.code += 1;
+        } else if has_comment {
+            counts.comment += 1;
+        } else {
+            counts.blank += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sloc::*;
+    use crate::objects::Language;
+
+    #[test] fn test_count_c_like() {
+        let source = "/* header\n * more\n */\n\nint main() { // entry\n    char *s = \"/* not a comment */\";\n    return 0;\n}\n";
+        let counts = count_lines(source, &comment_syntax(Language::C));
+        assert_eq!((counts.code, counts.comment, counts.blank), (4, 3, 1));
+    }
+
+    #[test] fn test_count_hash() {
+        let source = "#!/usr/bin/env python\n# comment\n\nx = '#not a comment'\n";
+        let counts = count_lines(source, &comment_syntax(Language::Python));
+        assert_eq!((counts.code, counts.comment, counts.blank), (1, 2, 1));
+    }
+
+    #[test] fn test_count_unknown() {
+        let counts = count_lines("a\n\nb\n", &comment_syntax(Language::Other));
+        assert_eq!((counts.code, counts.comment, counts.blank), (2, 0, 1));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::objects::Language;
+
+/** Directories whose contents are test code regardless of language. */
+pub static TEST_DIRECTORIES: [&'static str; 11] = [
+    "test", "tests", "__tests__", "__test__", "spec", "specs", "testing", "unittests",
+    "androidtest", "testsuite", "e2e",
+];
+
+/**
+ * Determines whether a file is test code, based on where it is and what it is called. The language
+ * (if known) selects the naming conventions of that language's test frameworks:
+ *
+ *  - any file in a test directory, eg. `src/test/`, `tests/`, `__tests__/`, `spec/`
+ *  - Go: `*_test.go`
+ *  - Python: `test_*.py`, `*_test.py`, `conftest.py`
+ *  - JavaScript and friends: `*.test.js`, `*.spec.ts`, etc.
+ *  - Ruby: `*_spec.rb`, `*_test.rb`
+ *  - JVM and .NET languages: `FooTest.java`, `FooTests.cs`, `TestFoo.java`, `FooIT.java`, `FooSpec.scala`
+ *  - C and C++: `*_test.cc`, `*_unittest.cpp`, `test_*.c`
+ *  - Perl: anything in `t/`
+ *  - others: `test_*`, `*_test`, `*_spec`, `*Test`, `*Spec`
+ */
+pub fn is_test_path(location: &str, language: Option<Language>) -> bool {
+    let mut components: Vec<&str> = location.split('/').filter(|component| !component.is_empty()).collect();
+    let file_name = match components.pop() {
+        Some(file_name) => file_name,
+        None => return false,
+    };
+
+    if components.iter().any(|directory| TEST_DIRECTORIES.contains(&directory.to_lowercase().as_str())) {
+        return true;
+    }
+
+    let (stem, extension) = match file_name.rfind('.') {
+        Some(0) | None => (file_name, ""),
+        Some(index) => (&file_name[..index], &file_name[index + 1..]),
+    };
+    let lowercase_stem = stem.to_lowercase();
+
+    use Language::*;
+    match language {
+        Some(Go) => lowercase_stem.ends_with("_test"),
+        Some(Python) => {
+            lowercase_stem.starts_with("test_") || lowercase_stem.ends_with("_test")
+                || lowercase_stem.ends_with("_tests") || lowercase_stem == "conftest"
+        }
+        Some(JavaScript) | Some(TypeScript) | Some(CoffeeScript) | Some(Vue) | Some(Svelte) => {
+            has_suffix(&lowercase_stem, &[".test", ".spec", ".e2e", "-test", "-spec", "_test", "_spec"])
+        }
+        Some(Ruby) => has_suffix(&lowercase_stem, &["_spec", "_test"]) || lowercase_stem.starts_with("test_"),
+        Some(Java) | Some(Kotlin) | Some(Scala) | Some(Groovy) | Some(CSharp) | Some(FSharp)
+        | Some(VisualBasic) | Some(Swift) | Some(ObjectiveC) | Some(PHP) | Some(Haskell) => {
+            is_camel_case_test(stem)
+        }
+        Some(C) | Some(Cpp) | Some(ObjectiveCpp) | Some(Cuda) => {
+            has_suffix(&lowercase_stem, &["_test", "_tests", "_unittest", "-test", "_spec"])
+                || lowercase_stem.starts_with("test_")
+                || is_camel_case_test(stem)
+        }
+        Some(Perl) | Some(Raku) => {
+            extension == "t" || components.iter().any(|directory| *directory == "t")
+        }
+        Some(Elixir) => has_suffix(&lowercase_stem, &["_test"]),
+        Some(Dart) | Some(Rust) | Some(Erlang) => has_suffix(&lowercase_stem, &["_test", "_tests", "_spec", "_suite"]),
+        _ => {
+            lowercase_stem.starts_with("test_") || has_suffix(&lowercase_stem, &["_test", "_tests", "_spec"])
+                || is_camel_case_test(stem)
+        }
+    }
+}
+
+fn has_suffix(stem: &str, suffixes: &[&str]) -> bool {
+    suffixes.iter().any(|suffix| stem.ends_with(suffix))
+}
+
+// FooTest, FooTests, FooSpec, FooIT, TestFoo: the marker must be separated from the rest of the name
+// by a change of case, so that eg. Latest, Contest, or Kit are not mistaken for tests.
+fn is_camel_case_test(stem: &str) -> bool {
+    let preceded_by_lowercase = |marker: &str| {
+        stem.len() > marker.len()
+            && stem.ends_with(marker)
+            && stem[..stem.len() - marker.len()].chars().last().map_or(false, |c| c.is_lowercase() || c.is_numeric())
+    };
+    let followed_by_uppercase = |marker: &str| {
+        stem.starts_with(marker)
+            && stem[marker.len()..].chars().next().map_or(false, |c| c.is_uppercase())
+    };
+    ["Test", "Tests", "Spec", "Specs", "IT"].iter().any(|marker| preceded_by_lowercase(marker))
+        || (stem.len() > 4 && ["Test", "Tests"].iter().any(|marker| followed_by_uppercase(marker)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testcode::*;
+    use crate::objects::Language;
+
+    #[test] fn test_test_directories() {
+        assert!(is_test_path("src/test/java/org/example/Main.java", Some(Language::Java)));
+        assert!(is_test_path("app/__tests__/button.js", Some(Language::JavaScript)));
+        assert!(is_test_path("tests/integration.rs", Some(Language::Rust)));
+        assert!(!is_test_path("src/main/java/org/example/Main.java", Some(Language::Java)));
+        assert!(!is_test_path("src/latest.py", Some(Language::Python)));
+    }
+
+    #[test] fn test_test_file_names() {
+        assert!(is_test_path("pkg/server/server_test.go", Some(Language::Go)));
+        assert!(is_test_path("lib/test_parser.py", Some(Language::Python)));
+        assert!(is_test_path("src/app/app.component.spec.ts", Some(Language::TypeScript)));
+        assert!(is_test_path("lib/parser_spec.rb", Some(Language::Ruby)));
+        assert!(is_test_path("src/ParserTest.java", Some(Language::Java)));
+        assert!(is_test_path("src/ParserIT.java", Some(Language::Java)));
+        assert!(!is_test_path("src/Contest.java", Some(Language::Java)));
+        assert!(!is_test_path("src/Kit.java", Some(Language::Java)));
+        assert!(!is_test_path("pkg/server/server.go", Some(Language::Go)));
+    }
+}
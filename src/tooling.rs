@@ -0,0 +1,77 @@
+use crate::objects::Tool;
+
+/**
+ * Recognizing development tools by their configuration files.
+ *
+ * CI configurations are only recognized where the CI service looks for them (eg. GitHub Actions
+ * workflows in `.github/workflows/` and CircleCI configuration in `.circleci/`). Build, linter, and
+ * container configurations are recognized by file name regardless of the directory they are in,
+ * since projects often keep several modules, each with its own build file.
+ */
+pub fn path_tool(location: &str) -> Option<Tool> {
+    let location = location.trim_start_matches('/');
+    let file_name = location.rsplit('/').next().unwrap_or(location);
+    let is_yaml = file_name.ends_with(".yml") || file_name.ends_with(".yaml");
+
+    if location.starts_with(".github/workflows/") && is_yaml {
+        return Some(Tool::GitHubActions);
+    }
+    if location == ".circleci/config.yml" || location == ".circleci/config.yaml" {
+        return Some(Tool::CircleCI);
+    }
+
+    match file_name {
+        ".travis.yml"                                               => Some(Tool::TravisCI),
+        "Jenkinsfile"                                               => Some(Tool::Jenkins),
+        ".gitlab-ci.yml"                                            => Some(Tool::GitLabCI),
+        "appveyor.yml" | ".appveyor.yml"                            => Some(Tool::AppVeyor),
+        "azure-pipelines.yml" | "azure-pipelines.yaml"              => Some(Tool::AzurePipelines),
+
+        "Makefile" | "makefile" | "GNUmakefile"                     => Some(Tool::Make),
+        "CMakeLists.txt"                                            => Some(Tool::CMake),
+        "pom.xml"                                                   => Some(Tool::Maven),
+        "build.gradle" | "build.gradle.kts"
+        | "settings.gradle" | "settings.gradle.kts"                 => Some(Tool::Gradle),
+        "package.json"                                              => Some(Tool::NPM),
+        "Cargo.toml"                                                => Some(Tool::Cargo),
+        "WORKSPACE" | "WORKSPACE.bazel" | "BUILD.bazel" | ".bazelrc" => Some(Tool::Bazel),
+
+        "eslint.config.js" | "eslint.config.mjs"                    => Some(Tool::ESLint),
+        ".pylintrc" | "pylintrc"                                    => Some(Tool::Pylint),
+        ".flake8"                                                   => Some(Tool::Flake8),
+        ".rubocop.yml"                                              => Some(Tool::RuboCop),
+        "checkstyle.xml"                                            => Some(Tool::Checkstyle),
+        "clippy.toml" | ".clippy.toml"                              => Some(Tool::Clippy),
+        "rustfmt.toml" | ".rustfmt.toml"                            => Some(Tool::Rustfmt),
+        ".golangci.yml" | ".golangci.yaml" | ".golangci.toml"       => Some(Tool::GolangCILint),
+        ".pre-commit-config.yaml"                                   => Some(Tool::PreCommit),
+
+        "Dockerfile" | "Containerfile"                              => Some(Tool::Docker),
+        "docker-compose.yml" | "docker-compose.yaml"
+        | "compose.yml" | "compose.yaml"                            => Some(Tool::DockerCompose),
+
+        // Configuration files that come in many formats: .eslintrc, .eslintrc.json, .eslintrc.js...
+        _ if file_name.starts_with(".eslintrc")                     => Some(Tool::ESLint),
+        _ if file_name.starts_with(".prettierrc")
+            || file_name.starts_with("prettier.config.")            => Some(Tool::Prettier),
+        _ if file_name.starts_with("Dockerfile.")
+            || file_name.ends_with(".dockerfile")                   => Some(Tool::Docker),
+        _                                                           => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tooling::*;
+    use crate::objects::Tool;
+
+    #[test] fn test_path_tool() {
+        assert_eq!(path_tool(".github/workflows/ci.yml"), Some(Tool::GitHubActions));
+        assert_eq!(path_tool("docs/.github/workflows/ci.yml"), None);
+        assert_eq!(path_tool(".circleci/config.yml"), Some(Tool::CircleCI));
+        assert_eq!(path_tool("backend/pom.xml"), Some(Tool::Maven));
+        assert_eq!(path_tool("web/.eslintrc.json"), Some(Tool::ESLint));
+        assert_eq!(path_tool("docker/Dockerfile.dev"), Some(Tool::Docker));
+        assert_eq!(path_tool("src/main.rs"), None);
+    }
+}
@@ -76,8 +76,20 @@ quick_impl_countable!(Snapshot);
 quick_impl_countable!(Head);
 quick_impl_countable!(Change);
 quick_impl_countable!(Language);
+quick_impl_countable!(LanguageKind);
 quick_impl_countable!(Store);
 quick_impl_countable!(Tree);
+quick_impl_countable!(ForkNetwork);
+quick_impl_countable!(Fork);
+quick_impl_countable!(TeamStructure);
+quick_impl_countable!(Dependency);
+quick_impl_countable!(DetectedLicense);
+quick_impl_countable!(LineCounts);
+quick_impl_countable!(Diff);
+quick_impl_countable!(Tool);
+quick_impl_countable!(CommitCategory);
+quick_impl_countable!(ToolKind);
+quick_impl_countable!((Tool, i64));
 
 quick_impl_countable!(UserId);
 quick_impl_countable!(ProjectId);
@@ -88,6 +100,7 @@ quick_impl_countable!(SnapshotId);
 quick_impl_countable!((usize, ProjectId));
 quick_impl_countable!((CommitId, CommitId));
 quick_impl_countable!((i64, i64));
+quick_impl_countable!((String, usize));
 
 
 //quick_impl_countable!(ChangeTuple);
@@ -148,8 +161,15 @@ quick_impl_weighed!(PathId);
 quick_impl_weighed!(SnapshotId);
 // quick_impl_weighed!(Change);
 quick_impl_weighed!(Language);
+quick_impl_weighed!(LanguageKind);
 quick_impl_weighed!(Store);
 quick_impl_weighed!(Tree);
+quick_impl_weighed!(Fork);
+quick_impl_weighed!(TeamStructure);
+quick_impl_weighed!(Ecosystem);
+quick_impl_weighed!(Tool);
+quick_impl_weighed!(CommitCategory);
+quick_impl_weighed!(ToolKind);
 
 macro_rules! quick_impl_weighed_static_collection {
    ($t:ty, $e:ty) => {
@@ -261,4 +281,14 @@ quick_impl_weighed_by_fields!(Commit, id, parents, committer, author);
 quick_impl_weighed_by_fields!(User, id, email);
 quick_impl_weighed_by_fields!(Path, id, location);
 quick_impl_weighed_by_fields!(Snapshot, id, contents);
-quick_impl_weighed_by_fields!(Head, name, commit);
\ No newline at end of file
+quick_impl_weighed_by_fields!(Head, name, commit);
+quick_impl_weighed_by_fields!(ForkNetwork, upstream, forks);
+quick_impl_weighed_by_fields!(Dependency, name, version, ecosystem);
+quick_impl_weighed_by_fields!(DetectedLicense, spdx, confidence, path);
+quick_impl_weighed_by_fields!(LineCounts, code, comment, blank);
+quick_impl_weighed_by_fields!(Hunk, old_start, old_lines, new_start, new_lines, lines);
+quick_impl_weighed_by_fields!(Diff, hunks, added, removed, similarity);
+
+impl Weighed for DiffLine {
+    fn weigh_dynamic_component(&self) -> usize { self.text().weigh_dynamic_component() }
+}